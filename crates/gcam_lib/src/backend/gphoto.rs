@@ -0,0 +1,246 @@
+//! libgphoto2 backend
+
+use super::{
+  BackendCamera, CameraBackend, CameraDescriptor, CameraEvent, CameraFileInfo, CameraFilePath,
+};
+use crate::{
+  camera::{
+    info::{CameraAbilities, CameraInfo, StorageInfo},
+    settings::{display_radio_choice, CameraSettings, RadioChoice, StaticWidget},
+  },
+  error::{AppError, AppResult},
+};
+use gphoto2::{
+  camera::CameraEvent as GPhotoEvent,
+  widget::{ButtonWidget, DateWidget, RadioWidget, RangeWidget, TextWidget, ToggleWidget, Widget},
+  Camera, Context,
+};
+use std::{collections::BTreeMap, time::Duration};
+
+pub struct GPhotoBackend {
+  context: Context,
+}
+
+pub struct GPhotoCamera {
+  camera: Camera,
+  descriptor: CameraDescriptor,
+}
+
+impl GPhotoBackend {
+  pub fn new() -> AppResult<Self> {
+    Ok(Self { context: Context::new()? })
+  }
+}
+
+impl CameraBackend for GPhotoBackend {
+  fn list_cameras(&mut self) -> AppResult<Vec<CameraDescriptor>> {
+    Ok(
+      self
+        .context
+        .list_cameras()?
+        .map(|descriptor| CameraDescriptor { model: descriptor.model, port: descriptor.port })
+        .collect(),
+    )
+  }
+
+  fn open_camera(&mut self, descriptor: &CameraDescriptor) -> AppResult<Box<dyn BackendCamera>> {
+    let camera = self.context.get_camera(&gphoto2::list::CameraDescriptor {
+      model: descriptor.model.clone(),
+      port: descriptor.port.clone(),
+    })?;
+
+    Ok(Box::new(GPhotoCamera { camera, descriptor: descriptor.clone() }))
+  }
+}
+
+impl BackendCamera for GPhotoCamera {
+  fn info(&self) -> AppResult<CameraInfo> {
+    let abilities = self.camera.abilities();
+
+    Ok(CameraInfo {
+      abilities: CameraAbilities {
+        driver_status: abilities.driver_status(),
+        capture_image: abilities.camera_operations().capture_image(),
+        capture_preview: abilities.camera_operations().capture_preview(),
+        configure: abilities.camera_operations().configure(),
+        delete_files: abilities.file_operations().delete(),
+        file_preview: abilities.file_operations().preview(),
+      },
+      about: self.camera.about().ok(),
+      manual: self.camera.manual().ok(),
+      model: self.descriptor.model.clone(),
+      port: self.descriptor.port.clone(),
+      storages: self
+        .camera
+        .storages()?
+        .iter()
+        .map(|storage| StorageInfo {
+          label: storage.label().map(|label| label.to_string()),
+          base_directory: storage.base_directory().map(|dir| dir.to_string()),
+          description: storage.description().map(|description| description.to_string()),
+          storage_type: storage.storage_type(),
+          filesystem_type: storage.filesystem_type(),
+          access_type: storage.access_type(),
+          capacity_kb: storage.capacity_kb(),
+          free_kb: storage.free_kb(),
+        })
+        .collect(),
+      summary: self.camera.summary().ok(),
+    })
+  }
+
+  fn config(&self) -> AppResult<CameraSettings> {
+    Widget::Group(self.camera.config()?).try_into()
+  }
+
+  fn set_config(&mut self, setting: &CameraSettings) -> AppResult<()> {
+    let cam_widget: Widget = self.camera.config_key(&setting.name)?;
+
+    // TODO: Clean this code up using macros maybe?
+    let widget = match &setting.widget {
+      StaticWidget::Group { .. } => Err("Group settings can not be written")?,
+      StaticWidget::Text(text) => {
+        let text_widget = cam_widget.try_into::<TextWidget>()?;
+        text_widget.set_value(text)?;
+        Widget::Text(text_widget)
+      }
+      StaticWidget::Range { value, .. } => {
+        let range_widget = cam_widget.try_into::<RangeWidget>()?;
+        range_widget.set_value(*value);
+        Widget::Range(range_widget)
+      }
+      StaticWidget::Toggle { value, .. } => {
+        let toggle_widget = cam_widget.try_into::<ToggleWidget>()?;
+        toggle_widget.set_toggled(*value);
+        Widget::Toggle(toggle_widget)
+      }
+      StaticWidget::Radio { choice, choices } => {
+        let radio_widget = cam_widget.try_into::<RadioWidget>()?;
+        radio_widget.set_choice(display_radio_choice(choices, choice))?;
+        Widget::Radio(radio_widget)
+      }
+      StaticWidget::Button => {
+        let button = cam_widget.try_into::<ButtonWidget>()?;
+        button.press(&self.camera)?;
+        Widget::Button(button)
+      }
+      StaticWidget::Date { timestamp } => {
+        let time_widget = cam_widget.try_into::<DateWidget>()?;
+        time_widget.set_timestamp(*timestamp);
+        Widget::Date(time_widget)
+      }
+    };
+
+    Ok(self.camera.set_config(&widget)?)
+  }
+
+  fn capture_preview(&mut self) -> AppResult<Vec<u8>> {
+    Ok(self.camera.capture_preview()?.get_data()?.into())
+  }
+
+  fn capture_image(&mut self) -> AppResult<CameraFilePath> {
+    let path = self.camera.capture_image()?;
+
+    Ok(CameraFilePath::new(path.folder(), path.name()))
+  }
+
+  fn list_folders(&self, folder: &str) -> AppResult<Vec<String>> {
+    Ok(self.camera.fs().list_folders(folder)?.collect())
+  }
+
+  fn list_files(&self, folder: &str) -> AppResult<Vec<String>> {
+    Ok(self.camera.fs().list_files(folder)?.collect())
+  }
+
+  fn file_info(&self, path: &CameraFilePath) -> AppResult<CameraFileInfo> {
+    let info = self.camera.fs().file_info(&path.folder, &path.name)?;
+
+    Ok(
+      info
+        .file()
+        .map(|file| CameraFileInfo {
+          size: file.size(),
+          mime_type: file.mime_type().map(|mime_type| mime_type.to_string()),
+          mtime: file.mtime(),
+        })
+        .unwrap_or_default(),
+    )
+  }
+
+  fn download(&self, path: &CameraFilePath) -> AppResult<Vec<u8>> {
+    Ok(self.camera.fs().download(&path.folder, &path.name)?.get_data()?.into())
+  }
+
+  fn download_thumbnail(&self, path: &CameraFilePath) -> AppResult<Vec<u8>> {
+    Ok(self.camera.fs().download_preview(&path.folder, &path.name)?.get_data()?.into())
+  }
+
+  fn delete_file(&mut self, path: &CameraFilePath) -> AppResult<()> {
+    Ok(self.camera.fs().delete_file(&path.folder, &path.name)?)
+  }
+
+  fn wait_event(&mut self, timeout: Duration) -> AppResult<Option<CameraEvent>> {
+    let path =
+      |path: gphoto2::file::CameraFilePath| CameraFilePath::new(path.folder(), path.name());
+
+    Ok(match self.camera.wait_event(timeout)? {
+      GPhotoEvent::Timeout => None,
+      GPhotoEvent::NewFile(file) => Some(CameraEvent::NewFile(path(file))),
+      GPhotoEvent::FileChanged(file) => Some(CameraEvent::FileChanged(path(file))),
+      GPhotoEvent::NewFolder(folder) => Some(CameraEvent::NewFolder(path(folder))),
+      GPhotoEvent::CaptureComplete => Some(CameraEvent::CaptureComplete),
+      GPhotoEvent::Unknown(event) => Some(CameraEvent::Unknown(event)),
+    })
+  }
+}
+
+impl TryFrom<Widget> for CameraSettings {
+  type Error = AppError;
+
+  fn try_from(widget: Widget) -> Result<Self, Self::Error> {
+    Ok(CameraSettings {
+      id: widget.id(),
+      name: widget.name(),
+      label: widget.label(),
+      readonly: widget.readonly(),
+      widget: match widget {
+        Widget::Group(group_widget) => {
+          let children: BTreeMap<i32, CameraSettings> = group_widget
+            .children_iter()
+            .map(|child| child.try_into::<CameraSettings>().map(|res| (res.id, res)))
+            .collect::<AppResult<_>>()?;
+
+          StaticWidget::Group {
+            id_by_names: children.iter().map(|(&id, child)| (child.name.clone(), id)).collect(),
+            children,
+          }
+        }
+        Widget::Text(text_widget) => StaticWidget::Text(text_widget.value()),
+        Widget::Range(range_widget) => {
+          let (range, step) = range_widget.range_and_step();
+
+          StaticWidget::Range { value: range_widget.value(), range, step }
+        }
+        Widget::Toggle(toggle_widget) => match toggle_widget.toggled() {
+          None => StaticWidget::Toggle { undefined: true, value: false },
+          Some(v) => StaticWidget::Toggle { undefined: false, value: v },
+        },
+        Widget::Radio(radio_widget) => {
+          let choices: Vec<_> = radio_widget.choices_iter().collect();
+          let current_choice = radio_widget.choice();
+
+          StaticWidget::Radio {
+            choice: choices
+              .iter()
+              .position(|choice| choice == &current_choice)
+              .map(RadioChoice::Indexed)
+              .unwrap_or(RadioChoice::Other(current_choice)),
+            choices,
+          }
+        }
+        Widget::Date(date_widget) => StaticWidget::Date { timestamp: date_widget.timestamp() },
+        Widget::Button(_) => StaticWidget::Button,
+      },
+    })
+  }
+}
@@ -0,0 +1,94 @@
+//! Camera backends
+//!
+//! Everything that talks to a camera goes through [`CameraBackend`] and [`BackendCamera`], so the
+//! application can run against real hardware ([`gphoto::GPhotoBackend`]) or against a fake camera
+//! ([`simulated::SimulatedBackend`]).
+
+pub mod gphoto;
+pub mod simulated;
+
+use crate::{
+  camera::{info::CameraInfo, settings::CameraSettings},
+  error::AppResult,
+};
+use std::time::Duration;
+
+/// Identifies a camera that can be opened by a backend
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CameraDescriptor {
+  pub model: String,
+  pub port: String,
+}
+
+/// Location of a file on the camera
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CameraFilePath {
+  pub folder: String,
+  pub name: String,
+}
+
+/// Metadata of a file on the camera
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CameraFileInfo {
+  pub size: Option<u64>,
+  pub mime_type: Option<String>,
+  /// Modification time as a unix timestamp
+  pub mtime: Option<i64>,
+}
+
+/// Something that happened on the camera, reported by [`BackendCamera::wait_event`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CameraEvent {
+  /// A picture was taken, eg. by pressing the shutter button or releasing bulb
+  NewFile(CameraFilePath),
+  FileChanged(CameraFilePath),
+  NewFolder(CameraFilePath),
+  CaptureComplete,
+  /// Events the backend does not understand, usually a setting changed on the camera
+  Unknown(String),
+}
+
+pub trait CameraBackend {
+  /// Lists all cameras that are currently connected
+  fn list_cameras(&mut self) -> AppResult<Vec<CameraDescriptor>>;
+
+  fn open_camera(&mut self, descriptor: &CameraDescriptor) -> AppResult<Box<dyn BackendCamera>>;
+}
+
+/// An opened camera
+pub trait BackendCamera {
+  fn info(&self) -> AppResult<CameraInfo>;
+
+  /// Reads the whole configuration tree
+  fn config(&self) -> AppResult<CameraSettings>;
+
+  /// Writes a single (non group) setting to the camera
+  fn set_config(&mut self, setting: &CameraSettings) -> AppResult<()>;
+
+  /// Captures a live view frame, returns the encoded image data
+  fn capture_preview(&mut self) -> AppResult<Vec<u8>>;
+
+  /// Takes a picture, the image stays on the camera storage
+  fn capture_image(&mut self) -> AppResult<CameraFilePath>;
+
+  fn list_folders(&self, folder: &str) -> AppResult<Vec<String>>;
+
+  fn list_files(&self, folder: &str) -> AppResult<Vec<String>>;
+
+  fn file_info(&self, path: &CameraFilePath) -> AppResult<CameraFileInfo>;
+
+  fn download(&self, path: &CameraFilePath) -> AppResult<Vec<u8>>;
+
+  fn download_thumbnail(&self, path: &CameraFilePath) -> AppResult<Vec<u8>>;
+
+  fn delete_file(&mut self, path: &CameraFilePath) -> AppResult<()>;
+
+  /// Waits up to `timeout` for an event, returns `None` if nothing happened
+  fn wait_event(&mut self, timeout: Duration) -> AppResult<Option<CameraEvent>>;
+}
+
+impl CameraFilePath {
+  pub fn new(folder: impl Into<String>, name: impl Into<String>) -> Self {
+    Self { folder: folder.into(), name: name.into() }
+  }
+}
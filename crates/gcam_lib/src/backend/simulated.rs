@@ -0,0 +1,731 @@
+//! Simulated camera, used to develop and test the application without a physical camera
+//!
+//! The simulated camera renders synthetic live view frames, exposes a settings tree modeled after
+//! a Canon EOS body and keeps its pictures in an in-memory storage.
+
+use super::{
+  BackendCamera, CameraBackend, CameraDescriptor, CameraEvent, CameraFileInfo, CameraFilePath,
+};
+use crate::{
+  camera::{
+    bracketing::BracketSetting,
+    info::{CameraAbilities, CameraInfo, StorageInfo},
+    settings::{CameraSettings, RadioChoice, StaticWidget},
+  },
+  error::AppResult,
+};
+use gphoto2::{
+  abilities::CameraDriverStatus,
+  filesys::{AccessType, FilesystemType, StorageType},
+};
+use image::{codecs::jpeg::JpegEncoder, ColorType};
+use std::{
+  collections::{BTreeMap, VecDeque},
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub const SIMULATED_MODEL: &str = "GCam Simulated Camera";
+pub const SIMULATED_PORT: &str = "sim:";
+
+const STORAGE_ROOT: &str = "/store_00010001";
+const CAPTURE_FOLDER: &str = "/store_00010001/DCIM/100GCAM";
+const STORAGE_CAPACITY_KB: usize = 32 * 1024 * 1024;
+
+const PREVIEW_SIZE: (u32, u32) = (640, 424);
+const CAPTURE_SIZE: (u32, u32) = (1200, 800);
+const THUMBNAIL_SIZE: u32 = 160;
+const JPEG_QUALITY: u8 = 85;
+
+const INITIAL_PICTURES: u32 = 3;
+
+const SHUTTER_SPEEDS: &[&str] = &[
+  "bulb", "30", "25", "20", "15", "13", "10", "8", "6", "5", "4", "3.2", "2.5", "2", "1.6", "1.3",
+  "1", "0.8", "0.6", "0.5", "0.4", "0.3", "1/4", "1/5", "1/6", "1/8", "1/10", "1/13", "1/15",
+  "1/20", "1/25", "1/30", "1/40", "1/50", "1/60", "1/80", "1/100", "1/125", "1/160", "1/200",
+  "1/250", "1/320", "1/400", "1/500", "1/640", "1/800", "1/1000", "1/1250", "1/1600", "1/2000",
+  "1/2500", "1/3200", "1/4000",
+];
+const APERTURES: &[&str] = &[
+  "2.8", "3.2", "3.5", "4", "4.5", "5", "5.6", "6.3", "7.1", "8", "9", "10", "11", "13", "14",
+  "16", "18", "20", "22",
+];
+const ISOS: &[&str] = &[
+  "Auto", "100", "125", "160", "200", "250", "320", "400", "500", "640", "800", "1000", "1250",
+  "1600", "2000", "2500", "3200", "4000", "5000", "6400",
+];
+const EXPOSURE_COMPENSATIONS: &[&str] = &[
+  "-3", "-2.6", "-2.3", "-2", "-1.6", "-1.3", "-1", "-0.6", "-0.3", "0", "0.3", "0.6", "1", "1.3",
+  "1.6", "2", "2.3", "2.6", "3",
+];
+const EXPOSURE_MODES: &[&str] = &["Manual", "AV", "TV", "P", "Bulb"];
+
+const DEFAULT_SHUTTER_SPEED: &str = "1/125";
+const DEFAULT_APERTURE: &str = "5.6";
+const DEFAULT_ISO: &str = "400";
+
+#[derive(Default)]
+pub struct SimulatedBackend;
+
+pub struct SimulatedCamera {
+  descriptor: CameraDescriptor,
+  started: Instant,
+  settings: CameraSettings,
+  /// Files by folder, every folder of the storage has an entry
+  folders: BTreeMap<String, BTreeMap<String, SimulatedFile>>,
+  next_picture: u32,
+  /// Events waiting to be returned by `wait_event`
+  events: VecDeque<CameraEvent>,
+  /// When the shutter was opened by a remote release in bulb mode
+  bulb_opened: Option<Instant>,
+}
+
+struct SimulatedFile {
+  data: Vec<u8>,
+  mtime: i64,
+}
+
+/// Assigns ids to the settings in the order they are created, like libgphoto2 does
+#[derive(Default)]
+struct SettingsBuilder {
+  next_id: i32,
+}
+
+impl SimulatedBackend {
+  pub fn descriptor() -> CameraDescriptor {
+    CameraDescriptor { model: SIMULATED_MODEL.to_string(), port: SIMULATED_PORT.to_string() }
+  }
+}
+
+impl CameraBackend for SimulatedBackend {
+  fn list_cameras(&mut self) -> AppResult<Vec<CameraDescriptor>> {
+    Ok(vec![Self::descriptor()])
+  }
+
+  fn open_camera(&mut self, descriptor: &CameraDescriptor) -> AppResult<Box<dyn BackendCamera>> {
+    if descriptor.port != SIMULATED_PORT {
+      Err(format!("There is no simulated camera on port {}", descriptor.port))?
+    }
+
+    Ok(Box::new(SimulatedCamera::new(descriptor.clone())?))
+  }
+}
+
+impl SimulatedCamera {
+  pub fn new(descriptor: CameraDescriptor) -> AppResult<Self> {
+    let mut camera = Self {
+      descriptor,
+      started: Instant::now(),
+      settings: default_settings(),
+      folders: Default::default(),
+      next_picture: 1,
+      events: Default::default(),
+      bulb_opened: None,
+    };
+
+    camera.add_folder(CAPTURE_FOLDER);
+    for _ in 0..INITIAL_PICTURES {
+      camera.store_picture(None)?;
+    }
+
+    Ok(camera)
+  }
+
+  fn add_folder(&mut self, folder: &str) {
+    let mut path = folder;
+
+    while path != "/" {
+      self.folders.entry(path.to_string()).or_default();
+      path = parent_folder(path);
+    }
+  }
+
+  fn file(&self, path: &CameraFilePath) -> AppResult<&SimulatedFile> {
+    Ok(
+      self
+        .folders
+        .get(&path.folder)
+        .and_then(|files| files.get(&path.name))
+        .ok_or_else(|| format!("File {}/{} does not exist", path.folder, path.name))?,
+    )
+  }
+
+  fn radio_index(&self, name: &str) -> Option<usize> {
+    match self.settings.find(name).map(|setting| &setting.widget) {
+      Some(StaticWidget::Radio { choice: RadioChoice::Indexed(index), .. }) => Some(*index),
+      _ => None,
+    }
+  }
+
+  /// Brightness multiplier of the current exposure settings compared to the default exposure,
+  /// `exposure_time` overrides the shutter speed (used for bulb exposures)
+  fn exposure_gain(&self, exposure_time: Option<f32>) -> f32 {
+    let stops = |name: &str, choices: &[&str], default: &str| {
+      let default = choices.iter().position(|choice| *choice == default).unwrap_or_default();
+      self.radio_index(name).map(|index| (index as f32 - default as f32) / 3.).unwrap_or_default()
+    };
+
+    let shutter = BracketSetting::ShutterSpeed;
+    let shutter_stops = exposure_time
+      .map(f32::log2)
+      .or_else(|| {
+        self.radio_index("shutterspeed").and_then(|index| shutter.stops(SHUTTER_SPEEDS[index]))
+      })
+      .zip(shutter.stops(DEFAULT_SHUTTER_SPEED))
+      .map(|(stops, default)| stops - default)
+      .unwrap_or_default();
+
+    let exposure_compensation = self
+      .radio_index("exposurecompensation")
+      .and_then(|index| EXPOSURE_COMPENSATIONS[index].parse::<f32>().ok())
+      .unwrap_or_default();
+
+    2f32.powf(
+      stops("iso", ISOS, DEFAULT_ISO) + shutter_stops
+        - stops("aperture", APERTURES, DEFAULT_APERTURE)
+        + exposure_compensation,
+    )
+  }
+
+  /// Renders the simulated scene: a sky gradient, a moving sun and color bars
+  fn render(&self, (width, height): (u32, u32), gain: f32) -> Vec<u8> {
+    let time = self.started.elapsed().as_secs_f32();
+
+    let sun_x = width as f32 * (0.5 + 0.35 * (time * 0.8).sin());
+    let sun_y = height as f32 * 0.35;
+    let sun_radius = height as f32 * 0.12;
+
+    let bars: [[f32; 3]; 7] = [
+      [191., 191., 191.],
+      [191., 191., 0.],
+      [0., 191., 191.],
+      [0., 191., 0.],
+      [191., 0., 191.],
+      [191., 0., 0.],
+      [0., 0., 191.],
+    ];
+
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height {
+      let fy = y as f32 / height as f32;
+
+      for x in 0..width {
+        let fx = x as f32 / width as f32;
+        let (dx, dy) = (x as f32 - sun_x, y as f32 - sun_y);
+
+        let color = if fy > 0.7 {
+          bars[((fx * bars.len() as f32) as usize).min(bars.len() - 1)]
+        } else if dx * dx + dy * dy < sun_radius * sun_radius {
+          [250., 220., 120.]
+        } else {
+          [70. + 80. * fy, 110. + 70. * fy, 200. - 40. * fy]
+        };
+
+        data.extend(color.map(|channel| (channel * gain).clamp(0., 255.) as u8));
+      }
+    }
+
+    data
+  }
+
+  /// Renders a picture and stores it in the capture folder
+  fn store_picture(&mut self, exposure_time: Option<f32>) -> AppResult<CameraFilePath> {
+    let data =
+      encode_jpeg(&self.render(CAPTURE_SIZE, self.exposure_gain(exposure_time)), CAPTURE_SIZE)?;
+    let path = CameraFilePath::new(CAPTURE_FOLDER, format!("IMG_{:04}.JPG", self.next_picture));
+
+    self.next_picture += 1;
+    self.add_folder(&path.folder);
+    self
+      .folders
+      .entry(path.folder.clone())
+      .or_default()
+      .insert(path.name.clone(), SimulatedFile { data, mtime: unix_now() });
+
+    Ok(path)
+  }
+
+  fn bulb_mode(&self) -> bool {
+    self.radio_index("shutterspeed").map(|index| SHUTTER_SPEEDS[index]) == Some("bulb")
+      || self.radio_index("autoexposuremode").map(|index| EXPOSURE_MODES[index]) == Some("Bulb")
+  }
+
+  /// Presses or releases the shutter button, like `eosremoterelease` on Canon bodies
+  fn remote_release(&mut self) -> AppResult<()> {
+    let release = match self.settings.find("eosremoterelease").map(|setting| &setting.widget) {
+      Some(StaticWidget::Radio { choices, choice: RadioChoice::Indexed(index) }) => {
+        choices[*index].clone()
+      }
+      _ => return Ok(()),
+    };
+
+    let exposure_time = match release.as_str() {
+      "Press Full" if self.bulb_mode() => {
+        self.bulb_opened.get_or_insert_with(Instant::now);
+        return Ok(());
+      }
+      "Press Full" => None,
+      "Release Full" => match self.bulb_opened.take() {
+        Some(opened) => Some(opened.elapsed().as_secs_f32()),
+        None => return Ok(()),
+      },
+      _ => return Ok(()),
+    };
+
+    let path = self.store_picture(exposure_time)?;
+    self.events.push_back(CameraEvent::NewFile(path));
+    self.events.push_back(CameraEvent::CaptureComplete);
+
+    Ok(())
+  }
+
+  fn update_exposure_locks(&mut self) {
+    let mode = match self.radio_index("autoexposuremode") {
+      Some(index) => EXPOSURE_MODES[index],
+      None => return,
+    };
+
+    if let Some(shutterspeed) = self.settings.find_mut("shutterspeed") {
+      shutterspeed.readonly = matches!(mode, "AV" | "P" | "Bulb");
+    }
+
+    if let Some(aperture) = self.settings.find_mut("aperture") {
+      aperture.readonly = matches!(mode, "TV" | "P");
+    }
+  }
+}
+
+impl BackendCamera for SimulatedCamera {
+  fn info(&self) -> AppResult<CameraInfo> {
+    let used_kb = self
+      .folders
+      .values()
+      .flat_map(|files| files.values())
+      .map(|file| file.data.len())
+      .sum::<usize>()
+      / 1024;
+
+    Ok(CameraInfo {
+      model: self.descriptor.model.clone(),
+      port: self.descriptor.port.clone(),
+      manual: None,
+      summary: Some(format!("Manufacturer: GCam\nModel: {}\nVersion: 1.0.0", SIMULATED_MODEL)),
+      about: Some("Simulated camera driver, it does not talk to any hardware".to_string()),
+      abilities: CameraAbilities {
+        driver_status: CameraDriverStatus::Testing,
+        capture_image: true,
+        capture_preview: true,
+        configure: true,
+        delete_files: true,
+        file_preview: true,
+      },
+      storages: vec![StorageInfo {
+        label: Some("SD".to_string()),
+        base_directory: Some(STORAGE_ROOT.to_string()),
+        description: Some("Simulated memory card".to_string()),
+        storage_type: Some(StorageType::RemovableRam),
+        filesystem_type: Some(FilesystemType::Dcf),
+        access_type: Some(AccessType::Rw),
+        capacity_kb: Some(STORAGE_CAPACITY_KB),
+        free_kb: Some(STORAGE_CAPACITY_KB.saturating_sub(used_kb)),
+      }],
+    })
+  }
+
+  fn config(&self) -> AppResult<CameraSettings> {
+    Ok(self.settings.clone())
+  }
+
+  fn set_config(&mut self, setting: &CameraSettings) -> AppResult<()> {
+    let current = self
+      .settings
+      .find_mut(&setting.name)
+      .ok_or_else(|| format!("The camera has no setting named {}", setting.name))?;
+
+    if current.readonly {
+      Err(format!("The setting {} is read only", setting.name))?
+    }
+
+    match (&mut current.widget, &setting.widget) {
+      (StaticWidget::Group { .. }, _) => Err("Group settings can not be written")?,
+      (StaticWidget::Button, StaticWidget::Button) => {}
+      (
+        StaticWidget::Radio { choices, choice },
+        StaticWidget::Radio { choices: new_choices, choice: new_choice },
+      ) => {
+        let value = match new_choice {
+          RadioChoice::Indexed(index) => new_choices.get(*index),
+          RadioChoice::Other(value) => Some(value),
+        };
+
+        *choice = value
+          .and_then(|value| choices.iter().position(|choice| choice == value))
+          .map(RadioChoice::Indexed)
+          .ok_or_else(|| format!("Invalid choice for {}", setting.name))?;
+      }
+      (StaticWidget::Range { value, range, .. }, StaticWidget::Range { value: new_value, .. }) => {
+        *value = new_value.clamp(*range.start(), *range.end());
+      }
+      (widget, new_widget)
+        if std::mem::discriminant(widget) == std::mem::discriminant(new_widget) =>
+      {
+        *widget = new_widget.clone();
+      }
+      _ => Err(format!("Invalid widget type for {}", setting.name))?,
+    }
+
+    self.update_exposure_locks();
+
+    if setting.name == "eosremoterelease" {
+      self.remote_release()?;
+    }
+
+    Ok(())
+  }
+
+  fn capture_preview(&mut self) -> AppResult<Vec<u8>> {
+    if self.bulb_opened.is_some() {
+      Err("Live view is not available while the shutter is open")?
+    }
+
+    encode_jpeg(&self.render(PREVIEW_SIZE, self.exposure_gain(None)), PREVIEW_SIZE)
+  }
+
+  fn capture_image(&mut self) -> AppResult<CameraFilePath> {
+    if self.bulb_mode() {
+      Err("Pictures in bulb mode have to be taken with a remote release")?
+    }
+
+    self.store_picture(None)
+  }
+
+  fn list_folders(&self, folder: &str) -> AppResult<Vec<String>> {
+    Ok(
+      self
+        .folders
+        .keys()
+        .filter(|path| parent_folder(path) == folder)
+        .filter_map(|path| path.rsplit('/').next().map(ToString::to_string))
+        .collect(),
+    )
+  }
+
+  fn list_files(&self, folder: &str) -> AppResult<Vec<String>> {
+    Ok(self.folders.get(folder).map(|files| files.keys().cloned().collect()).unwrap_or_default())
+  }
+
+  fn file_info(&self, path: &CameraFilePath) -> AppResult<CameraFileInfo> {
+    let file = self.file(path)?;
+
+    Ok(CameraFileInfo {
+      size: Some(file.data.len() as u64),
+      mime_type: Some("image/jpeg".to_string()),
+      mtime: Some(file.mtime),
+    })
+  }
+
+  fn download(&self, path: &CameraFilePath) -> AppResult<Vec<u8>> {
+    Ok(self.file(path)?.data.clone())
+  }
+
+  fn download_thumbnail(&self, path: &CameraFilePath) -> AppResult<Vec<u8>> {
+    let thumbnail =
+      image::load_from_memory(&self.file(path)?.data)?.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+
+    encode_jpeg(thumbnail.to_rgb8().as_raw(), (thumbnail.width(), thumbnail.height()))
+  }
+
+  fn delete_file(&mut self, path: &CameraFilePath) -> AppResult<()> {
+    self
+      .folders
+      .get_mut(&path.folder)
+      .and_then(|files| files.remove(&path.name))
+      .ok_or_else(|| format!("File {}/{} does not exist", path.folder, path.name))?;
+
+    Ok(())
+  }
+
+  fn wait_event(&mut self, timeout: Duration) -> AppResult<Option<CameraEvent>> {
+    if self.events.is_empty() {
+      std::thread::sleep(timeout);
+    }
+
+    Ok(self.events.pop_front())
+  }
+}
+
+impl SettingsBuilder {
+  fn setting(&mut self, name: &str, label: &str, widget: StaticWidget) -> CameraSettings {
+    self.next_id += 1;
+
+    CameraSettings {
+      id: self.next_id,
+      name: name.to_string(),
+      label: label.to_string(),
+      widget,
+      readonly: false,
+    }
+  }
+
+  fn group(&mut self, name: &str, label: &str, children: Vec<CameraSettings>) -> CameraSettings {
+    let children: BTreeMap<_, _> = children.into_iter().map(|child| (child.id, child)).collect();
+
+    self.setting(
+      name,
+      label,
+      StaticWidget::Group {
+        id_by_names: children.iter().map(|(&id, child)| (child.name.clone(), id)).collect(),
+        children,
+      },
+    )
+  }
+
+  fn radio(&mut self, name: &str, label: &str, choices: &[&str], current: &str) -> CameraSettings {
+    let choices: Vec<String> = choices.iter().map(ToString::to_string).collect();
+
+    self.setting(
+      name,
+      label,
+      StaticWidget::Radio {
+        choice: choices
+          .iter()
+          .position(|choice| choice == current)
+          .map(RadioChoice::Indexed)
+          .unwrap_or_else(|| RadioChoice::Other(current.to_string())),
+        choices,
+      },
+    )
+  }
+
+  fn text(&mut self, name: &str, label: &str, value: &str) -> CameraSettings {
+    self.setting(name, label, StaticWidget::Text(value.to_string()))
+  }
+
+  fn status(&mut self, name: &str, label: &str, value: &str) -> CameraSettings {
+    CameraSettings { readonly: true, ..self.text(name, label, value) }
+  }
+
+  fn toggle(&mut self, name: &str, label: &str, value: bool) -> CameraSettings {
+    self.setting(name, label, StaticWidget::Toggle { undefined: false, value })
+  }
+}
+
+fn default_settings() -> CameraSettings {
+  let mut builder = SettingsBuilder::default();
+  let b = &mut builder;
+
+  let actions = vec![
+    b.toggle("autofocusdrive", "Drive Canon DSLR Autofocus", false),
+    b.setting(
+      "manualfocusdrive",
+      "Drive Canon DSLR Manual focus",
+      StaticWidget::Range { value: 0., range: -32767f32..=32767f32, step: 1. },
+    ),
+    b.toggle("cancelautofocus", "Cancel Canon DSLR Autofocus", false),
+    b.toggle("viewfinder", "Canon EOS Viewfinder", false),
+    b.radio(
+      "eosremoterelease",
+      "Canon EOS Remote Release",
+      &[
+        "None",
+        "Press Half",
+        "Press Full",
+        "Release Half",
+        "Release Full",
+        "Immediate",
+        "Press 1",
+        "Press 2",
+        "Press 3",
+        "Release 1",
+        "Release 2",
+        "Release 3",
+      ],
+      "None",
+    ),
+  ];
+  let actions = b.group("actions", "Camera Actions", actions);
+
+  let settings = vec![
+    b.setting(
+      "datetime",
+      "Camera Date and Time",
+      StaticWidget::Date { timestamp: unix_now() as i32 },
+    ),
+    b.text("artist", "Artist", ""),
+    b.text("copyright", "Copyright", ""),
+    b.text("ownername", "Owner Name", ""),
+    b.radio("capturetarget", "Capture Target", &["Internal RAM", "Memory card"], "Memory card"),
+    b.radio(
+      "reviewtime",
+      "Quick Review Time",
+      &["None", "2 seconds", "4 seconds", "8 seconds", "Hold"],
+      "2 seconds",
+    ),
+    b.radio("liveviewsize", "Live View Size", &["Large", "Medium", "Small"], "Large"),
+  ];
+  let settings = b.group("settings", "Camera Settings", settings);
+
+  let status = vec![
+    b.status("serialnumber", "Serial Number", "0123456789"),
+    b.status("manufacturer", "Camera Manufacturer", "GCam"),
+    b.status("cameramodel", "Camera Model", SIMULATED_MODEL),
+    b.status("deviceversion", "Device Version", "1.0.0"),
+    b.status("batterylevel", "Battery Level", "100%"),
+    b.status("lensname", "Lens Name", "Simulated 24-70mm f/2.8"),
+    b.status("availableshots", "Available Shots", "999"),
+  ];
+  let status = b.group("status", "Camera Status Information", status);
+
+  let image_settings = vec![
+    b.radio(
+      "imageformat",
+      "Image Format",
+      &["Large Fine JPEG", "Large Normal JPEG", "Medium Fine JPEG", "Small Fine JPEG", "RAW"],
+      "Large Fine JPEG",
+    ),
+    b.radio("iso", "ISO Speed", ISOS, DEFAULT_ISO),
+    b.radio(
+      "whitebalance",
+      "WhiteBalance",
+      &["Auto", "Daylight", "Shadow", "Cloudy", "Tungsten", "Fluorescent", "Flash", "Manual"],
+      "Auto",
+    ),
+    b.radio("colorspace", "Color Space", &["sRGB", "AdobeRGB"], "sRGB"),
+  ];
+  let image_settings = b.group("imgsettings", "Image Settings", image_settings);
+
+  let capture_settings = vec![
+    b.radio("autoexposuremode", "Auto Exposure Mode", EXPOSURE_MODES, "Manual"),
+    b.radio("shutterspeed", "Shutter Speed", SHUTTER_SPEEDS, DEFAULT_SHUTTER_SPEED),
+    b.radio("aperture", "Aperture", APERTURES, DEFAULT_APERTURE),
+    b.radio("exposurecompensation", "Exposure Compensation", EXPOSURE_COMPENSATIONS, "0"),
+    b.radio("focusmode", "Focus Mode", &["One Shot", "AI Servo", "AI Focus", "Manual"], "One Shot"),
+    b.radio(
+      "drivemode",
+      "Drive Mode",
+      &["Single", "Continuous", "Timer 10 sec", "Timer 2 sec"],
+      "Single",
+    ),
+    b.radio(
+      "meteringmode",
+      "Metering Mode",
+      &["Evaluative", "Partial", "Spot", "Center-weighted average"],
+      "Evaluative",
+    ),
+  ];
+  let capture_settings = b.group("capturesettings", "Capture Settings", capture_settings);
+
+  b.group(
+    "main",
+    "Camera and Driver Configuration",
+    vec![actions, settings, status, image_settings, capture_settings],
+  )
+}
+
+fn encode_jpeg(data: &[u8], (width, height): (u32, u32)) -> AppResult<Vec<u8>> {
+  let mut buffer = Vec::new();
+  JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY).encode(
+    data,
+    width,
+    height,
+    ColorType::Rgb8,
+  )?;
+
+  Ok(buffer)
+}
+
+fn parent_folder(path: &str) -> &str {
+  match path.rsplit_once('/') {
+    Some(("", _)) | None => "/",
+    Some((parent, _)) => parent,
+  }
+}
+
+fn unix_now() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() as i64).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::camera::settings::display_radio_choice;
+
+  fn camera() -> SimulatedCamera {
+    SimulatedCamera::new(SimulatedBackend::descriptor()).unwrap()
+  }
+
+  fn set(camera: &mut SimulatedCamera, name: &str, value: &str) -> AppResult<()> {
+    let mut setting = camera.config()?.find(name).unwrap().clone();
+
+    setting.widget = match setting.widget {
+      StaticWidget::Radio { choices, .. } => {
+        let index = choices.iter().position(|choice| choice == value).unwrap();
+        StaticWidget::Radio { choices, choice: RadioChoice::Indexed(index) }
+      }
+      StaticWidget::Text(_) => StaticWidget::Text(value.into()),
+      widget => panic!("Unexpected widget {:?}", widget),
+    };
+
+    camera.set_config(&setting)
+  }
+
+  fn value(camera: &SimulatedCamera, name: &str) -> String {
+    match camera.config().unwrap().find(name).unwrap().widget.clone() {
+      StaticWidget::Radio { choices, choice } => display_radio_choice(&choices, &choice).into(),
+      StaticWidget::Text(text) => text,
+      widget => panic!("Unexpected widget {:?}", widget),
+    }
+  }
+
+  #[test]
+  fn capture_stores_a_picture() {
+    let mut camera = camera();
+    let files_before = camera.list_files(CAPTURE_FOLDER).unwrap().len();
+
+    let path = camera.capture_image().unwrap();
+
+    assert_eq!(path.folder, CAPTURE_FOLDER);
+    assert!(camera.list_files(CAPTURE_FOLDER).unwrap().contains(&path.name));
+    assert_eq!(camera.list_files(CAPTURE_FOLDER).unwrap().len(), files_before + 1);
+
+    let data = camera.download(&path).unwrap();
+    assert_eq!(camera.file_info(&path).unwrap().size, Some(data.len() as u64));
+    let image = image::load_from_memory(&data).unwrap();
+    assert_eq!((image.width(), image.height()), CAPTURE_SIZE);
+  }
+
+  #[test]
+  fn config_round_trips() {
+    let mut camera = camera();
+
+    set(&mut camera, "iso", "800").unwrap();
+    set(&mut camera, "ownername", "GCam").unwrap();
+
+    assert_eq!(value(&camera, "iso"), "800");
+    assert_eq!(value(&camera, "ownername"), "GCam");
+  }
+
+  #[test]
+  fn exposure_mode_locks_settings() {
+    let mut camera = camera();
+
+    set(&mut camera, "autoexposuremode", "AV").unwrap();
+
+    assert!(camera.config().unwrap().find("shutterspeed").unwrap().readonly);
+    assert!(set(&mut camera, "shutterspeed", "1/250").is_err());
+  }
+
+  #[test]
+  fn remote_release_queues_events() {
+    let mut camera = camera();
+
+    set(&mut camera, "eosremoterelease", "Press Full").unwrap();
+
+    let path = match camera.wait_event(Duration::ZERO).unwrap() {
+      Some(CameraEvent::NewFile(path)) => path,
+      event => panic!("Expected a new file, got {:?}", event),
+    };
+    assert!(camera.download(&path).is_ok());
+    assert_eq!(camera.wait_event(Duration::ZERO).unwrap(), Some(CameraEvent::CaptureComplete));
+    assert_eq!(camera.wait_event(Duration::ZERO).unwrap(), None);
+  }
+}
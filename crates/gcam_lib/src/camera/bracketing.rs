@@ -0,0 +1,222 @@
+//! Exposure bracketing using the choices of the camera's exposure settings
+//!
+//! Every choice is converted to its exposure value in stops (higher is brighter), so the order of
+//! the choices does not matter and irregular lists (eg. ISO lists with a gap) still work.
+
+use super::settings::{display_radio_choice, CameraSettings, RadioChoice, StaticWidget};
+use crate::error::AppResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketSetting {
+  ShutterSpeed,
+  Aperture,
+  Iso,
+}
+
+/// Choices to capture for a bracket
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketPlan {
+  /// Stops between two adjacent choices of the setting (eg. 1/3 or 1/2)
+  pub step: f32,
+  /// Choice that was selected before bracketing, it has to be restored afterwards
+  pub original: usize,
+  /// Choice indices in capture order, with their exposure offset in stops
+  pub frames: Vec<(usize, f32)>,
+}
+
+impl BracketSetting {
+  pub const ALL: [BracketSetting; 3] = [Self::ShutterSpeed, Self::Aperture, Self::Iso];
+
+  /// Name of the camera setting
+  pub fn setting_name(&self) -> &'static str {
+    match self {
+      Self::ShutterSpeed => "shutterspeed",
+      Self::Aperture => "aperture",
+      Self::Iso => "iso",
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::ShutterSpeed => "Shutter speed",
+      Self::Aperture => "Aperture",
+      Self::Iso => "ISO",
+    }
+  }
+
+  /// Exposure of a choice in stops, `None` for choices like "Auto" or "bulb"
+  pub fn stops(&self, choice: &str) -> Option<f32> {
+    let value = match self {
+      Self::ShutterSpeed => match choice.split_once('/') {
+        Some((numerator, denominator)) => {
+          numerator.trim().parse::<f32>().ok()? / denominator.trim().parse::<f32>().ok()?
+        }
+        None => choice.trim().trim_end_matches('s').parse().ok()?,
+      },
+      Self::Aperture => choice.trim().trim_start_matches("f/").parse().ok()?,
+      Self::Iso => choice.trim().parse().ok()?,
+    };
+
+    if value <= 0. || !value.is_finite() {
+      return None;
+    }
+
+    Some(match self {
+      Self::ShutterSpeed | Self::Iso => value.log2(),
+      // The amount of light is proportional to the inverse of the squared f-number
+      Self::Aperture => -2. * value.log2(),
+    })
+  }
+}
+
+/// Stops between adjacent choices, rounded to a full, half or third stop
+pub fn choice_step(choice_stops: &[f32]) -> Option<f32> {
+  let mut differences: Vec<f32> =
+    choice_stops.windows(2).map(|pair| (pair[1] - pair[0]).abs()).filter(|d| *d > 0.).collect();
+
+  if differences.is_empty() {
+    return None;
+  }
+
+  differences.sort_by(f32::total_cmp);
+  let median = differences[differences.len() / 2];
+
+  [1. / 3., 1. / 2., 1.]
+    .into_iter()
+    .min_by(|a: &f32, b: &f32| (a - median).abs().total_cmp(&(b - median).abs()))
+}
+
+/// Plans `frames` captures spaced `spacing` stops apart, centered on the current choice
+pub fn plan_bracket(
+  setting: &CameraSettings,
+  kind: BracketSetting,
+  frames: u32,
+  spacing: f32,
+) -> AppResult<BracketPlan> {
+  if setting.readonly {
+    Err(format!(
+      "{} can not be changed in the current exposure mode, bracketing is not possible",
+      kind.label()
+    ))?
+  }
+
+  let (choices, choice) = match &setting.widget {
+    StaticWidget::Radio { choices, choice } => (choices, choice),
+    widget => Err(format!("Expected {} to be a radio widget, got {:?}", setting.name, widget))?,
+  };
+
+  let original = match choice {
+    RadioChoice::Indexed(index) => *index,
+    RadioChoice::Other(_) => Err(format!(
+      "The current {} ({}) is not one of the camera's choices",
+      kind.label(),
+      display_radio_choice(choices, choice)
+    ))?,
+  };
+
+  let choice_stops: Vec<Option<f32>> = choices.iter().map(|choice| kind.stops(choice)).collect();
+  let current_stops = choice_stops[original]
+    .ok_or_else(|| format!("Can not bracket around {} {}", kind.label(), choices[original]))?;
+  let step = choice_step(&choice_stops.iter().flatten().copied().collect::<Vec<_>>())
+    .ok_or_else(|| format!("{} has no choices to bracket with", kind.label()))?;
+
+  // Spacing is rounded to the step size of the camera
+  let spacing_steps = (spacing / step).round().max(1.);
+
+  let mut frames_plan = Vec::with_capacity(frames as usize);
+  for frame in 0..frames as i32 {
+    // 0, -1, +1, -2, +2, ...
+    let position = if frame % 2 == 1 { -(frame + 1) / 2 } else { frame / 2 };
+    let offset = position as f32 * spacing_steps * step;
+    let target = current_stops + offset;
+
+    let (index, stops) = choice_stops
+      .iter()
+      .enumerate()
+      .filter_map(|(index, stops)| stops.map(|stops| (index, stops)))
+      .min_by(|(_, a), (_, b)| (a - target).abs().total_cmp(&(b - target).abs()))
+      .expect("There is at least one choice with a value");
+
+    if (stops - target).abs() > step / 2. {
+      Err(format!(
+        "A bracket of {:+.1} stops is outside of the {} range of the camera",
+        offset,
+        kind.label()
+      ))?
+    }
+
+    frames_plan.push((index, offset));
+  }
+
+  Ok(BracketPlan { step, original, frames: frames_plan })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn shutter_speed(choices: &[&str], choice: RadioChoice) -> CameraSettings {
+    CameraSettings {
+      id: 1,
+      name: "shutterspeed".into(),
+      label: "Shutter Speed".into(),
+      widget: StaticWidget::Radio {
+        choices: choices.iter().map(|choice| choice.to_string()).collect(),
+        choice,
+      },
+      readonly: false,
+    }
+  }
+
+  const THIRDS: [&str; 9] = ["bulb", "1", "0.8", "0.6", "1/2", "1/2.5", "1/3", "1/4", "1/5"];
+
+  #[test]
+  fn stops_of_choices() {
+    let close = |a: Option<f32>, b: f32| (a.unwrap() - b).abs() < 1e-4;
+
+    assert!(close(BracketSetting::ShutterSpeed.stops("1/3"), (1f32 / 3.).log2()));
+    assert!(close(BracketSetting::ShutterSpeed.stops("2.5s"), 2.5f32.log2()));
+    assert!(close(BracketSetting::Aperture.stops("f/4"), -4.));
+    assert!(close(BracketSetting::Iso.stops("400"), 400f32.log2()));
+    assert_eq!(BracketSetting::ShutterSpeed.stops("bulb"), None);
+    assert_eq!(BracketSetting::ShutterSpeed.stops("1/0"), None);
+    assert_eq!(BracketSetting::Iso.stops("Auto"), None);
+  }
+
+  #[test]
+  fn step_is_rounded_to_thirds_halves_or_full_stops() {
+    assert_eq!(choice_step(&[0., 0.33, 0.67, 1.]), Some(1. / 3.));
+    assert_eq!(choice_step(&[0., 0.5, 1., 1.5]), Some(0.5));
+    assert_eq!(choice_step(&[0., 1., 2.]), Some(1.));
+    assert_eq!(choice_step(&[1.]), None);
+  }
+
+  #[test]
+  fn frames_are_centered_on_the_current_choice() {
+    let setting = shutter_speed(&THIRDS, RadioChoice::Indexed(4));
+    let plan = plan_bracket(&setting, BracketSetting::ShutterSpeed, 3, 1. / 3.).unwrap();
+
+    assert_eq!(plan.original, 4);
+    assert_eq!(plan.frames.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![4, 5, 3]);
+  }
+
+  #[test]
+  fn frames_past_the_choices_fail() {
+    let setting = shutter_speed(&THIRDS, RadioChoice::Indexed(7));
+
+    assert!(plan_bracket(&setting, BracketSetting::ShutterSpeed, 5, 1. / 3.).is_err());
+    assert!(plan_bracket(&setting, BracketSetting::ShutterSpeed, 3, 1. / 3.).is_ok());
+  }
+
+  #[test]
+  fn current_choice_has_to_be_a_value() {
+    let bulb = shutter_speed(&THIRDS, RadioChoice::Indexed(0));
+    let other = shutter_speed(&THIRDS, RadioChoice::Other("1/7".into()));
+    let mut readonly = shutter_speed(&THIRDS, RadioChoice::Indexed(4));
+    readonly.readonly = true;
+
+    for setting in [bulb, other, readonly] {
+      assert!(plan_bracket(&setting, BracketSetting::ShutterSpeed, 3, 1.).is_err());
+    }
+  }
+}
@@ -0,0 +1,192 @@
+//! Manual focus drive through the `manualfocusdrive` setting
+//!
+//! Depending on the camera and the libgphoto2 version the setting is a radio widget with choices
+//! like "Near 2" and "Far 3", or a range where the sign gives the direction. Ranges are in motor
+//! steps on some bodies (eg. Nikon), so the step sizes are a share of the range.
+
+use super::settings::{CameraSettings, RadioChoice, StaticWidget};
+use crate::error::AppResult;
+
+pub const MANUAL_FOCUS_SETTING: &str = "manualfocusdrive";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+  Near,
+  Far,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusStepSize {
+  Small,
+  Medium,
+  Large,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusStep {
+  pub direction: FocusDirection,
+  pub size: FocusStepSize,
+}
+
+impl FocusDirection {
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::Near => "Near",
+      Self::Far => "Far",
+    }
+  }
+}
+
+impl FocusStepSize {
+  pub const ALL: [FocusStepSize; 3] = [Self::Small, Self::Medium, Self::Large];
+
+  /// Level of the step as used by the camera, 1 is the smallest
+  pub fn level(&self) -> u8 {
+    match self {
+      Self::Small => 1,
+      Self::Medium => 2,
+      Self::Large => 3,
+    }
+  }
+
+  /// Share of a range widget moved by a step, at least [`Self::level`]
+  fn range_share(&self) -> f32 {
+    match self {
+      Self::Small => 0.01,
+      Self::Medium => 0.05,
+      Self::Large => 0.2,
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::Small => "Small",
+      Self::Medium => "Medium",
+      Self::Large => "Large",
+    }
+  }
+}
+
+/// Returns the setting that has to be written to the camera to move the focus by `step`
+pub fn focus_drive(setting: &CameraSettings, step: FocusStep) -> AppResult<CameraSettings> {
+  if setting.readonly {
+    Err("Manual focus can not be driven, is the lens set to autofocus?")?
+  }
+
+  let mut drive = setting.clone();
+
+  match &mut drive.widget {
+    StaticWidget::Radio { choices, choice } => {
+      let wanted = format!("{} {}", step.direction.label(), step.size.level());
+      let index = choices
+        .iter()
+        .position(|choice| choice.eq_ignore_ascii_case(&wanted))
+        .ok_or_else(|| format!("The camera does not support the focus step {}", wanted))?;
+
+      *choice = RadioChoice::Indexed(index);
+    }
+    StaticWidget::Range { value, range, step: range_step } => {
+      let extent = match step.direction {
+        FocusDirection::Near => -range.start(),
+        FocusDirection::Far => *range.end(),
+      };
+      if extent <= 0. {
+        Err(format!("The camera can not drive the focus {}", step.direction.label()))?
+      }
+
+      let mut amount = (extent * step.size.range_share()).max(f32::from(step.size.level()));
+      if *range_step > 0. {
+        amount = (amount / *range_step).round() * *range_step;
+      }
+
+      let amount = match step.direction {
+        FocusDirection::Near => -amount,
+        FocusDirection::Far => amount,
+      };
+      *value = amount.clamp(*range.start(), *range.end());
+    }
+    widget => Err(format!("Invalid widget type for {}: {:?}", MANUAL_FOCUS_SETTING, widget))?,
+  }
+
+  Ok(drive)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::ops::RangeInclusive;
+
+  fn setting(widget: StaticWidget) -> CameraSettings {
+    CameraSettings {
+      id: 1,
+      name: MANUAL_FOCUS_SETTING.into(),
+      label: "Manual Focus Drive".into(),
+      widget,
+      readonly: false,
+    }
+  }
+
+  fn radio() -> CameraSettings {
+    let choices = ["Near 1", "Near 2", "Near 3", "None", "Far 1", "Far 2"];
+    setting(StaticWidget::Radio {
+      choices: choices.iter().map(|choice| choice.to_string()).collect(),
+      choice: RadioChoice::Indexed(3),
+    })
+  }
+
+  fn range(range: RangeInclusive<f32>) -> CameraSettings {
+    setting(StaticWidget::Range { value: 0., range, step: 1. })
+  }
+
+  fn drive_value(setting: &CameraSettings, direction: FocusDirection, size: FocusStepSize) -> f32 {
+    match focus_drive(setting, FocusStep { direction, size }).unwrap().widget {
+      StaticWidget::Range { value, .. } => value,
+      widget => panic!("Expected a range, got {:?}", widget),
+    }
+  }
+
+  #[test]
+  fn radio_choices() {
+    let step = FocusStep { direction: FocusDirection::Near, size: FocusStepSize::Medium };
+    let drive = focus_drive(&radio(), step).unwrap();
+    assert_eq!(drive.widget.value_text().as_deref(), Some("Near 2"));
+
+    let step = FocusStep { direction: FocusDirection::Far, size: FocusStepSize::Large };
+    assert!(focus_drive(&radio(), step).is_err());
+  }
+
+  #[test]
+  fn small_ranges_use_the_step_levels() {
+    let setting = range(-3.0..=3.);
+
+    assert_eq!(drive_value(&setting, FocusDirection::Near, FocusStepSize::Small), -1.);
+    assert_eq!(drive_value(&setting, FocusDirection::Far, FocusStepSize::Medium), 2.);
+    assert_eq!(drive_value(&setting, FocusDirection::Far, FocusStepSize::Large), 3.);
+  }
+
+  #[test]
+  fn motor_step_ranges_are_scaled() {
+    let setting = range(-32767.0..=32767.);
+
+    assert_eq!(drive_value(&setting, FocusDirection::Far, FocusStepSize::Small), 328.);
+    assert_eq!(drive_value(&setting, FocusDirection::Near, FocusStepSize::Medium), -1638.);
+    assert_eq!(drive_value(&setting, FocusDirection::Far, FocusStepSize::Large), 6553.);
+  }
+
+  #[test]
+  fn one_sided_ranges() {
+    let setting = range(0.0..=2.);
+
+    assert_eq!(drive_value(&setting, FocusDirection::Far, FocusStepSize::Large), 2.);
+    let step = FocusStep { direction: FocusDirection::Near, size: FocusStepSize::Small };
+    assert!(focus_drive(&setting, step).is_err());
+  }
+
+  #[test]
+  fn readonly_setting() {
+    let setting = CameraSettings { readonly: true, ..radio() };
+    let step = FocusStep { direction: FocusDirection::Near, size: FocusStepSize::Small };
+
+    assert!(focus_drive(&setting, step).is_err());
+  }
+}
@@ -0,0 +1,275 @@
+//! History of the settings written to the camera, with undo and redo
+//!
+//! Every entry is a group of settings written together, eg. all edits of one apply or the frame
+//! of a bracket. Only changes made by the user or a preset can be undone, changes of sequences are
+//! restored by the sequence itself and are kept for reference.
+
+use super::settings::CameraSettings;
+use crate::utils::time::format_timestamp_seconds;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeSource {
+  User,
+  /// Name of the applied preset
+  Preset(String),
+  /// Name of the sequence, eg. `Bracketing`
+  Sequence(String),
+}
+
+impl ChangeSource {
+  pub fn can_undo(&self) -> bool {
+    !matches!(self, Self::Sequence(_))
+  }
+}
+
+impl std::fmt::Display for ChangeSource {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::User => write!(f, "User"),
+      Self::Preset(name) => write!(f, "Preset {}", name),
+      Self::Sequence(name) => write!(f, "{}", name),
+    }
+  }
+}
+
+/// A setting with its values before and after it was written
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedSetting {
+  pub old: CameraSettings,
+  pub new: CameraSettings,
+}
+
+impl ChangedSetting {
+  pub fn old_value(&self) -> String {
+    self.old.widget.value_text().unwrap_or_default()
+  }
+
+  pub fn new_value(&self) -> String {
+    self.new.widget.value_text().unwrap_or_default()
+  }
+}
+
+/// Settings written together
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingChange {
+  pub source: ChangeSource,
+  pub time: SystemTime,
+  pub settings: Vec<ChangedSetting>,
+}
+
+impl SettingChange {
+  /// Seconds since the unix epoch
+  pub fn timestamp(&self) -> i64 {
+    self.time.duration_since(UNIX_EPOCH).map(|since| since.as_secs() as i64).unwrap_or_default()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryState {
+  Applied,
+  /// Reverted by an undo, it can be redone
+  Undone,
+  /// Reverted by an undo before another change was made, it can not be redone anymore
+  Abandoned,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+  pub change: SettingChange,
+  pub state: EntryState,
+}
+
+/// Undo or redo of a history entry, by index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryStep {
+  Undo(usize),
+  Redo(usize),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SettingsHistory {
+  /// Oldest first
+  pub entries: Vec<HistoryEntry>,
+}
+
+impl SettingsHistory {
+  /// Adds a change, changes that were undone before can not be redone afterwards
+  pub fn push(&mut self, change: SettingChange) {
+    if change.settings.is_empty() {
+      return;
+    }
+
+    if change.source.can_undo() {
+      for entry in &mut self.entries {
+        if entry.state == EntryState::Undone {
+          entry.state = EntryState::Abandoned;
+        }
+      }
+    }
+
+    self.entries.push(HistoryEntry { change, state: EntryState::Applied });
+  }
+
+  /// Entry that is reverted by the next undo
+  pub fn undo_target(&self) -> Option<usize> {
+    self
+      .entries
+      .iter()
+      .rposition(|entry| entry.state == EntryState::Applied && entry.change.source.can_undo())
+  }
+
+  /// Entry that is applied again by the next redo, the oldest one that was undone
+  pub fn redo_target(&self) -> Option<usize> {
+    self.entries.iter().position(|entry| entry.state == EntryState::Undone)
+  }
+
+  /// Settings to write for a step, with the values to restore
+  pub fn step_settings(&self, step: HistoryStep) -> Vec<CameraSettings> {
+    match step {
+      HistoryStep::Undo(index) => self.entries[index]
+        .change
+        .settings
+        .iter()
+        .rev()
+        .map(|setting| setting.old.clone())
+        .collect(),
+      HistoryStep::Redo(index) => {
+        self.entries[index].change.settings.iter().map(|setting| setting.new.clone()).collect()
+      }
+    }
+  }
+
+  /// Marks a step as done once its settings were written
+  pub fn finish_step(&mut self, step: HistoryStep) {
+    let (index, state) = match step {
+      HistoryStep::Undo(index) => (index, EntryState::Undone),
+      HistoryStep::Redo(index) => (index, EntryState::Applied),
+    };
+
+    if let Some(entry) = self.entries.get_mut(index) {
+      entry.state = state;
+    }
+  }
+
+  /// All entries as CSV, one line per setting
+  pub fn to_csv(&self) -> String {
+    let mut csv = String::from("time (UTC),source,setting,label,old value,new value,state\n");
+
+    for entry in &self.entries {
+      let state = match entry.state {
+        EntryState::Applied => "applied",
+        EntryState::Undone | EntryState::Abandoned => "undone",
+      };
+
+      for setting in &entry.change.settings {
+        let fields = [
+          format_timestamp_seconds(entry.change.timestamp()),
+          entry.change.source.to_string(),
+          setting.new.name.clone(),
+          setting.new.label.clone(),
+          setting.old_value(),
+          setting.new_value(),
+          state.to_string(),
+        ];
+
+        csv.push_str(&fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+      }
+    }
+
+    csv
+  }
+}
+
+/// Quotes a field that contains separators, quotes or line breaks
+fn csv_field(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::camera::settings::StaticWidget;
+  use std::time::Duration;
+
+  fn change(source: ChangeSource, old: &str, new: &str) -> SettingChange {
+    let setting = |value: &str| CameraSettings {
+      id: 1,
+      name: "owner".into(),
+      label: "Owner".into(),
+      widget: StaticWidget::Text(value.into()),
+      readonly: false,
+    };
+
+    SettingChange {
+      source,
+      time: UNIX_EPOCH + Duration::from_secs(86400 + 3723),
+      settings: vec![ChangedSetting { old: setting(old), new: setting(new) }],
+    }
+  }
+
+  #[test]
+  fn undo_and_redo() {
+    let mut history = SettingsHistory::default();
+    history.push(change(ChangeSource::User, "a", "b"));
+    history.push(change(ChangeSource::Preset("day".into()), "b", "c"));
+    history.push(change(ChangeSource::Sequence("Bracketing".into()), "c", "d"));
+
+    assert_eq!(history.undo_target(), Some(1));
+    assert_eq!(history.redo_target(), None);
+
+    history.finish_step(HistoryStep::Undo(1));
+    assert_eq!(history.undo_target(), Some(0));
+    history.finish_step(HistoryStep::Undo(0));
+    assert_eq!(history.undo_target(), None);
+
+    assert_eq!(history.redo_target(), Some(0));
+    history.finish_step(HistoryStep::Redo(0));
+    assert_eq!(history.redo_target(), Some(1));
+    assert_eq!(history.undo_target(), Some(0));
+  }
+
+  #[test]
+  fn new_change_abandons_undone_entries() {
+    let mut history = SettingsHistory::default();
+    history.push(change(ChangeSource::User, "a", "b"));
+    history.push(change(ChangeSource::User, "b", "c"));
+    history.finish_step(HistoryStep::Undo(1));
+
+    history.push(change(ChangeSource::Sequence("Bulb".into()), "b", "x"));
+    assert_eq!(history.redo_target(), Some(1));
+
+    history.push(change(ChangeSource::User, "b", "d"));
+    assert_eq!(history.entries[1].state, EntryState::Abandoned);
+    assert_eq!(history.redo_target(), None);
+    assert_eq!(history.undo_target(), Some(3));
+  }
+
+  #[test]
+  fn step_settings_use_old_and_new_values() {
+    let mut history = SettingsHistory::default();
+    history.push(change(ChangeSource::User, "a", "b"));
+
+    let undo = history.step_settings(HistoryStep::Undo(0));
+    assert_eq!(undo[0].widget, StaticWidget::Text("a".into()));
+    let redo = history.step_settings(HistoryStep::Redo(0));
+    assert_eq!(redo[0].widget, StaticWidget::Text("b".into()));
+  }
+
+  #[test]
+  fn csv_export() {
+    let mut history = SettingsHistory::default();
+    history.push(change(ChangeSource::Preset("night, tripod".into()), "a", "say \"hi\""));
+
+    assert_eq!(
+      history.to_csv(),
+      "time (UTC),source,setting,label,old value,new value,state\n\
+       1970-01-02 01:02:03,\"Preset night, tripod\",owner,Owner,a,\"say \"\"hi\"\"\",applied\n"
+    );
+  }
+}
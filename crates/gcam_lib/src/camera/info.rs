@@ -0,0 +1,39 @@
+use gphoto2::{
+  abilities::CameraDriverStatus,
+  filesys::{AccessType, FilesystemType, StorageType},
+};
+
+#[derive(Debug)]
+pub struct CameraInfo {
+  pub model: String,
+  pub port: String,
+  pub manual: Option<String>,
+  pub summary: Option<String>,
+  pub about: Option<String>,
+  pub abilities: CameraAbilities,
+  pub storages: Vec<StorageInfo>,
+}
+
+/// Operations supported by the camera driver
+#[derive(Debug)]
+pub struct CameraAbilities {
+  pub driver_status: CameraDriverStatus,
+  pub capture_image: bool,
+  pub capture_preview: bool,
+  pub configure: bool,
+  pub delete_files: bool,
+  pub file_preview: bool,
+}
+
+/// Owned copy of the information the camera reports about one of its storages
+#[derive(Debug)]
+pub struct StorageInfo {
+  pub label: Option<String>,
+  pub base_directory: Option<String>,
+  pub description: Option<String>,
+  pub storage_type: Option<StorageType>,
+  pub filesystem_type: Option<FilesystemType>,
+  pub access_type: Option<AccessType>,
+  pub capacity_kb: Option<usize>,
+  pub free_kb: Option<usize>,
+}
@@ -0,0 +1,7 @@
+pub mod bracketing;
+pub mod focus;
+pub mod history;
+pub mod info;
+pub mod presets;
+pub mod settings;
+pub mod zoom;
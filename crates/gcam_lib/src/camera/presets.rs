@@ -0,0 +1,272 @@
+//! Snapshots of the writable camera settings, used for setting presets
+//!
+//! Values are stored as text (the choice of a radio, the number of a range, ...), so a preset stays
+//! readable and can be compared with cameras whose settings tree has different ids.
+
+use super::settings::{display_radio_choice, CameraSettings, RadioChoice, StaticWidget};
+use crate::error::AppResult;
+use std::collections::{BTreeMap, HashSet};
+
+/// How a value of a preset relates to the camera it is loaded on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresetStatus {
+  /// The camera already has the value
+  Unchanged,
+  /// Applying the preset changes the setting
+  Changed,
+  /// The camera has no setting with this name
+  Missing,
+  /// The setting can not be changed on the camera
+  ReadOnly,
+  /// The camera does not accept the value, eg. a choice it does not offer
+  Invalid(String),
+}
+
+/// A value of a preset compared with the camera
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresetEntry {
+  pub name: String,
+  /// Label of the camera setting, the name if the camera has no such setting
+  pub label: String,
+  /// Current value on the camera
+  pub current: Option<String>,
+  pub value: String,
+  pub status: PresetStatus,
+  /// The setting with the value of the preset and the id of its section, for changed entries
+  pub setting: Option<(i32, CameraSettings)>,
+}
+
+/// Value of a setting that can be written, `None` for groups, buttons, dates and read only settings
+///
+/// Dates are left out as restoring an old date is never wanted.
+pub fn setting_value(setting: &CameraSettings) -> Option<String> {
+  if setting.readonly {
+    return None;
+  }
+
+  match &setting.widget {
+    StaticWidget::Text(text) => Some(text.clone()),
+    StaticWidget::Range { value, .. } => Some(value.to_string()),
+    StaticWidget::Toggle { undefined: false, value } => Some(value.to_string()),
+    StaticWidget::Radio { choices, choice } => Some(display_radio_choice(choices, choice).into()),
+    StaticWidget::Toggle { undefined: true, .. }
+    | StaticWidget::Date { .. }
+    | StaticWidget::Button
+    | StaticWidget::Group { .. } => None,
+  }
+}
+
+/// Copy of `setting` with the value parsed from `value`
+pub fn with_value(setting: &CameraSettings, value: &str) -> AppResult<CameraSettings> {
+  let mut setting = setting.clone();
+
+  match &mut setting.widget {
+    StaticWidget::Text(text) => *text = value.to_string(),
+    StaticWidget::Range { value: current, range, .. } => {
+      let parsed: f32 = value.trim().parse().map_err(|_| format!("{} is not a number", value))?;
+
+      if !range.contains(&parsed) {
+        Err(format!("{} is outside of {} to {}", value, range.start(), range.end()))?
+      }
+
+      *current = parsed;
+    }
+    StaticWidget::Toggle { undefined, value: current } => {
+      *current = value.trim().parse().map_err(|_| format!("{} is not on or off", value))?;
+      *undefined = false;
+    }
+    StaticWidget::Radio { choices, choice } => {
+      let index = choices
+        .iter()
+        .position(|choice| choice == value)
+        .ok_or_else(|| format!("{} is not one of the choices", value))?;
+
+      *choice = RadioChoice::Indexed(index);
+    }
+    widget => Err(format!("Unsupported widget: {:?}", widget))?,
+  }
+
+  Ok(setting)
+}
+
+/// Values of all writable settings, by name
+///
+/// Settings named in `exclude` are left out, together with their children.
+pub fn snapshot(settings: &CameraSettings, exclude: &HashSet<String>) -> BTreeMap<String, String> {
+  let mut values = BTreeMap::new();
+  collect_values(settings, exclude, &mut values);
+  values
+}
+
+fn collect_values(
+  setting: &CameraSettings,
+  exclude: &HashSet<String>,
+  values: &mut BTreeMap<String, String>,
+) {
+  if exclude.contains(&setting.name) {
+    return;
+  }
+
+  if let StaticWidget::Group { children, .. } = &setting.widget {
+    for child in children.values() {
+      collect_values(child, exclude, values);
+    }
+  } else if let Some(value) = setting_value(setting) {
+    values.insert(setting.name.clone(), value);
+  }
+}
+
+/// Searches the tree for a setting, together with the id of the group that contains it
+fn find_with_section<'a>(
+  settings: &'a CameraSettings,
+  name: &str,
+) -> Option<(i32, &'a CameraSettings)> {
+  match &settings.widget {
+    StaticWidget::Group { children, .. } => children.values().find_map(|child| {
+      if child.name == name {
+        Some((settings.id, child))
+      } else {
+        find_with_section(child, name)
+      }
+    }),
+    _ => None,
+  }
+}
+
+/// Compares the values of a preset with the settings of a camera
+pub fn diff(values: &BTreeMap<String, String>, settings: &CameraSettings) -> Vec<PresetEntry> {
+  values
+    .iter()
+    .map(|(name, value)| {
+      let mut entry = PresetEntry {
+        name: name.clone(),
+        label: name.clone(),
+        current: None,
+        value: value.clone(),
+        status: PresetStatus::Missing,
+        setting: None,
+      };
+
+      let (section_id, setting) = match find_with_section(settings, name) {
+        Some(found) => found,
+        None => return entry,
+      };
+
+      entry.label = setting.label.clone();
+
+      if setting.readonly {
+        entry.status = PresetStatus::ReadOnly;
+        return entry;
+      }
+
+      entry.current = setting_value(setting);
+      entry.status = match with_value(setting, value) {
+        Err(err) => PresetStatus::Invalid(err.to_string()),
+        Ok(changed) if changed.widget == setting.widget => PresetStatus::Unchanged,
+        Ok(changed) => {
+          entry.setting = Some((section_id, changed));
+          PresetStatus::Changed
+        }
+      };
+
+      entry
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn setting(id: i32, name: &str, widget: StaticWidget, readonly: bool) -> CameraSettings {
+    CameraSettings { id, name: name.into(), label: name.to_uppercase(), widget, readonly }
+  }
+
+  fn group(id: i32, name: &str, children: Vec<CameraSettings>) -> CameraSettings {
+    let id_by_names = children.iter().map(|child| (child.name.clone(), child.id)).collect();
+    let children = children.into_iter().map(|child| (child.id, child)).collect();
+
+    setting(id, name, StaticWidget::Group { children, id_by_names }, false)
+  }
+
+  fn camera() -> CameraSettings {
+    let iso = StaticWidget::Radio {
+      choices: vec!["100".into(), "200".into(), "400".into()],
+      choice: RadioChoice::Indexed(0),
+    };
+
+    group(
+      1,
+      "main",
+      vec![
+        group(
+          2,
+          "imgsettings",
+          vec![
+            setting(3, "iso", iso, false),
+            setting(4, "owner", StaticWidget::Text("me".into()), false),
+            setting(5, "datetime", StaticWidget::Date { timestamp: 0 }, false),
+          ],
+        ),
+        group(
+          6,
+          "status",
+          vec![
+            setting(7, "serialnumber", StaticWidget::Text("123".into()), true),
+            setting(
+              8,
+              "zoom",
+              StaticWidget::Range { value: 1., range: 1.0..=10., step: 1. },
+              false,
+            ),
+          ],
+        ),
+      ],
+    )
+  }
+
+  #[test]
+  fn snapshot_keeps_writable_leaves() {
+    let values = snapshot(&camera(), &HashSet::from(["status".to_string()]));
+
+    assert_eq!(
+      values,
+      BTreeMap::from([("iso".into(), "100".into()), ("owner".into(), "me".into())])
+    );
+  }
+
+  #[test]
+  fn diff_with_camera() {
+    let values = BTreeMap::from([
+      ("iso".to_string(), "400".to_string()),
+      ("owner".to_string(), "me".to_string()),
+      ("serialnumber".to_string(), "456".to_string()),
+      ("zoom".to_string(), "20".to_string()),
+      ("shutterspeed".to_string(), "1/100".to_string()),
+    ]);
+    let entries = diff(&values, &camera());
+    let status = |name: &str| {
+      entries.iter().find(|entry| entry.name == name).map(|entry| entry.status.clone()).unwrap()
+    };
+
+    assert_eq!(status("iso"), PresetStatus::Changed);
+    assert_eq!(status("owner"), PresetStatus::Unchanged);
+    assert_eq!(status("serialnumber"), PresetStatus::ReadOnly);
+    assert!(matches!(status("zoom"), PresetStatus::Invalid(_)));
+    assert_eq!(status("shutterspeed"), PresetStatus::Missing);
+
+    let iso = entries.iter().find(|entry| entry.name == "iso").unwrap();
+    let (section_id, setting) = iso.setting.as_ref().unwrap();
+    assert_eq!(*section_id, 2);
+    assert_eq!(setting_value(setting).as_deref(), Some("400"));
+    assert_eq!(iso.current.as_deref(), Some("100"));
+  }
+
+  #[test]
+  fn unknown_choice_is_invalid() {
+    let entries = diff(&BTreeMap::from([("iso".to_string(), "12800".to_string())]), &camera());
+
+    assert!(matches!(entries[0].status, PresetStatus::Invalid(_)));
+    assert!(entries[0].setting.is_none());
+  }
+}
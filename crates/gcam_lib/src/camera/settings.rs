@@ -0,0 +1,104 @@
+//! Camera settings tree
+
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RadioChoice {
+  /// A choice that is inside the available choices
+  Indexed(usize),
+  /// The camera decided to return something else than the allowed choices
+  Other(String),
+}
+
+// TODO: Reconsider using structs for each of the variants for better type safety
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaticWidget {
+  Group { children: BTreeMap<i32, CameraSettings>, id_by_names: BTreeMap<String, i32> },
+  Text(String),
+  Range { value: f32, range: RangeInclusive<f32>, step: f32 },
+  Toggle { undefined: bool, value: bool },
+  Radio { choices: Vec<String>, choice: RadioChoice },
+  Date { timestamp: i32 },
+  Button,
+}
+
+impl StaticWidget {
+  /// Current value as text, `None` for groups and buttons
+  pub fn value_text(&self) -> Option<String> {
+    match self {
+      Self::Text(text) => Some(text.clone()),
+      Self::Range { value, .. } => Some(value.to_string()),
+      Self::Toggle { undefined: true, .. } => Some("undefined".into()),
+      Self::Toggle { value, .. } => Some(if *value { "on" } else { "off" }.into()),
+      Self::Radio { choices, choice } => Some(display_radio_choice(choices, choice).into()),
+      Self::Date { timestamp } => Some(timestamp.to_string()),
+      Self::Group { .. } | Self::Button => None,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CameraSettings {
+  pub id: i32,
+  pub name: String,
+  pub label: String,
+  pub widget: StaticWidget,
+  pub readonly: bool,
+}
+
+impl CameraSettings {
+  pub fn get_child(&self, name: &str) -> Option<&'_ CameraSettings> {
+    if let StaticWidget::Group { children, id_by_names } = &self.widget {
+      id_by_names.get(name).and_then(|id| children.get(id))
+    } else {
+      None
+    }
+  }
+
+  /// Searches the whole tree for a setting with the given name
+  pub fn find(&self, name: &str) -> Option<&'_ CameraSettings> {
+    if self.name == name {
+      return Some(self);
+    }
+
+    if let StaticWidget::Group { children, .. } = &self.widget {
+      children.values().find_map(|child| child.find(name))
+    } else {
+      None
+    }
+  }
+
+  /// Calls `visit` with every setting of the tree that is not a group
+  pub fn for_each_leaf<'a>(&'a self, visit: &mut impl FnMut(&'a CameraSettings)) {
+    match &self.widget {
+      StaticWidget::Group { children, .. } => {
+        children.values().for_each(|child| child.for_each_leaf(visit))
+      }
+      _ => visit(self),
+    }
+  }
+
+  /// Same as [`CameraSettings::find`], but returns a mutable reference
+  pub fn find_mut(&mut self, name: &str) -> Option<&'_ mut CameraSettings> {
+    if self.name == name {
+      return Some(self);
+    }
+
+    if let StaticWidget::Group { children, .. } = &mut self.widget {
+      children.values_mut().find_map(|child| child.find_mut(name))
+    } else {
+      None
+    }
+  }
+}
+
+#[allow(clippy::ptr_arg)] // This is no problem here, it makes no new allocations and usage is easier
+pub fn display_radio_choice<'widget>(
+  choices: &'widget Vec<String>,
+  choice: &'widget RadioChoice,
+) -> &'widget str {
+  match choice {
+    RadioChoice::Indexed(idx) => &choices[*idx],
+    RadioChoice::Other(text) => text,
+  }
+}
@@ -0,0 +1,47 @@
+//! Magnification of the live view by the camera through the `liveviewimagezoomratio` setting
+//!
+//! Zooming on the camera shows real sensor pixels instead of an upscaled preview. The camera
+//! magnifies around its own zoom position (usually the focus point).
+
+use super::settings::{CameraSettings, RadioChoice, StaticWidget};
+use crate::error::AppResult;
+
+pub const LIVE_VIEW_ZOOM_SETTING: &str = "liveviewimagezoomratio";
+
+/// Ratio of a radio choice, choices can have a unit or prefix like "x5"
+fn choice_ratio(choice: &str) -> Option<f32> {
+  choice.trim_matches(|c: char| !c.is_ascii_digit() && c != '.').parse().ok()
+}
+
+/// Returns the setting for the largest camera zoom that is not above `zoom`, together with its
+/// ratio
+pub fn zoom_setting(setting: &CameraSettings, zoom: f32) -> AppResult<(CameraSettings, f32)> {
+  if setting.readonly {
+    Err("The live view zoom of the camera can not be changed")?
+  }
+
+  let mut zoomed = setting.clone();
+
+  let ratio = match &mut zoomed.widget {
+    StaticWidget::Radio { choices, choice } => {
+      let (index, ratio) = choices
+        .iter()
+        .enumerate()
+        .filter_map(|(index, choice)| Some((index, choice_ratio(choice)?)))
+        .filter(|(_, ratio)| *ratio <= zoom.max(1.))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .ok_or("The camera has no live view zoom choices")?;
+
+      *choice = RadioChoice::Indexed(index);
+      ratio
+    }
+    StaticWidget::Range { value, range, step } => {
+      let steps = ((zoom - range.start()) / step.max(f32::EPSILON)).floor();
+      *value = (range.start() + steps * *step).clamp(*range.start(), *range.end());
+      *value
+    }
+    widget => Err(format!("Unsupported live view zoom widget: {:?}", widget))?,
+  };
+
+  Ok((zoomed, ratio.max(1.)))
+}
@@ -9,6 +9,8 @@ pub enum AppError {
   IoError(std::io::Error),
   ThreadError(String),
   ImageError(image::ImageError),
+  /// Image data in a format that can not be detected or decoded
+  UnsupportedImage(String),
 }
 
 impl AppError {
@@ -19,6 +21,7 @@ impl AppError {
       Self::IoError(_) => "I/O Error",
       Self::ThreadError(_) => "Threading Error",
       Self::ImageError(_) => "Image Error",
+      Self::UnsupportedImage(_) => "Unsupported Image",
     }
   }
 }
@@ -33,6 +36,7 @@ impl fmt::Display for AppError {
       Self::ThreadError(err) => write!(f, "An error threading error has ocurred: {}", err),
       Self::IoError(err) => write!(f, "An I/O error has ocurred: {:?}", err),
       Self::ImageError(err) => write!(f, "Image error: {}", err),
+      Self::UnsupportedImage(err) => write!(f, "Unsupported image data: {}", err),
     }
   }
 }
@@ -1,2 +1,4 @@
+pub mod backend;
+pub mod camera;
 pub mod error;
 pub mod utils;
@@ -0,0 +1,99 @@
+//! Exposure overlays for live view: zebra stripes and false color
+
+use super::histogram::luma;
+use epaint::{Color32, ColorImage};
+
+/// Default zebra level in IRE
+pub const DEFAULT_ZEBRA_LEVEL: u8 = 95;
+/// Width of a zebra stripe in preview pixels
+const STRIPE_WIDTH: usize = 4;
+
+/// Luminance bands of the false color mode, as the upper bound of the band in IRE and the
+/// color it is shown in, `None` keeps the band in grayscale
+pub const FALSE_COLOR_BANDS: [(f32, Option<Color32>); 9] = [
+  (2.5, Some(Color32::from_rgb(128, 0, 160))), // Crushed blacks
+  (4., Some(Color32::from_rgb(0, 90, 255))),   // Near black
+  (38., None),
+  (42., Some(Color32::from_rgb(0, 200, 0))), // 18% gray
+  (52., None),
+  (56., Some(Color32::from_rgb(255, 130, 180))), // Skin tones
+  (97., None),
+  (99., Some(Color32::from_rgb(255, 230, 0))), // Near white
+  (100., Some(Color32::from_rgb(255, 0, 0))),  // Clipped
+];
+
+fn ire(luma: u8) -> f32 {
+  luma as f32 * 100. / 255.
+}
+
+/// Diagonal black and white stripes over the pixels at or above `level` (IRE), the rest of
+/// the image is transparent
+pub fn zebra(image: &ColorImage, level: u8) -> ColorImage {
+  let width = image.size[0].max(1);
+  let mut mask = ColorImage::new(image.size, Color32::TRANSPARENT);
+
+  for (index, (&pixel, masked)) in image.pixels.iter().zip(&mut mask.pixels).enumerate() {
+    if ire(luma(pixel)) >= level as f32 {
+      let (x, y) = (index % width, index / width);
+      let stripe = ((x + y) / STRIPE_WIDTH) & 1 == 0;
+
+      *masked = if stripe { Color32::WHITE } else { Color32::BLACK };
+    }
+  }
+
+  mask
+}
+
+/// Maps the luminance of every pixel to the color of its band in [`FALSE_COLOR_BANDS`]
+pub fn false_color(image: &ColorImage) -> ColorImage {
+  let lookup: Vec<Color32> = (0..=u8::MAX)
+    .map(|luma| {
+      let band = FALSE_COLOR_BANDS.iter().find(|(upper, _)| ire(luma) <= *upper);
+
+      match band {
+        Some((_, Some(color))) => *color,
+        // Grayscale bands are dimmed so the colored bands stand out
+        _ => Color32::from_gray(luma / 2 + 32),
+      }
+    })
+    .collect();
+
+  ColorImage {
+    size: image.size,
+    pixels: image.pixels.iter().map(|&pixel| lookup[luma(pixel) as usize]).collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn zebra_marks_bright_pixels() {
+    let image =
+      ColorImage { size: [2, 1], pixels: vec![Color32::from_gray(250), Color32::from_gray(128)] };
+    let mask = zebra(&image, DEFAULT_ZEBRA_LEVEL);
+
+    assert_ne!(mask.pixels[0], Color32::TRANSPARENT);
+    assert_eq!(mask.pixels[1], Color32::TRANSPARENT);
+  }
+
+  #[test]
+  fn false_color_bands() {
+    let image = ColorImage {
+      size: [4, 1],
+      pixels: vec![
+        Color32::BLACK,
+        Color32::WHITE,
+        Color32::from_gray(102), // 40 %
+        Color32::from_gray(180),
+      ],
+    };
+    let colored = false_color(&image);
+
+    assert_eq!(colored.pixels[0], FALSE_COLOR_BANDS[0].1.unwrap());
+    assert_eq!(colored.pixels[1], FALSE_COLOR_BANDS[8].1.unwrap());
+    assert_eq!(colored.pixels[2], FALSE_COLOR_BANDS[3].1.unwrap());
+    assert_eq!(colored.pixels[3], Color32::from_gray(180 / 2 + 32));
+  }
+}
@@ -0,0 +1,144 @@
+//! File names for downloaded pictures
+//!
+//! Templates can contain the following placeholders:
+//! - `{date}`: capture date as `YYYY-MM-DD` (UTC)
+//! - `{time}`: capture time as `HH-MM-SS` (UTC)
+//! - `{model}`: camera model
+//! - `{seq}`: sequence number, a width can be given with `{seq:04}`
+//! - `{name}`: name of the file on the camera, without extension
+//! - `{ext}`: extension of the file on the camera, in lowercase
+
+use super::time::civil_from_days;
+use crate::error::AppResult;
+
+pub const DEFAULT_TEMPLATE: &str = "{date}_{model}_{seq:04}.{ext}";
+
+pub struct FilenameFields<'a> {
+  /// Unix timestamp of the capture
+  pub timestamp: i64,
+  pub model: &'a str,
+  pub sequence: u32,
+  /// Name of the file on the camera
+  pub camera_name: &'a str,
+}
+
+/// Whether different sequence numbers produce different file names
+pub fn has_sequence(template: &str) -> bool {
+  template.contains("{seq}") || template.contains("{seq:")
+}
+
+pub fn render_filename(template: &str, fields: &FilenameFields) -> AppResult<String> {
+  let (stem, extension) = fields.camera_name.rsplit_once('.').unwrap_or((fields.camera_name, ""));
+  let (year, month, day) = civil_from_days(fields.timestamp.div_euclid(86400));
+  let seconds_of_day = fields.timestamp.rem_euclid(86400);
+
+  let mut rendered = String::new();
+  let mut rest = template;
+
+  while let Some(start) = rest.find('{') {
+    rendered.push_str(&rest[..start]);
+
+    let end = rest[start..]
+      .find('}')
+      .map(|end| start + end)
+      .ok_or_else(|| format!("Unclosed placeholder in file name template: {}", template))?;
+    let (placeholder, format) = match rest[start + 1..end].split_once(':') {
+      Some((placeholder, format)) => (placeholder, Some(format)),
+      None => (&rest[start + 1..end], None),
+    };
+
+    match (placeholder, format) {
+      ("date", None) => rendered.push_str(&format!("{:04}-{:02}-{:02}", year, month, day)),
+      ("time", None) => rendered.push_str(&format!(
+        "{:02}-{:02}-{:02}",
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+      )),
+      ("model", None) => rendered.push_str(fields.model),
+      ("name", None) => rendered.push_str(stem),
+      ("ext", None) => rendered.push_str(&extension.to_lowercase()),
+      ("seq", None) => rendered.push_str(&fields.sequence.to_string()),
+      ("seq", Some(width)) => {
+        let width: usize =
+          width.parse().map_err(|_| format!("Invalid sequence width in file name: {}", width))?;
+        rendered.push_str(&format!("{:0width$}", fields.sequence, width = width));
+      }
+      _ => Err(format!("Unknown placeholder in file name template: {}", &rest[start..=end]))?,
+    }
+
+    rest = &rest[end + 1..];
+  }
+  rendered.push_str(rest);
+
+  Ok(sanitize(&rendered))
+}
+
+/// Replaces characters that are not allowed (or annoying) in file names
+fn sanitize(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| match c {
+      '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+      c if c.is_whitespace() => '_',
+      c => c,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fields() -> FilenameFields<'static> {
+    // 2022-09-14 08:05:09 UTC
+    FilenameFields {
+      timestamp: 1_663_142_709,
+      model: "Canon EOS 80D",
+      sequence: 42,
+      camera_name: "IMG_1234.CR2",
+    }
+  }
+
+  #[test]
+  fn placeholders() {
+    assert_eq!(
+      render_filename("{date}_{time}_{name}.{ext}", &fields()).unwrap(),
+      "2022-09-14_08-05-09_IMG_1234.cr2"
+    );
+    assert_eq!(render_filename("{seq}-{seq:05}", &fields()).unwrap(), "42-00042");
+    assert_eq!(
+      render_filename(DEFAULT_TEMPLATE, &fields()).unwrap(),
+      "2022-09-14_Canon_EOS_80D_0042.cr2"
+    );
+  }
+
+  #[test]
+  fn invalid_templates() {
+    assert!(render_filename("{camera}", &fields()).is_err());
+    assert!(render_filename("{date", &fields()).is_err());
+    assert!(render_filename("{seq:wide}", &fields()).is_err());
+    assert!(render_filename("{date:short}", &fields()).is_err());
+  }
+
+  #[test]
+  fn separators_are_replaced() {
+    let fields = FilenameFields { model: "A/B\\C: D", ..fields() };
+
+    assert_eq!(render_filename("{model}/{seq}", &fields).unwrap(), "A_B_C__D_42");
+  }
+
+  #[test]
+  fn files_without_extension() {
+    let fields = FilenameFields { camera_name: "README", ..fields() };
+
+    assert_eq!(render_filename("{name}.{ext}", &fields).unwrap(), "README.");
+  }
+
+  #[test]
+  fn sequence_detection() {
+    assert!(has_sequence("{seq}"));
+    assert!(has_sequence("{date}_{seq:04}"));
+    assert!(!has_sequence("{date}_{time}"));
+  }
+}
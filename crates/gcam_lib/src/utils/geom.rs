@@ -1,11 +1,163 @@
-use epaint::Vec2;
+use epaint::{pos2, Pos2, Rect, Vec2};
 
-pub fn fit_size_into(inner: Vec2, container: Vec2) -> Vec2 {
-  let inner_aspect = inner.x / inner.y;
-  let container_aspect = container.x / container.y;
+/// Most an image can be magnified, relative to fitting it into the viewport
+pub const MAX_ZOOM: f32 = 32.;
 
-  let resize_factor =
-    if container_aspect < inner_aspect { container.x / inner.x } else { container.y / inner.y };
+/// Placement of an image inside of a viewport, with zoom and pan
+///
+/// Image coordinates are in pixels of the image, screen coordinates in points of the UI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+  pub image_size: Vec2,
+  pub viewport: Rect,
+  /// Magnification relative to fitting the whole image into the viewport, at least 1
+  pub zoom: f32,
+  /// Point of the image in the center of the viewport, relative to the image size
+  pub center: Pos2,
+}
+
+impl Default for ViewTransform {
+  fn default() -> Self {
+    Self { image_size: Vec2::ZERO, viewport: Rect::NOTHING, zoom: 1., center: pos2(0.5, 0.5) }
+  }
+}
+
+impl ViewTransform {
+  /// Fits the whole image into the viewport, centered
+  pub fn fit(image_size: Vec2, viewport: Rect) -> Self {
+    Self { image_size, viewport, ..Default::default() }
+  }
+
+  /// Same transform for another image or viewport, keeping zoom and pan
+  pub fn with_layout(self, image_size: Vec2, viewport: Rect) -> Self {
+    let mut transform = Self { image_size, viewport, ..self };
+    transform.clamp_center();
+    transform
+  }
+
+  /// Screen points per image pixel when the image fits into the viewport
+  pub fn fit_scale(&self) -> f32 {
+    if self.image_size.x <= 0. || self.image_size.y <= 0. {
+      return 1.;
+    }
+
+    (self.viewport.width() / self.image_size.x).min(self.viewport.height() / self.image_size.y)
+  }
+
+  /// Screen points per image pixel
+  pub fn scale(&self) -> f32 {
+    self.fit_scale() * self.zoom
+  }
+
+  /// Screen rectangle of the whole image, it can be larger than the viewport
+  pub fn image_rect(&self) -> Rect {
+    let size = self.image_size * self.scale();
+    Rect::from_min_size(self.viewport.center() - self.center.to_vec2() * size, size)
+  }
+
+  /// Part of the viewport that shows the image
+  pub fn visible_rect(&self) -> Rect {
+    self.image_rect().intersect(self.viewport)
+  }
+
+  pub fn image_to_screen(&self, pos: Pos2) -> Pos2 {
+    self.image_rect().min + pos.to_vec2() * self.scale()
+  }
+
+  pub fn screen_to_image(&self, pos: Pos2) -> Pos2 {
+    ((pos - self.image_rect().min) / self.scale()).to_pos2()
+  }
+
+  /// Sets the zoom while keeping the image point under `screen_pos` in place
+  pub fn zoom_at(&mut self, screen_pos: Pos2, zoom: f32) {
+    let anchor = self.screen_to_image(screen_pos);
+    self.zoom = zoom.clamp(1., MAX_ZOOM);
+
+    let size = self.image_size * self.scale();
+    let min = screen_pos - anchor.to_vec2() * self.scale();
+    self.center = ((self.viewport.center() - min) / size).to_pos2();
+    self.clamp_center();
+  }
+
+  /// Zoom at which one image pixel is shown on `pixels_per_point` screen points, ie. 100 %
+  pub fn pixel_zoom(&self, pixels_per_point: f32) -> f32 {
+    1. / (self.fit_scale() * pixels_per_point)
+  }
+
+  /// Moves the image by `delta` screen points
+  pub fn pan(&mut self, delta: Vec2) {
+    let size = self.image_size * self.scale();
+    if size.x > 0. && size.y > 0. {
+      self.center -= delta / size;
+      self.clamp_center();
+    }
+  }
+
+  /// Keeps the image covering the viewport, an axis on which the image is smaller is centered
+  fn clamp_center(&mut self) {
+    let size = self.image_size * self.scale();
+    let viewport = self.viewport.size();
+
+    for (center, size, viewport) in
+      [(&mut self.center.x, size.x, viewport.x), (&mut self.center.y, size.y, viewport.y)]
+    {
+      if size <= viewport || size <= 0. {
+        *center = 0.5;
+      } else {
+        let half = viewport / 2. / size;
+        *center = center.clamp(half, 1. - half);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn transform() -> ViewTransform {
+    ViewTransform::fit(
+      Vec2::new(400., 200.),
+      Rect::from_min_size(pos2(10., 10.), Vec2::splat(200.)),
+    )
+  }
+
+  #[test]
+  fn fits_centered() {
+    let transform = transform();
+
+    assert_eq!(transform.scale(), 0.5);
+    assert_eq!(transform.image_rect(), Rect::from_min_size(pos2(10., 60.), Vec2::new(200., 100.)));
+  }
+
+  #[test]
+  fn maps_both_ways() {
+    let mut transform = transform();
+    transform.zoom_at(pos2(50., 80.), 4.);
+
+    let pos = pos2(123., 45.);
+    let back = transform.screen_to_image(transform.image_to_screen(pos));
+    assert!((back - pos).length() < 1e-3);
+  }
+
+  #[test]
+  fn zoom_keeps_point_under_cursor() {
+    let mut transform = transform();
+    let cursor = pos2(100., 100.);
+    let before = transform.screen_to_image(cursor);
+
+    transform.zoom_at(cursor, 3.);
+
+    assert_eq!(transform.zoom, 3.);
+    assert!((transform.screen_to_image(cursor) - before).length() < 1e-3);
+  }
+
+  #[test]
+  fn pan_stays_inside_image() {
+    let mut transform = transform();
+    transform.zoom_at(transform.viewport.center(), 2.);
+    transform.pan(Vec2::new(10_000., 0.));
 
-  Vec2 { x: inner.x * resize_factor, y: inner.y * resize_factor }
+    assert_eq!(transform.image_rect().min.x, transform.viewport.min.x);
+  }
 }
@@ -0,0 +1,149 @@
+//! RGB and luminance histograms of preview images, used to judge the exposure in live view
+
+use epaint::{Color32, ColorImage};
+
+pub const BINS: usize = 256;
+
+/// Luminance of a color with the Rec. 709 weights, in the same range as the channels
+pub fn luma(color: Color32) -> u8 {
+  ((54 * color.r() as u32 + 183 * color.g() as u32 + 19 * color.b() as u32) >> 8) as u8
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+  pub red: [u32; BINS],
+  pub green: [u32; BINS],
+  pub blue: [u32; BINS],
+  pub luma: [u32; BINS],
+  pub pixels: u32,
+  /// Pixels that are black in every channel
+  shadows_clipped: u32,
+  /// Pixels that are white in at least one channel
+  highlights_clipped: u32,
+}
+
+impl Histogram {
+  pub fn from_image(image: &ColorImage) -> Self {
+    let mut histogram = Self {
+      red: [0; BINS],
+      green: [0; BINS],
+      blue: [0; BINS],
+      luma: [0; BINS],
+      pixels: image.pixels.len() as u32,
+      shadows_clipped: 0,
+      highlights_clipped: 0,
+    };
+
+    for &pixel in &image.pixels {
+      let [r, g, b, _] = pixel.to_array();
+
+      histogram.red[r as usize] += 1;
+      histogram.green[g as usize] += 1;
+      histogram.blue[b as usize] += 1;
+      histogram.luma[luma(pixel) as usize] += 1;
+
+      if r == 0 && g == 0 && b == 0 {
+        histogram.shadows_clipped += 1;
+      } else if r == u8::MAX || g == u8::MAX || b == u8::MAX {
+        histogram.highlights_clipped += 1;
+      }
+    }
+
+    histogram
+  }
+
+  /// Percentage of the pixels that are clipped to black
+  pub fn shadow_clipping(&self) -> f32 {
+    self.percentage(self.shadows_clipped)
+  }
+
+  /// Percentage of the pixels that are clipped to white in any channel
+  pub fn highlight_clipping(&self) -> f32 {
+    self.percentage(self.highlights_clipped)
+  }
+
+  /// Highest count of all channels without the clipped bins, which would flatten the rest of the
+  /// histogram when a part of the image is clipped
+  pub fn peak(&self) -> u32 {
+    [&self.red, &self.green, &self.blue, &self.luma]
+      .iter()
+      .flat_map(|channel| channel[1..BINS - 1].iter())
+      .copied()
+      .max()
+      .unwrap_or(0)
+  }
+
+  fn percentage(&self, count: u32) -> f32 {
+    if self.pixels == 0 {
+      0.
+    } else {
+      count as f32 * 100. / self.pixels as f32
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn image(colors: &[Color32]) -> ColorImage {
+    ColorImage { size: [colors.len(), 1], pixels: colors.to_vec() }
+  }
+
+  #[test]
+  fn counts_every_channel() {
+    let histogram = Histogram::from_image(&image(&[
+      Color32::from_rgb(10, 20, 30),
+      Color32::from_rgb(10, 0, 255),
+    ]));
+
+    assert_eq!(histogram.pixels, 2);
+    assert_eq!(histogram.red[10], 2);
+    assert_eq!(histogram.green[20], 1);
+    assert_eq!(histogram.green[0], 1);
+    assert_eq!(histogram.blue[30], 1);
+    assert_eq!(histogram.blue[255], 1);
+    assert_eq!(histogram.luma.iter().sum::<u32>(), 2);
+  }
+
+  #[test]
+  fn luma_keeps_the_range() {
+    assert_eq!(luma(Color32::BLACK), 0);
+    assert_eq!(luma(Color32::WHITE), 255);
+    assert!(luma(Color32::from_rgb(0, 255, 0)) > luma(Color32::from_rgb(255, 0, 0)));
+    assert!(luma(Color32::from_rgb(255, 0, 0)) > luma(Color32::from_rgb(0, 0, 255)));
+  }
+
+  #[test]
+  fn clipping_percentages() {
+    let histogram = Histogram::from_image(&image(&[
+      Color32::BLACK,
+      Color32::from_rgb(255, 128, 128),
+      Color32::WHITE,
+      Color32::from_gray(128),
+    ]));
+
+    assert_eq!(histogram.shadow_clipping(), 25.);
+    assert_eq!(histogram.highlight_clipping(), 50.);
+  }
+
+  #[test]
+  fn peak_ignores_clipped_bins() {
+    let histogram = Histogram::from_image(&image(&[
+      Color32::BLACK,
+      Color32::BLACK,
+      Color32::BLACK,
+      Color32::from_gray(100),
+    ]));
+
+    assert_eq!(histogram.peak(), 1);
+  }
+
+  #[test]
+  fn empty_image() {
+    let histogram = Histogram::from_image(&image(&[]));
+
+    assert_eq!(histogram.shadow_clipping(), 0.);
+    assert_eq!(histogram.peak(), 0);
+  }
+}
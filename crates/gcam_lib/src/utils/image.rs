@@ -1,14 +1,100 @@
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
 use epaint::ColorImage;
-use image::{codecs::jpeg::JpegDecoder, DynamicImage};
+use image::{DynamicImage, ImageError, ImageFormat};
 
-/// Decodes a JPG image to a ColorImage
-pub fn decode_image(image: &[u8]) -> AppResult<ColorImage> {
-  let jpg_decoder = JpegDecoder::new(image)?;
-  let image_data = DynamicImage::from_decoder(jpg_decoder)?.to_rgba8();
+/// Detects the format of an image from its magic bytes
+pub fn sniff_format(image: &[u8]) -> AppResult<ImageFormat> {
+  if image.is_empty() {
+    Err(AppError::UnsupportedImage("The image is empty".to_string()))?
+  }
+
+  image::guess_format(image).map_err(|_| {
+    let start: Vec<String> = image.iter().take(8).map(|byte| format!("{:02x}", byte)).collect();
+    AppError::UnsupportedImage(format!("Unknown image format (starts with {})", start.join(" ")))
+  })
+}
+
+/// Whether files of a mime type can be decoded, RAW files and movies can not
+pub fn can_decode_mime_type(mime_type: &str) -> bool {
+  ImageFormat::from_mime_type(mime_type).is_some_and(|format| format.can_read())
+}
+
+/// Decodes an image in any format the `image` crate can read, the format is detected from the
+/// data
+fn load(image: &[u8]) -> AppResult<DynamicImage> {
+  let format = sniff_format(image)?;
 
-  Ok(ColorImage::from_rgba_unmultiplied(
+  image::load_from_memory_with_format(image, format).map_err(|err| match err {
+    ImageError::Unsupported(err) => {
+      AppError::UnsupportedImage(format!("{:?} images can not be decoded: {}", format, err))
+    }
+    err => err.into(),
+  })
+}
+
+fn to_color_image(image: DynamicImage) -> ColorImage {
+  let image_data = image.to_rgba8();
+
+  ColorImage::from_rgba_unmultiplied(
     [image_data.width() as usize, image_data.height() as usize],
     image_data.as_flat_samples().as_slice(),
-  ))
+  )
+}
+
+/// Decodes an image (usually a JPEG preview) to a ColorImage
+pub fn decode_image(image: &[u8]) -> AppResult<ColorImage> {
+  Ok(to_color_image(load(image)?))
+}
+
+/// Decodes an image, downscaled to fit into `max_size` x `max_size`
+pub fn decode_scaled(image: &[u8], max_size: u32) -> AppResult<ColorImage> {
+  let mut image = load(image)?;
+
+  if image.width() > max_size || image.height() > max_size {
+    image = image.thumbnail(max_size, max_size);
+  }
+
+  Ok(to_color_image(image))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use epaint::Color32;
+
+  #[test]
+  fn decodes_ppm() {
+    let mut data = b"P6\n2 1\n255\n".to_vec();
+    data.extend_from_slice(&[255, 0, 0, 0, 0, 255]);
+
+    let image = decode_image(&data).unwrap();
+    assert_eq!(image.size, [2, 1]);
+    assert_eq!(image.pixels, vec![Color32::RED, Color32::BLUE]);
+  }
+
+  #[test]
+  fn decodes_png() {
+    let mut data = vec![];
+    DynamicImage::new_rgb8(3, 2)
+      .write_to(&mut std::io::Cursor::new(&mut data), ImageFormat::Png)
+      .unwrap();
+
+    assert_eq!(sniff_format(&data).unwrap(), ImageFormat::Png);
+    assert_eq!(decode_image(&data).unwrap().size, [3, 2]);
+  }
+
+  #[test]
+  fn decodable_mime_types() {
+    assert!(can_decode_mime_type("image/jpeg"));
+    assert!(can_decode_mime_type("image/tiff"));
+    assert!(!can_decode_mime_type("image/x-canon-cr2"));
+    assert!(!can_decode_mime_type("video/quicktime"));
+    assert!(!can_decode_mime_type(""));
+  }
+
+  #[test]
+  fn unknown_data_is_unsupported() {
+    assert!(matches!(decode_image(b"not an image"), Err(AppError::UnsupportedImage(_))));
+    assert!(matches!(decode_image(&[]), Err(AppError::UnsupportedImage(_))));
+  }
 }
@@ -0,0 +1,243 @@
+//! Importing camera files into a dated folder tree (`YYYY/MM-DD/`)
+//!
+//! Imported files are recorded in an index at the root of the destination folder, one line of tab
+//! separated fields per file (tabs, line breaks and backslashes are escaped), so later imports can
+//! skip them. Lines are appended as soon as a file is written, which
+//! lets an interrupted import continue where it stopped.
+
+use super::time::civil_from_days;
+use crate::error::AppResult;
+use std::{
+  collections::HashSet,
+  fs::{self, OpenOptions},
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+pub const INDEX_FILE_NAME: &str = ".gcam-import-index";
+
+/// Identifies a camera file independently of the folder it is in
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImportKey {
+  pub name: String,
+  pub size: Option<u64>,
+  pub mtime: Option<i64>,
+}
+
+pub struct ImportIndex {
+  path: PathBuf,
+  entries: HashSet<ImportKey>,
+}
+
+impl ImportIndex {
+  /// Reads the index of `directory`, it is empty if nothing was imported there yet
+  pub fn open(directory: &Path) -> AppResult<Self> {
+    let path = directory.join(INDEX_FILE_NAME);
+    let mut entries = HashSet::new();
+
+    if path.is_file() {
+      for line in fs::read_to_string(&path)?.lines() {
+        let mut fields = line.split('\t');
+
+        // Lines that were cut off by a crash are ignored, the file is imported again
+        if let (Some(name), Some(size), Some(mtime), Some(_)) =
+          (fields.next(), fields.next(), fields.next(), fields.next())
+        {
+          entries.insert(ImportKey {
+            name: unescape_field(name),
+            size: size.parse().ok(),
+            mtime: mtime.parse().ok(),
+          });
+        }
+      }
+    }
+
+    Ok(Self { path, entries })
+  }
+
+  pub fn contains(&self, key: &ImportKey) -> bool {
+    self.entries.contains(key)
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Records an imported file
+  pub fn insert(&mut self, key: ImportKey, local_path: &Path) -> AppResult<()> {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let line = format!(
+      "{}\t{}\t{}\t{}\n",
+      escape_field(&key.name),
+      optional(key.size.map(|size| size.to_string())),
+      optional(key.mtime.map(|mtime| mtime.to_string())),
+      escape_field(&local_path.display().to_string())
+    );
+
+    OpenOptions::new().create(true).append(true).open(&self.path)?.write_all(line.as_bytes())?;
+    self.entries.insert(key);
+
+    Ok(())
+  }
+}
+
+fn escape_field(field: &str) -> String {
+  let mut escaped = String::with_capacity(field.len());
+
+  for c in field.chars() {
+    match c {
+      '\\' => escaped.push_str("\\\\"),
+      '\t' => escaped.push_str("\\t"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      c => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+fn unescape_field(field: &str) -> String {
+  let mut unescaped = String::with_capacity(field.len());
+  let mut chars = field.chars();
+
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      unescaped.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('t') => unescaped.push('\t'),
+      Some('n') => unescaped.push('\n'),
+      Some('r') => unescaped.push('\r'),
+      Some(c) => unescaped.push(c),
+      None => unescaped.push('\\'),
+    }
+  }
+
+  unescaped
+}
+
+/// Free path for a file in the dated folder of `timestamp`, a number is appended to the name if
+/// another file already has it
+pub fn import_path(directory: &Path, timestamp: i64, name: &str) -> PathBuf {
+  let (year, month, day) = civil_from_days(timestamp.div_euclid(86400));
+  let folder = directory.join(format!("{:04}", year)).join(format!("{:02}-{:02}", month, day));
+
+  let (stem, extension) = match name.rsplit_once('.') {
+    Some((stem, extension)) => (stem, format!(".{}", extension)),
+    None => (name, String::new()),
+  };
+
+  let mut path = folder.join(name);
+  let mut counter = 1;
+  while path.exists() {
+    path = folder.join(format!("{}_{}{}", stem, counter, extension));
+    counter += 1;
+  }
+
+  path
+}
+
+/// Writes a file through a temporary file, so an interrupted import never leaves a partial file
+/// with the final name
+pub fn write_file(path: &Path, data: &[u8]) -> AppResult<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  let mut partial = path.as_os_str().to_owned();
+  partial.push(".part");
+
+  fs::write(&partial, data)?;
+  fs::rename(&partial, path)?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Empty directory for a test, removed first if a previous run left it behind
+  fn test_directory(name: &str) -> PathBuf {
+    let directory =
+      std::env::temp_dir().join(format!("gcam-import-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+  }
+
+  #[test]
+  fn escaped_fields_round_trip() {
+    for field in ["IMG_0001.JPG", "tab\there", "line\nbreak\r", "back\\slash\\t", "end\\"] {
+      let escaped = escape_field(field);
+
+      assert!(!escaped.contains(['\t', '\n', '\r']));
+      assert_eq!(unescape_field(&escaped), field);
+    }
+  }
+
+  #[test]
+  fn index_round_trips_through_the_file() {
+    let directory = test_directory("index");
+    let keys = [
+      ImportKey { name: "IMG_0001.JPG".into(), size: Some(1234), mtime: Some(1_600_000_000) },
+      ImportKey { name: "odd\tname\n.CR2".into(), size: None, mtime: None },
+    ];
+
+    let mut index = ImportIndex::open(&directory).unwrap();
+    assert!(index.is_empty());
+    for key in &keys {
+      index.insert(key.clone(), &directory.join(&key.name)).unwrap();
+    }
+
+    let reopened = ImportIndex::open(&directory).unwrap();
+    assert_eq!(reopened.len(), 2);
+    for key in &keys {
+      assert!(reopened.contains(key));
+    }
+
+    fs::remove_dir_all(&directory).unwrap();
+  }
+
+  #[test]
+  fn cut_off_lines_are_ignored() {
+    let directory = test_directory("cut-off");
+    fs::write(directory.join(INDEX_FILE_NAME), "a.JPG\t1\t2\t/a.JPG\nb.JPG\t3").unwrap();
+
+    let index = ImportIndex::open(&directory).unwrap();
+    assert_eq!(index.len(), 1);
+    assert!(index.contains(&ImportKey { name: "a.JPG".into(), size: Some(1), mtime: Some(2) }));
+
+    fs::remove_dir_all(&directory).unwrap();
+  }
+
+  #[test]
+  fn import_path_uses_dated_folders_and_free_names() {
+    let directory = test_directory("path");
+    // 2021-03-04 12:00 UTC
+    let timestamp = 1_614_859_200;
+
+    let path = import_path(&directory, timestamp, "IMG_0001.JPG");
+    assert_eq!(path, directory.join("2021").join("03-04").join("IMG_0001.JPG"));
+
+    write_file(&path, b"first").unwrap();
+    let second = import_path(&directory, timestamp, "IMG_0001.JPG");
+    assert_eq!(second, directory.join("2021").join("03-04").join("IMG_0001_1.JPG"));
+
+    write_file(&second, b"second").unwrap();
+    assert_eq!(
+      import_path(&directory, timestamp, "IMG_0001.JPG").file_name().unwrap(),
+      "IMG_0001_2.JPG"
+    );
+    assert_eq!(import_path(&directory, timestamp, "README").file_name().unwrap(), "README");
+
+    fs::remove_dir_all(&directory).unwrap();
+  }
+}
@@ -1,2 +1,11 @@
+pub mod exposure;
+pub mod filename;
 pub mod geom;
+pub mod histogram;
 pub mod image;
+pub mod import;
+pub mod peaking;
+pub mod scopes;
+pub mod sidecar;
+pub mod size;
+pub mod time;
@@ -0,0 +1,69 @@
+//! Focus peaking, marks the sharp edges of an image
+
+use super::histogram::luma;
+use epaint::{Color32, ColorImage};
+
+pub const DEFAULT_THRESHOLD: u8 = 48;
+
+/// Mask of the pixels where the luminance gradient is above `threshold`, edges are white and
+/// everything else is transparent so the mask can be tinted when it is drawn
+///
+/// The gradient is the sum of the horizontal and vertical central differences, a single pass over
+/// the image that is cheap enough to run on every live view frame.
+pub fn focus_peaking(image: &ColorImage, threshold: u8) -> ColorImage {
+  let [width, height] = image.size;
+  let mut mask = ColorImage::new(image.size, Color32::TRANSPARENT);
+
+  if width < 3 || height < 3 {
+    return mask;
+  }
+
+  let luma: Vec<i16> = image.pixels.iter().map(|&pixel| luma(pixel) as i16).collect();
+  let threshold = threshold as i16;
+
+  for y in 1..height - 1 {
+    let row = y * width;
+
+    for index in row + 1..row + width - 1 {
+      let dx = luma[index + 1] - luma[index - 1];
+      let dy = luma[index + width] - luma[index - width];
+
+      if dx.abs() + dy.abs() > threshold {
+        mask.pixels[index] = Color32::WHITE;
+      }
+    }
+  }
+
+  mask
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Image with a black left half and a white right half
+  fn edge_image(width: usize, height: usize) -> ColorImage {
+    let pixels = (0..width * height)
+      .map(|index| if index % width < width / 2 { Color32::BLACK } else { Color32::WHITE })
+      .collect();
+
+    ColorImage { size: [width, height], pixels }
+  }
+
+  #[test]
+  fn marks_edges() {
+    let mask = focus_peaking(&edge_image(8, 4), DEFAULT_THRESHOLD);
+
+    assert_eq!(mask.pixels[8 + 3], Color32::WHITE);
+    assert_eq!(mask.pixels[8 + 4], Color32::WHITE);
+    assert_eq!(mask.pixels[8 + 1], Color32::TRANSPARENT);
+    assert_eq!(mask.pixels[8 + 6], Color32::TRANSPARENT);
+  }
+
+  #[test]
+  fn flat_image_has_no_edges() {
+    let image = ColorImage::new([8, 8], Color32::from_gray(128));
+
+    assert!(focus_peaking(&image, 1).pixels.iter().all(|&pixel| pixel == Color32::TRANSPARENT));
+  }
+}
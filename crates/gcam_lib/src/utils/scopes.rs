@@ -0,0 +1,216 @@
+//! Waveform monitor and vectorscope of preview images
+//!
+//! Both scopes count pixels into a grid and render the counts as a glowing trace, the graticule is
+//! left to the UI.
+
+use super::histogram::luma;
+use epaint::{pos2, Color32, ColorImage, Pos2};
+
+/// Luminance levels of the waveform, one row per level
+pub const LEVELS: usize = 256;
+/// Columns of the waveform, image columns are combined into these
+pub const WAVEFORM_COLUMNS: usize = 256;
+/// Width and height of the vectorscope
+pub const VECTORSCOPE_SIZE: usize = 256;
+
+const TRACE_COLOR: [f32; 3] = [0.6, 1., 0.6];
+
+/// Luminance distribution of every column of the image, bright levels at the top
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Waveform {
+  pub columns: usize,
+  /// Row-major, the first row is the highest level
+  counts: Vec<u32>,
+}
+
+/// Chroma distribution of the image, neutral colors are in the center and saturation grows
+/// towards the edge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vectorscope {
+  pub size: usize,
+  counts: Vec<u32>,
+}
+
+/// Position of a color on the vectorscope with the Rec. 709 color difference components, relative
+/// to the size of the scope: blue is towards the right and red towards the top
+pub fn chroma_position(color: Color32) -> Pos2 {
+  let [r, g, b] = [color.r(), color.g(), color.b()].map(|channel| channel as f32 / 255.);
+  let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+  let cb = (b - y) / 1.8556;
+  let cr = (r - y) / 1.5748;
+
+  pos2(0.5 + cb, 0.5 - cr)
+}
+
+impl Waveform {
+  pub fn from_image(image: &ColorImage, columns: usize) -> Self {
+    let columns = columns.max(1);
+    let width = image.size[0].max(1);
+    let mut counts = vec![0; columns * LEVELS];
+
+    for (index, &pixel) in image.pixels.iter().enumerate() {
+      let column = index % width * columns / width;
+      let row = LEVELS - 1 - luma(pixel) as usize;
+
+      counts[row * columns + column] += 1;
+    }
+
+    Self { columns, counts }
+  }
+
+  /// Pixels of `column` that have the luminance `level`
+  pub fn count(&self, column: usize, level: u8) -> u32 {
+    self.counts[(LEVELS - 1 - level as usize) * self.columns + column]
+  }
+
+  pub fn to_image(&self) -> ColorImage {
+    render(&self.counts, [self.columns, LEVELS])
+  }
+}
+
+impl Vectorscope {
+  pub fn from_image(image: &ColorImage, size: usize) -> Self {
+    let size = size.max(1);
+    let mut counts = vec![0; size * size];
+
+    for &pixel in &image.pixels {
+      let (x, y) = Self::bin(size, pixel);
+      counts[y * size + x] += 1;
+    }
+
+    Self { size, counts }
+  }
+
+  fn bin(size: usize, color: Color32) -> (usize, usize) {
+    let position = chroma_position(color);
+    let cell = |value: f32| ((value * size as f32) as usize).min(size - 1);
+
+    (cell(position.x), cell(position.y))
+  }
+
+  /// Pixels that fall into the same cell as `color`
+  pub fn count(&self, color: Color32) -> u32 {
+    let (x, y) = Self::bin(self.size, color);
+    self.counts[y * self.size + x]
+  }
+
+  pub fn to_image(&self) -> ColorImage {
+    render(&self.counts, [self.size, self.size])
+  }
+}
+
+/// Renders counts as a trace on a transparent background, with a logarithmic brightness so sparse
+/// parts of the image remain visible
+fn render(counts: &[u32], size: [usize; 2]) -> ColorImage {
+  let max = counts.iter().copied().max().unwrap_or(0);
+  let scale = (1. + max as f32).ln().max(f32::EPSILON);
+
+  let pixels = counts
+    .iter()
+    .map(|&count| {
+      if count == 0 {
+        return Color32::TRANSPARENT;
+      }
+
+      let intensity = (0.25 + 0.75 * (1. + count as f32).ln() / scale).min(1.);
+      let [r, g, b] = TRACE_COLOR.map(|channel| (channel * intensity * 255.) as u8);
+
+      Color32::from_rgba_premultiplied(r, g, b, (intensity * 255.) as u8)
+    })
+    .collect();
+
+  ColorImage { size, pixels }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BAR_LEVEL: u8 = 191;
+  const BAR_WIDTH: usize = 10;
+  const HEIGHT: usize = 4;
+
+  /// 75 % color bars: white, yellow, cyan, green, magenta, red and blue
+  fn bar_colors() -> [Color32; 7] {
+    let (on, off) = (BAR_LEVEL, 0);
+
+    [
+      Color32::from_rgb(on, on, on),
+      Color32::from_rgb(on, on, off),
+      Color32::from_rgb(off, on, on),
+      Color32::from_rgb(off, on, off),
+      Color32::from_rgb(on, off, on),
+      Color32::from_rgb(on, off, off),
+      Color32::from_rgb(off, off, on),
+    ]
+  }
+
+  fn color_bars() -> ColorImage {
+    let colors = bar_colors();
+    let width = colors.len() * BAR_WIDTH;
+    let pixels = (0..width * HEIGHT).map(|index| colors[index % width / BAR_WIDTH]).collect();
+
+    ColorImage { size: [width, HEIGHT], pixels }
+  }
+
+  #[test]
+  fn waveform_of_color_bars() {
+    let colors = bar_colors();
+    let waveform = Waveform::from_image(&color_bars(), colors.len());
+
+    for (column, color) in colors.into_iter().enumerate() {
+      let level = luma(color);
+
+      assert_eq!(waveform.count(column, level), (BAR_WIDTH * HEIGHT) as u32);
+      assert_eq!(
+        (0..=u8::MAX).map(|level| waveform.count(column, level)).sum::<u32>(),
+        (BAR_WIDTH * HEIGHT) as u32,
+        "every pixel of the bar is in its column"
+      );
+    }
+
+    // The luminance of the bars decreases from white to blue
+    let levels: Vec<u8> = bar_colors().into_iter().map(luma).collect();
+    assert!(levels.windows(2).all(|pair| pair[0] > pair[1]));
+  }
+
+  #[test]
+  fn waveform_image_has_high_levels_at_the_top() {
+    let image = ColorImage { size: [2, 1], pixels: vec![Color32::WHITE, Color32::BLACK] };
+    let rendered = Waveform::from_image(&image, 2).to_image();
+
+    assert_eq!(rendered.size, [2, LEVELS]);
+    assert_ne!(rendered.pixels[0], Color32::TRANSPARENT);
+    assert_eq!(rendered.pixels[1], Color32::TRANSPARENT);
+    assert_ne!(rendered.pixels[(LEVELS - 1) * 2 + 1], Color32::TRANSPARENT);
+  }
+
+  #[test]
+  fn vectorscope_of_color_bars() {
+    let vectorscope = Vectorscope::from_image(&color_bars(), VECTORSCOPE_SIZE);
+    let colors = bar_colors();
+
+    for color in colors {
+      assert_eq!(vectorscope.count(color), (BAR_WIDTH * HEIGHT) as u32);
+    }
+
+    // White is neutral, the colors are placed around it
+    let center = chroma_position(colors[0]);
+    assert!((center - pos2(0.5, 0.5)).length() < 1e-3);
+
+    let red = chroma_position(colors[5]);
+    let blue = chroma_position(colors[6]);
+    let green = chroma_position(colors[3]);
+    assert!(red.y < 0.5 && red.x < 0.5);
+    assert!(blue.x > 0.5);
+    assert!(green.x < 0.5 && green.y > 0.5);
+
+    // Complementary colors are opposite of each other
+    for (color, complement) in
+      [(colors[5], colors[2]), (colors[3], colors[4]), (colors[6], colors[1])]
+    {
+      let sum = chroma_position(color).to_vec2() + chroma_position(complement).to_vec2();
+      assert!((sum - pos2(1., 1.).to_vec2()).length() < 1e-3);
+    }
+  }
+}
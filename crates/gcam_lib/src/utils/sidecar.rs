@@ -0,0 +1,82 @@
+//! XMP sidecar files, used to attach GCam specific metadata to downloaded pictures without
+//! rewriting the pictures themselves
+
+use crate::error::AppResult;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+const GCAM_NAMESPACE: &str = "https://github.com/maxicarlos08/gcam/ns/1.0/";
+
+/// Path of the sidecar of a picture, `IMG_0001.JPG` becomes `IMG_0001.JPG.xmp`. The extension is
+/// kept, so the JPEG and the RAW file of a RAW+JPEG capture get their own sidecar.
+pub fn sidecar_path(image: &Path) -> PathBuf {
+  let mut name = image.file_name().unwrap_or_default().to_os_string();
+  name.push(".xmp");
+
+  image.with_file_name(name)
+}
+
+/// Writes the given properties into the sidecar of `image`, in the `gcam` namespace
+pub fn write_sidecar(image: &Path, properties: &[(&str, String)]) -> AppResult<PathBuf> {
+  let mut attributes = String::new();
+  for (name, value) in properties {
+    attributes.push_str(&format!("\n      gcam:{}=\"{}\"", name, escape(value)));
+  }
+
+  let xmp = format!(
+    r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+    <rdf:Description rdf:about=""
+      xmlns:gcam="{}"{}/>
+  </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>
+"#,
+    GCAM_NAMESPACE, attributes
+  );
+
+  let path = sidecar_path(image);
+  fs::write(&path, xmp)?;
+
+  Ok(path)
+}
+
+fn escape(value: &str) -> String {
+  value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sidecar_paths() {
+    assert_eq!(sidecar_path(Path::new("/a/IMG_0001.JPG")), Path::new("/a/IMG_0001.JPG.xmp"));
+    assert_eq!(sidecar_path(Path::new("/a/IMG_0001.CR2")), Path::new("/a/IMG_0001.CR2.xmp"));
+    assert_eq!(sidecar_path(Path::new("IMG_0001")), Path::new("IMG_0001.xmp"));
+  }
+
+  #[test]
+  fn escapes_values() {
+    assert_eq!(escape(r#"a & "b" <c>"#), "a &amp; &quot;b&quot; &lt;c&gt;");
+  }
+
+  #[test]
+  fn writes_properties() {
+    let directory = std::env::temp_dir().join(format!("gcam-sidecar-test-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let image = directory.join("IMG_0001.JPG");
+
+    let path =
+      write_sidecar(&image, &[("FocusStackStep", "3".into()), ("Note", "<&>".into())]).unwrap();
+    let xmp = fs::read_to_string(&path).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(path, directory.join("IMG_0001.JPG.xmp"));
+    assert!(xmp.contains(r#"gcam:FocusStackStep="3""#));
+    assert!(xmp.contains(r#"gcam:Note="&lt;&amp;&gt;""#));
+  }
+}
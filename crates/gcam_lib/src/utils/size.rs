@@ -0,0 +1,17 @@
+/// Formats a size in bytes for humans, eg. `512 B`, `1.5 KiB` or `23.1 MiB`
+pub fn format_size(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+  if bytes < 1024 {
+    return format!("{} B", bytes);
+  }
+
+  let mut size = bytes as f64 / 1024.;
+  let mut unit = 0;
+  while size >= 1024. && unit < UNITS.len() - 1 {
+    size /= 1024.;
+    unit += 1;
+  }
+
+  format!("{:.1} {}", size, UNITS[unit])
+}
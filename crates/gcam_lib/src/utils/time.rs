@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+/// Formats a duration for humans, eg. `1h 02m 03s`, `2m 05s` or `4.2s`
+pub fn format_duration(duration: Duration) -> String {
+  let seconds = duration.as_secs();
+
+  if seconds >= 3600 {
+    format!("{}h {:02}m {:02}s", seconds / 3600, seconds / 60 % 60, seconds % 60)
+  } else if seconds >= 60 {
+    format!("{}m {:02}s", seconds / 60, seconds % 60)
+  } else {
+    format!("{:.1}s", duration.as_secs_f32())
+  }
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DD HH:MM` (UTC)
+pub fn format_timestamp(timestamp: i64) -> String {
+  let (year, month, day) = civil_from_days(timestamp.div_euclid(86400));
+  let seconds_of_day = timestamp.rem_euclid(86400);
+
+  format!(
+    "{:04}-{:02}-{:02} {:02}:{:02}",
+    year,
+    month,
+    day,
+    seconds_of_day / 3600,
+    seconds_of_day / 60 % 60
+  )
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DD HH:MM:SS` (UTC)
+pub fn format_timestamp_seconds(timestamp: i64) -> String {
+  format!("{}:{:02}", format_timestamp(timestamp), timestamp.rem_euclid(60))
+}
+
+/// Formats the time of day of a unix timestamp as `HH:MM:SS` (UTC)
+pub fn format_time_of_day(timestamp: i64) -> String {
+  let seconds_of_day = timestamp.rem_euclid(86400);
+
+  format!("{:02}:{:02}:{:02}", seconds_of_day / 3600, seconds_of_day / 60 % 60, seconds_of_day % 60)
+}
+
+/// Next unix timestamp after `now` at which the local time is `seconds_of_day`, today or
+/// tomorrow. The local time is `utc_offset` seconds ahead of UTC.
+pub fn next_time_of_day(now: i64, seconds_of_day: i64, utc_offset: i64) -> i64 {
+  let local_now = now + utc_offset;
+  let time = local_now - local_now.rem_euclid(86400) + seconds_of_day;
+
+  if time > local_now {
+    time - utc_offset
+  } else {
+    time + 86400 - utc_offset
+  }
+}
+
+/// Offset of the local time zone from UTC in seconds at a unix timestamp, including daylight
+/// saving time
+#[cfg(unix)]
+pub fn local_utc_offset(timestamp: i64) -> i64 {
+  let time = timestamp as libc::time_t;
+  // SAFETY: `tm` is plain data, `localtime_r` only writes to it
+  let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+
+  if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+    0
+  } else {
+    tm.tm_gmtoff as i64
+  }
+}
+
+/// Offset of the local time zone from UTC, it is only known on unix
+#[cfg(not(unix))]
+pub fn local_utc_offset(_timestamp: i64) -> i64 {
+  0
+}
+
+/// Converts days since the unix epoch to a (year, month, day) date
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+
+  (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn next_time_of_day_today_or_tomorrow() {
+    let now = 86400 * 10 + 12 * 3600;
+
+    assert_eq!(next_time_of_day(now, 18 * 3600, 0), 86400 * 10 + 18 * 3600);
+    assert_eq!(next_time_of_day(now, 6 * 3600, 0), 86400 * 11 + 6 * 3600);
+    assert_eq!(next_time_of_day(now, 12 * 3600, 0), 86400 * 11 + 12 * 3600);
+  }
+
+  #[test]
+  fn next_time_of_day_across_midnight() {
+    // 23:30 local time at UTC+2, 21:30 UTC
+    let now = 86400 * 10 + 21 * 3600 + 1800;
+    let offset = 2 * 3600;
+
+    // 00:15 local time is 22:15 UTC of the same UTC day
+    assert_eq!(next_time_of_day(now, 15 * 60, offset), 86400 * 10 + 22 * 3600 + 15 * 60);
+    // 23:00 local time has passed, it is 21:00 UTC of the next day
+    assert_eq!(next_time_of_day(now, 23 * 3600, offset), 86400 * 11 + 21 * 3600);
+
+    // 01:00 UTC is 20:00 of the previous day at UTC-5, 06:00 local time is on the next local day
+    let now = 86400 * 10 + 3600;
+    let offset = -5 * 3600;
+    assert_eq!(next_time_of_day(now, 6 * 3600, offset), 86400 * 10 + 11 * 3600);
+    assert_eq!(next_time_of_day(now, 21 * 3600, offset), 86400 * 10 + 2 * 3600);
+  }
+
+  #[test]
+  fn civil_dates() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    // Leap days, including the 400 year rule
+    assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    assert_eq!(civil_from_days(19417), (2023, 3, 1));
+    assert_eq!(civil_from_days(19051), (2022, 2, 28));
+    assert_eq!(civil_from_days(47541), (2100, 3, 1));
+  }
+
+  #[test]
+  fn timestamps() {
+    assert_eq!(format_timestamp(1_663_142_709), "2022-09-14 08:05");
+    assert_eq!(format_timestamp_seconds(1_663_142_709), "2022-09-14 08:05:09");
+    assert_eq!(format_time_of_day(-1), "23:59:59");
+  }
+}
@@ -1,7 +1,8 @@
 //! This example is only used to debug the Toggle widget renderer
 
 use eframe::{egui::CentralPanel, run_native};
-use gcam::{cam_thread::settings::StaticWidget, ui::widgets::toggle};
+use gcam::ui::widgets::toggle;
+use gcam_lib::camera::settings::StaticWidget;
 
 struct ToggleExample {
   toggle: StaticWidget,
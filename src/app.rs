@@ -3,9 +3,6 @@ use crate::{
   ui::{components, state::AppState, views, windows},
 };
 use eframe::egui::Context;
-use std::time::Duration;
-
-const REPAINT_INTERVAL: Duration = Duration::from_millis(100);
 
 impl eframe::App for AppState {
   fn update(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
@@ -21,6 +18,8 @@ impl eframe::App for AppState {
       windows::settings::show(ctx, self);
     }
 
+    windows::preset::show(ctx, self);
+
     {
       let mut pop_error = false;
 
@@ -34,8 +33,6 @@ impl eframe::App for AppState {
         self.errors.pop();
       }
     }
-
-    ctx.request_repaint_after(REPAINT_INTERVAL);
   }
 
   fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
@@ -0,0 +1,88 @@
+//! Exposure bracketing, takes a series of pictures while stepping through the choices of an
+//! exposure setting
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{BracketOptions, CapturedImage, MessageFromThread},
+};
+use gcam_lib::{
+  camera::{
+    bracketing::{plan_bracket, BracketPlan},
+    settings::{CameraSettings, RadioChoice, StaticWidget},
+  },
+  error::AppResult,
+};
+
+impl CameraThreadState {
+  /// Captures a bracket and restores the original value of the setting, even if a frame failed
+  ///
+  /// Frames downloaded before a failure are reported as single captures.
+  pub(crate) fn capture_bracket(
+    &mut self,
+    options: &BracketOptions,
+  ) -> AppResult<Vec<CapturedImage>> {
+    let name = options.setting.setting_name();
+    // The cached settings of the UI might be outdated, the exposure mode decides what is writable
+    let setting = self
+      .camera()?
+      .config()?
+      .find(name)
+      .cloned()
+      .ok_or_else(|| format!("The camera has no {} setting", name))?;
+    let plan = plan_bracket(&setting, options.setting, options.frames, options.spacing)?;
+
+    log::info!(
+      "Bracketing {} frames on {} in steps of {:.2} stops",
+      plan.frames.len(),
+      name,
+      plan.step
+    );
+
+    let mut current = setting.clone();
+    let mut captured = Vec::with_capacity(plan.frames.len());
+    let result = self.capture_bracket_frames(&mut current, &plan, options, &mut captured);
+    let restored = self.set_choice(&mut current, plan.original);
+
+    if let Err(err) = result.and(restored) {
+      self.outbox.extend(captured.into_iter().map(MessageFromThread::ImageCaptured));
+      return Err(err);
+    }
+
+    Ok(captured)
+  }
+
+  fn capture_bracket_frames(
+    &mut self,
+    current: &mut CameraSettings,
+    plan: &BracketPlan,
+    options: &BracketOptions,
+    captured: &mut Vec<CapturedImage>,
+  ) -> AppResult<()> {
+    let mut capture = options.capture.clone();
+
+    for (choice, offset) in &plan.frames {
+      log::debug!("Bracket frame at {:+.2} stops", offset);
+
+      self.set_choice(current, *choice)?;
+      let image = self.capture_image(&capture)?;
+      capture.sequence = image.sequence + 1;
+      captured.push(image);
+    }
+
+    Ok(())
+  }
+
+  /// Selects a choice of the setting, `current` is the value on the camera and is updated
+  fn set_choice(&mut self, current: &mut CameraSettings, choice: usize) -> AppResult<()> {
+    let mut setting = current.clone();
+
+    if let StaticWidget::Radio { choice: selected, .. } = &mut setting.widget {
+      *selected = RadioChoice::Indexed(choice);
+    }
+
+    self.write_sequence_setting("Bracketing", current, &setting)?;
+    *current = setting;
+
+    Ok(())
+  }
+}
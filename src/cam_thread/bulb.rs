@@ -0,0 +1,333 @@
+//! Bulb exposures timed by the camera thread, for exposures longer than the slowest shutter speed
+//!
+//! The camera is switched to bulb, the shutter is opened through the remote release (or the `bulb`
+//! toggle of cameras that have one) and closed again once the duration has passed. The picture is
+//! downloaded when the camera reports the new file, then the original exposure settings are
+//! restored.
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{BulbOptions, BulbState, BulbStatus, MessageFromThread},
+  MessageSender,
+};
+use gcam_lib::{
+  backend::{CameraEvent, CameraFilePath},
+  camera::settings::{CameraSettings, RadioChoice, StaticWidget},
+  error::AppResult,
+};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the camera to report the picture after closing the shutter, long exposure
+/// noise reduction adds the exposure time on top of this
+const FILE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Timeout of a single `wait_event` call while waiting for the picture
+const EVENT_POLL: Duration = Duration::from_millis(200);
+/// Source of the setting changes in the history
+const SEQUENCE_NAME: &str = "Bulb";
+/// Delay before the first retry to close the shutter, doubled after every failure
+const CLOSE_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+/// Longest delay between two retries to close the shutter
+const MAX_CLOSE_RETRY_INTERVAL: Duration = Duration::from_secs(8);
+
+pub struct BulbExposure {
+  options: BulbOptions,
+  /// Settings that were changed to get into bulb mode, as `(original, bulb)`
+  restore: Vec<(CameraSettings, CameraSettings)>,
+  close: CameraSettings,
+  opened_at: Instant,
+  /// The shutter is closed before the duration has passed, because the exposure was stopped or
+  /// closing it failed
+  close_requested: bool,
+  /// Failed attempts to close the shutter, the shutter may still be open while this is not zero
+  close_failures: u32,
+  /// When the camera thread tries to close the shutter again after a failure
+  retry_close_at: Option<Instant>,
+  /// Set once the shutter is closed, the picture is downloaded afterwards
+  closed_at: Option<Instant>,
+}
+
+impl BulbExposure {
+  pub fn status(&self) -> BulbStatus {
+    let exposed = match self.closed_at {
+      Some(closed_at) => closed_at.duration_since(self.opened_at),
+      None => self.opened_at.elapsed(),
+    };
+
+    let state = match self.closed_at {
+      Some(_) => BulbState::Downloading,
+      None if self.close_failures > 0 => BulbState::CloseFailed,
+      None => BulbState::Exposing,
+    };
+
+    BulbStatus { state, duration: self.options.duration, exposed }
+  }
+
+  /// Time until the camera thread has to close the shutter or look for the picture
+  pub fn time_until_due(&self) -> Duration {
+    match self.closed_at {
+      Some(_) => Duration::ZERO,
+      None => match self.retry_close_at {
+        Some(retry_close_at) => retry_close_at.saturating_duration_since(Instant::now()),
+        None if self.close_requested => Duration::ZERO,
+        None => self.options.duration.saturating_sub(self.opened_at.elapsed()),
+      },
+    }
+  }
+
+  /// Schedules another attempt to close the shutter, with a longer delay after every failure
+  fn close_failed(&mut self) {
+    let backoff = CLOSE_RETRY_INTERVAL * 2u32.pow(self.close_failures.min(4));
+    self.close_requested = true;
+    self.close_failures += 1;
+    self.retry_close_at = Some(Instant::now() + backoff.min(MAX_CLOSE_RETRY_INTERVAL));
+  }
+}
+
+impl CameraThreadState {
+  pub(crate) fn start_bulb(&mut self, options: BulbOptions) -> AppResult<BulbStatus> {
+    if self.bulb.is_some() {
+      Err("A bulb exposure is already running")?
+    }
+
+    let config = self.camera()?.config()?;
+
+    let (open, close) = if let Some(release) = config.find("eosremoterelease") {
+      (with_choice(release, "Press Full")?, with_choice(release, "Release Full")?)
+    } else if let Some(bulb) = config.find("bulb") {
+      (with_toggle(bulb, true)?, with_toggle(bulb, false)?)
+    } else {
+      Err("The camera has no setting to open the shutter in bulb mode")?
+    };
+
+    let mut restore = vec![];
+    if !is_choice(config.find("shutterspeed"), "bulb")
+      && !is_choice(config.find("autoexposuremode"), "bulb")
+    {
+      let bulb_setting = ["shutterspeed", "autoexposuremode"]
+        .into_iter()
+        .filter_map(|name| config.find(name))
+        .find_map(|setting| {
+          if setting.readonly {
+            None
+          } else {
+            with_choice(setting, "bulb").ok().map(|bulb| (setting, bulb))
+          }
+        });
+
+      let (original, bulb) = bulb_setting
+        .ok_or("The camera can not be switched to bulb, set the exposure mode to bulb manually")?;
+      self.write_sequence_setting(SEQUENCE_NAME, original, &bulb)?;
+      restore.push((original.clone(), bulb));
+    }
+
+    if let Err(err) = self.camera()?.set_config(&open) {
+      self.restore_settings(&restore);
+      return Err(err);
+    }
+
+    log::info!("Opened the shutter for {:?}", options.duration);
+
+    let bulb = BulbExposure {
+      options,
+      restore,
+      close,
+      opened_at: Instant::now(),
+      close_requested: false,
+      close_failures: 0,
+      retry_close_at: None,
+      closed_at: None,
+    };
+    let status = bulb.status();
+    self.bulb = Some(bulb);
+
+    Ok(status)
+  }
+
+  /// Closes the shutter before the duration has passed, the picture is still downloaded
+  pub(crate) fn stop_bulb(&mut self) -> AppResult<BulbStatus> {
+    let mut bulb = self.bulb.take().ok_or("No bulb exposure is running")?;
+
+    if bulb.closed_at.is_none() {
+      if let Err(err) = self.close_shutter(&mut bulb) {
+        // The exposure is kept, so `run_bulb` tries to close the shutter again
+        bulb.close_failed();
+        self.outbox.push(MessageFromThread::Bulb(bulb.status()));
+        self.bulb = Some(bulb);
+        return Err(format!("Failed to close the shutter, it may still be open: {}", err).into());
+      }
+    }
+
+    let status = bulb.status();
+    self.bulb = Some(bulb);
+
+    Ok(status)
+  }
+
+  /// Closes the shutter and restores the settings of a running exposure, before the camera is
+  /// closed or another one is opened. The picture is not downloaded anymore.
+  pub(crate) fn abort_bulb(&mut self) {
+    let mut bulb = match self.bulb.take() {
+      Some(bulb) => bulb,
+      None => return,
+    };
+
+    if self.camera.is_some() {
+      if bulb.closed_at.is_none() {
+        if let Err(err) = self.close_shutter(&mut bulb) {
+          self.outbox.push(MessageFromThread::Error {
+            id: None,
+            error: format!("Failed to close the shutter, it may still be open: {}", err).into(),
+          });
+        }
+      }
+
+      self.restore_settings(&bulb.restore);
+    }
+
+    self
+      .outbox
+      .push(MessageFromThread::Bulb(BulbStatus { state: BulbState::Failed, ..bulb.status() }));
+  }
+
+  fn close_shutter(&mut self, bulb: &mut BulbExposure) -> AppResult<()> {
+    self.camera()?.set_config(&bulb.close)?;
+    bulb.closed_at = Some(Instant::now());
+
+    log::info!("Closed the shutter after {:?}", bulb.opened_at.elapsed());
+
+    if bulb.close_failures > 0 {
+      // The UI still shows that the shutter may be open
+      self.outbox.push(MessageFromThread::Bulb(bulb.status()));
+    }
+
+    Ok(())
+  }
+
+  /// Closes the shutter when the exposure is done and downloads the picture once it is saved
+  pub(crate) fn run_bulb(&mut self, send: &MessageSender) -> AppResult<()> {
+    let mut bulb = match self.bulb.take() {
+      Some(bulb) if bulb.time_until_due().is_zero() => bulb,
+      bulb => {
+        self.bulb = bulb;
+        return Ok(());
+      }
+    };
+
+    match self.advance_bulb(&mut bulb) {
+      Ok(None) => {
+        self.bulb = Some(bulb);
+        return Ok(());
+      }
+      Ok(Some(path)) => {
+        let captured = self.download_capture(path, &bulb.options.capture);
+        self.restore_settings(&bulb.restore);
+
+        let state = if captured.is_ok() { BulbState::Finished } else { BulbState::Failed };
+        send.send(MessageFromThread::Bulb(BulbStatus { state, ..bulb.status() }))?;
+        send.send(MessageFromThread::ImageCaptured(captured?))?;
+      }
+      Err(err) if bulb.closed_at.is_none() && self.camera.is_some() => {
+        // The shutter may still be open, the exposure is kept until closing it succeeds
+        bulb.close_failed();
+        let status = bulb.status();
+        let first_failure = bulb.close_failures == 1;
+        self.bulb = Some(bulb);
+
+        if !first_failure {
+          log::warn!("Failed to close the shutter again: {}", err);
+          return Ok(());
+        }
+
+        send.send(MessageFromThread::Bulb(status))?;
+        Err(format!("Failed to close the shutter, it may still be open: {}", err))?
+      }
+      Err(err) => {
+        // Settings can not be restored once the camera is gone
+        if self.camera.is_some() {
+          self.restore_settings(&bulb.restore);
+        }
+
+        send.send(MessageFromThread::Bulb(BulbStatus {
+          state: BulbState::Failed,
+          ..bulb.status()
+        }))?;
+        Err(err)?
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Returns the picture once the camera saved it
+  fn advance_bulb(&mut self, bulb: &mut BulbExposure) -> AppResult<Option<CameraFilePath>> {
+    let closed_at = match bulb.closed_at {
+      Some(closed_at) => closed_at,
+      None => {
+        self.close_shutter(bulb)?;
+        return Ok(None);
+      }
+    };
+
+    if let Some(CameraEvent::NewFile(path)) = self.camera()?.wait_event(EVENT_POLL)? {
+      return Ok(Some(path));
+    }
+
+    if closed_at.elapsed() > FILE_TIMEOUT + bulb.options.duration {
+      Err("The camera did not save the bulb exposure")?
+    }
+
+    Ok(None)
+  }
+
+  fn restore_settings(&mut self, settings: &[(CameraSettings, CameraSettings)]) {
+    for (original, bulb) in settings {
+      if let Err(err) = self.write_sequence_setting(SEQUENCE_NAME, bulb, original) {
+        log::warn!("Failed to restore {} after the bulb exposure: {}", original.name, err);
+      }
+    }
+  }
+}
+
+fn is_choice(setting: Option<&CameraSettings>, value: &str) -> bool {
+  match setting.map(|setting| &setting.widget) {
+    Some(StaticWidget::Radio { choices, choice: RadioChoice::Indexed(index) }) => {
+      choices[*index].eq_ignore_ascii_case(value)
+    }
+    Some(StaticWidget::Radio { choice: RadioChoice::Other(choice), .. }) => {
+      choice.eq_ignore_ascii_case(value)
+    }
+    _ => false,
+  }
+}
+
+/// Copy of a radio setting with `value` selected
+fn with_choice(setting: &CameraSettings, value: &str) -> AppResult<CameraSettings> {
+  let mut setting = setting.clone();
+
+  match &mut setting.widget {
+    StaticWidget::Radio { choices, choice } => {
+      let index = choices
+        .iter()
+        .position(|choice| choice.eq_ignore_ascii_case(value))
+        .ok_or_else(|| format!("{} has no choice {}", setting.name, value))?;
+      *choice = RadioChoice::Indexed(index);
+    }
+    _ => Err(format!("Expected {} to be a radio widget", setting.name))?,
+  }
+
+  Ok(setting)
+}
+
+fn with_toggle(setting: &CameraSettings, value: bool) -> AppResult<CameraSettings> {
+  let mut setting = setting.clone();
+
+  match &mut setting.widget {
+    StaticWidget::Toggle { undefined, value: toggled } => {
+      *undefined = false;
+      *toggled = value;
+    }
+    _ => Err(format!("Expected {} to be a toggle widget", setting.name))?,
+  }
+
+  Ok(setting)
+}
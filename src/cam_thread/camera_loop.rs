@@ -1,50 +1,138 @@
-use crate::cam_thread::messages::{MessageFromThread, MessageToThread, PreviewImage};
-use crossbeam_channel::{Receiver, Sender, TryRecvError};
-use gcam_lib::{error::AppResult, utils};
-use gphoto2::{Camera, Context, Result};
+use crate::cam_thread::{
+  bulb::BulbExposure,
+  events::EventListener,
+  focus_stack::FocusStack,
+  hotplug::Hotplug,
+  import::Import,
+  intervalometer::Intervalometer,
+  live_view::{LiveViewPipeline, DEFAULT_TARGET_FPS},
+  messages::{CameraRequest, MessageFromThread, MessageToThread, PreviewAnalysis},
+  MessageSender,
+};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use gcam_lib::{
+  backend::{
+    gphoto::GPhotoBackend, simulated::SimulatedBackend, BackendCamera, CameraBackend,
+    CameraDescriptor,
+  },
+  error::AppResult,
+};
+use std::time::{Duration, Instant};
 
 pub struct CameraThreadState {
-  pub context: Context,
-  pub camera: Option<Camera>,
+  pub backend: Box<dyn CameraBackend>,
+  pub camera: Option<Box<dyn BackendCamera>>,
+  pub camera_descriptor: Option<CameraDescriptor>,
   pub capturing_live_view: bool,
+  pub preview_analysis: PreviewAnalysis,
+  pub live_view: LiveViewPipeline,
+  /// Time between two live view frames, from the target frame rate
+  pub preview_interval: Duration,
+  pub next_preview_at: Instant,
+  pub hotplug: Hotplug,
+  pub intervalometer: Option<Intervalometer>,
+  pub focus_stack: Option<FocusStack>,
+  pub bulb: Option<BulbExposure>,
+  pub import: Option<Import>,
+  pub events: EventListener,
+  /// Messages of commands and tasks besides their response, eg. settings written by sequences, sent
+  /// before the response of the command or the next command is received
+  pub outbox: Vec<MessageFromThread>,
 }
 
 pub(crate) fn camera_loop(
+  simulated: bool,
   message_receiver: Receiver<MessageToThread>,
-  message_sender: Sender<MessageFromThread>,
-) -> Result<()> {
-  let mut camera_thread_state =
-    CameraThreadState { context: Context::new()?, camera: None, capturing_live_view: false };
+  message_sender: MessageSender,
+  live_view: LiveViewPipeline,
+) -> AppResult<()> {
+  let backend: Box<dyn CameraBackend> =
+    if simulated { Box::new(SimulatedBackend) } else { Box::new(GPhotoBackend::new()?) };
+
+  let mut camera_thread_state = CameraThreadState {
+    backend,
+    camera: None,
+    camera_descriptor: None,
+    capturing_live_view: false,
+    preview_analysis: Default::default(),
+    live_view,
+    preview_interval: frame_interval(DEFAULT_TARGET_FPS),
+    next_preview_at: Instant::now(),
+    hotplug: Default::default(),
+    intervalometer: None,
+    focus_stack: None,
+    bulb: None,
+    import: None,
+    events: Default::default(),
+    outbox: vec![],
+  };
 
   loop {
-    let action = if camera_thread_state.capturing_live_view {
-      match message_receiver.try_recv() {
-        Err(TryRecvError::Empty) => {
-          if let Err(err) = capture_preview(&mut camera_thread_state, &message_sender) {
-            message_sender.send(MessageFromThread::Error(err)).unwrap();
-          };
+    if camera_thread_state.hotplug.time_until_poll().is_zero() {
+      if let Err(err) = camera_thread_state.poll_ports(&message_sender) {
+        log::warn!("Failed to poll camera ports: {}", err);
+      }
+    }
 
-          std::thread::sleep(std::time::Duration::from_millis(20));
+    if let Err(err) = camera_thread_state.run_intervalometer(&message_sender) {
+      message_sender.send(MessageFromThread::Error { id: None, error: err }).unwrap();
+    }
 
-          continue;
-        }
-        Err(TryRecvError::Disconnected) => Err("Sender seems to be dead")?,
-        Ok(action) => action,
-      }
-    } else {
-      message_receiver.recv().map_err(|_| "Sender seems to be dead")?
+    if let Err(err) = camera_thread_state.run_focus_stack(&message_sender) {
+      message_sender.send(MessageFromThread::Error { id: None, error: err }).unwrap();
+    }
+
+    if let Err(err) = camera_thread_state.run_bulb(&message_sender) {
+      message_sender.send(MessageFromThread::Error { id: None, error: err }).unwrap();
+    }
+
+    if let Err(err) = camera_thread_state.run_import(&message_sender) {
+      message_sender.send(MessageFromThread::Error { id: None, error: err }).unwrap();
+    }
+
+    if let Err(err) = camera_thread_state.run_events(&message_sender) {
+      log::warn!("Failed to read camera events: {}", err);
+    }
+
+    if camera_thread_state.capturing_live_view
+      && camera_thread_state.next_preview_at <= Instant::now()
+    {
+      camera_thread_state.next_preview_at = Instant::now() + camera_thread_state.preview_interval;
+
+      if let Err(err) = capture_preview(&mut camera_thread_state, &message_sender) {
+        message_sender.send(MessageFromThread::Error { id: None, error: err }).unwrap();
+      };
+    }
+
+    for message in camera_thread_state.outbox.drain(..) {
+      message_sender.send(message).unwrap();
+    }
+
+    let action = match message_receiver.recv_timeout(camera_thread_state.time_until_next_task()) {
+      Err(RecvTimeoutError::Timeout) => continue,
+      Err(RecvTimeoutError::Disconnected) => Err("Sender seems to be dead")?,
+      Ok(action) => action,
     };
 
     match action {
-      MessageToThread::SetLiveView(lv) => camera_thread_state.capturing_live_view = lv,
       MessageToThread::Break => break,
-      MessageToThread::Closure(fun) => {
-        message_sender
-          .send(match fun(&mut camera_thread_state) {
-            Ok(back_action) => MessageFromThread::Closure(back_action),
-            Err(err) => MessageFromThread::Error(err),
-          })
-          .unwrap();
+      MessageToThread::Command(CameraRequest { id, command }) => {
+        log::debug!("Executing command #{}: {:?}", id, command);
+
+        let message = match camera_thread_state.execute(command) {
+          Ok(response) => MessageFromThread::Response { id, response },
+          Err(error) => {
+            log::debug!("Command #{} failed: {}", id, error);
+            MessageFromThread::Error { id: Some(id), error }
+          }
+        };
+
+        // Messages of the command come first, eg. the statuses of sequences stopped by opening
+        // another camera
+        for message in camera_thread_state.outbox.drain(..) {
+          message_sender.send(message).unwrap();
+        }
+        message_sender.send(message).unwrap();
       }
     }
   }
@@ -52,31 +140,59 @@ pub(crate) fn camera_loop(
   Ok(())
 }
 
-fn capture_preview(
-  state: &mut CameraThreadState,
-  send: &Sender<MessageFromThread>,
-) -> AppResult<()> {
-  if let Some(camera) = &state.camera {
+/// Time between two frames at `fps` frames per second
+pub(crate) fn frame_interval(fps: u32) -> Duration {
+  Duration::from_secs(1) / fps.max(1)
+}
+
+impl CameraThreadState {
+  /// Time until the camera thread has to do something on its own (polling, sequences)
+  fn time_until_next_task(&self) -> Duration {
+    let mut timeout = self.hotplug.time_until_poll();
+
+    if self.capturing_live_view {
+      timeout = timeout.min(self.next_preview_at.saturating_duration_since(Instant::now()));
+    }
+
+    if let Some(until_frame) =
+      self.intervalometer.as_ref().and_then(|intervalometer| intervalometer.time_until_frame())
+    {
+      timeout = timeout.min(until_frame);
+    }
+
+    if let Some(until_frame) =
+      self.focus_stack.as_ref().and_then(|focus_stack| focus_stack.time_until_frame())
+    {
+      timeout = timeout.min(until_frame);
+    }
+
+    if let Some(bulb) = &self.bulb {
+      timeout = timeout.min(bulb.time_until_due());
+    }
+
+    if let Some(until_file) = self.import.as_ref().and_then(|import| import.time_until_file()) {
+      timeout = timeout.min(until_file);
+    }
+
+    if self.listens_to_events() {
+      timeout = timeout.min(self.events.time_until_poll());
+    }
+
+    timeout
+  }
+}
+
+fn capture_preview(state: &mut CameraThreadState, send: &MessageSender) -> AppResult<()> {
+  if let Some(camera) = &mut state.camera {
     match camera.capture_preview() {
-      Ok(preview) => {
-        let data = preview.get_data()?;
-        send
-          .send(MessageFromThread::PreviewCapture(PreviewImage(utils::image::decode_image(&data)?)))
-          .unwrap();
-      }
+      Ok(data) => state.live_view.submit(data, state.preview_analysis.clone()),
       Err(error) => {
-        send.send(MessageFromThread::Error(error.into())).unwrap();
+        send.send(MessageFromThread::Error { id: None, error }).unwrap();
       }
     }
   } else {
     state.capturing_live_view = false;
-    send
-      .send(MessageFromThread::Closure(Box::new(|state| {
-        if let Some(camera) = state.camera.as_mut() {
-          camera.live_view_enabled = false;
-        }
-      })))
-      .unwrap();
+    send.send(MessageFromThread::LiveViewStopped).unwrap();
   }
 
   Ok(())
@@ -0,0 +1,83 @@
+//! Capturing pictures and downloading them to the computer
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{CaptureOptions, CapturedImage},
+};
+use gcam_lib::{
+  backend::CameraFilePath,
+  error::AppResult,
+  utils::filename::{self, FilenameFields},
+};
+use std::{
+  fs,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Sequence numbers tried before giving up when the file names are taken
+const MAX_SEQUENCE_TRIES: u32 = 100_000;
+
+impl CameraThreadState {
+  pub(crate) fn capture_image(&mut self, options: &CaptureOptions) -> AppResult<CapturedImage> {
+    let camera_path = self.camera()?.capture_image()?;
+
+    self.download_capture(camera_path, options)
+  }
+
+  /// Downloads a captured picture to the target directory and removes it from the camera storage
+  /// if it should not be kept there
+  pub(crate) fn download_capture(
+    &mut self,
+    camera_path: CameraFilePath,
+    options: &CaptureOptions,
+  ) -> AppResult<CapturedImage> {
+    let model = self.camera_descriptor.as_ref().map(|descriptor| descriptor.model.clone());
+    let camera = self.camera()?;
+    let data = camera.download(&camera_path)?;
+
+    let timestamp =
+      SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() as i64).unwrap_or(0);
+
+    fs::create_dir_all(&options.directory)?;
+
+    let mut sequence = options.sequence;
+    let local_path = loop {
+      let name = filename::render_filename(
+        &options.filename_template,
+        &FilenameFields {
+          timestamp,
+          model: model.as_deref().unwrap_or("camera"),
+          sequence,
+          camera_name: &camera_path.name,
+        },
+      )?;
+      let path = options.directory.join(name);
+
+      if !path.exists() {
+        break path;
+      } else if !filename::has_sequence(&options.filename_template)
+        || sequence - options.sequence >= MAX_SEQUENCE_TRIES
+      {
+        Err(format!("The file {} already exists", path.display()))?
+      }
+
+      sequence += 1;
+    };
+
+    fs::write(&local_path, data)?;
+    log::info!(
+      "Downloaded {}/{} to {}",
+      camera_path.folder,
+      camera_path.name,
+      local_path.display()
+    );
+
+    if !options.keep_on_camera {
+      camera.delete_file(&camera_path)?;
+    }
+
+    self.events.captured(camera_path.clone());
+
+    Ok(CapturedImage { camera_path, local_path, sequence })
+  }
+}
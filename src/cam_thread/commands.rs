@@ -0,0 +1,178 @@
+//! Execution of [`CameraCommand`]s inside of the camera thread
+
+use super::{
+  camera_loop::{frame_interval, CameraThreadState},
+  live_view::decode_preview,
+  messages::{CameraCommand, CameraResponse, MessageFromThread},
+};
+use gcam_lib::{
+  backend::BackendCamera,
+  error::{AppError, AppResult},
+};
+
+impl CameraThreadState {
+  pub(crate) fn execute(&mut self, command: CameraCommand) -> AppResult<CameraResponse> {
+    Ok(match command {
+      CameraCommand::ListCameras => CameraResponse::Cameras(self.backend.list_cameras()?),
+      CameraCommand::OpenCamera(descriptor) => {
+        self.stop_camera_tasks();
+        drop(self.camera.take());
+        self.hotplug.lost_camera = None;
+
+        let camera = self.backend.open_camera(&descriptor)?;
+        let info = camera.info()?;
+        self.camera = Some(camera);
+        self.camera_descriptor = Some(descriptor.clone());
+
+        CameraResponse::CameraOpened { descriptor, info }
+      }
+      CameraCommand::CloseCamera => {
+        self.stop_camera_tasks();
+        drop(self.camera.take()); // Goodbye camera
+        self.camera_descriptor = None;
+        self.hotplug.lost_camera = None;
+
+        CameraResponse::CameraClosed
+      }
+      CameraCommand::ReloadConfig => CameraResponse::Config(self.camera()?.config()?),
+      CameraCommand::SetConfig { settings, all_or_nothing } => {
+        let results = self.apply_config(settings, all_or_nothing)?;
+        let config = match self.camera()?.config() {
+          Ok(config) => Some(config),
+          Err(err) => {
+            log::warn!("Failed to read the config after applying settings: {}", err);
+            None
+          }
+        };
+
+        CameraResponse::ConfigApplied { results, config }
+      }
+      CameraCommand::SetLiveView(live_view) => {
+        if let Some(lost_camera) = &mut self.hotplug.lost_camera {
+          lost_camera.live_view = live_view;
+        } else {
+          if live_view && !self.capturing_live_view {
+            self.live_view.reset_stats();
+          }
+          self.capturing_live_view = live_view;
+        }
+
+        CameraResponse::LiveView(live_view)
+      }
+      CameraCommand::SetLiveViewFps(fps) => {
+        let fps = fps.max(1);
+        self.preview_interval = frame_interval(fps);
+
+        CameraResponse::LiveViewFps(fps)
+      }
+      CameraCommand::SetPreviewAnalysis(analysis) => {
+        self.preview_analysis = analysis.clone();
+
+        CameraResponse::PreviewAnalysis(analysis)
+      }
+      CameraCommand::CapturePreview => {
+        let data = self.camera()?.capture_preview()?;
+
+        CameraResponse::Preview(decode_preview(&data, &self.preview_analysis)?)
+      }
+      CameraCommand::CaptureImage(options) => {
+        CameraResponse::ImageCaptured(self.capture_image(&options)?)
+      }
+      CameraCommand::StartIntervalometer(options) => {
+        CameraResponse::Intervalometer(self.start_intervalometer(options)?)
+      }
+      CameraCommand::SetIntervalometerPaused(paused) => {
+        let intervalometer =
+          self.intervalometer.as_mut().ok_or("The intervalometer is not running")?;
+        intervalometer.set_paused(paused);
+
+        CameraResponse::Intervalometer(intervalometer.status(false))
+      }
+      CameraCommand::CancelIntervalometer => {
+        let intervalometer =
+          self.intervalometer.take().ok_or("The intervalometer is not running")?;
+
+        CameraResponse::Intervalometer(intervalometer.status(true))
+      }
+      CameraCommand::CaptureBracket(options) => {
+        CameraResponse::BracketCaptured(self.capture_bracket(&options)?)
+      }
+      CameraCommand::DriveFocus(step) => {
+        self.drive_focus(step)?;
+
+        CameraResponse::FocusDriven
+      }
+      CameraCommand::StartFocusStack(options) => {
+        CameraResponse::FocusStack(self.start_focus_stack(options)?)
+      }
+      CameraCommand::CancelFocusStack => {
+        let focus_stack = self.focus_stack.take().ok_or("No focus stack is running")?;
+
+        CameraResponse::FocusStack(focus_stack.status(true))
+      }
+      CameraCommand::StartBulb(options) => CameraResponse::Bulb(self.start_bulb(options)?),
+      CameraCommand::StopBulb => CameraResponse::Bulb(self.stop_bulb()?),
+      CameraCommand::ListFolders(folder) => {
+        let folders = self.camera()?.list_folders(&folder)?;
+
+        CameraResponse::Folders { folder, folders }
+      }
+      CameraCommand::ListFiles(folder) => {
+        let files = self.camera()?.list_files(&folder)?;
+
+        CameraResponse::Files { folder, files }
+      }
+      CameraCommand::ListMedia => CameraResponse::Media(self.list_media()?),
+      CameraCommand::LoadThumbnail(path) => {
+        let image = self.load_thumbnail(&path)?;
+
+        CameraResponse::Thumbnail { path, image }
+      }
+      CameraCommand::LoadMediaPreview(path) => {
+        let image = self.load_media_preview(&path)?;
+
+        CameraResponse::MediaPreview { path, image }
+      }
+      CameraCommand::DownloadCapture { path, options } => {
+        CameraResponse::ImageCaptured(self.download_capture(path, &options)?)
+      }
+      CameraCommand::DownloadFiles { files, options } => {
+        CameraResponse::FilesDownloaded(self.download_files(&files, &options)?)
+      }
+      CameraCommand::DeleteFiles(files) => {
+        self.delete_files(&files)?;
+
+        CameraResponse::FilesDeleted(files)
+      }
+      CameraCommand::StartImport(options) => CameraResponse::Import(self.start_import(options)?),
+      CameraCommand::CancelImport => CameraResponse::Import(self.cancel_import()?),
+    })
+  }
+
+  /// Ends the sequences and the live view of the opened camera, their statuses are sent before the
+  /// response of the command, so they still reach the UI state of this camera
+  fn stop_camera_tasks(&mut self) {
+    // The shutter is closed first, the settings of the exposure only exist on this camera
+    self.abort_bulb();
+
+    if let Some(intervalometer) = self.intervalometer.take() {
+      self.outbox.push(MessageFromThread::Intervalometer(intervalometer.status(true)));
+    }
+
+    if let Some(focus_stack) = self.focus_stack.take() {
+      self.outbox.push(MessageFromThread::FocusStack(focus_stack.status(true)));
+    }
+
+    if self.capturing_live_view {
+      self.capturing_live_view = false;
+      self.outbox.push(MessageFromThread::LiveViewStopped);
+    }
+
+    // The file list of an import belongs to the camera
+    self.import = None;
+  }
+
+  pub(crate) fn camera(&mut self) -> AppResult<&mut Box<dyn BackendCamera>> {
+    self.camera.as_mut().ok_or_else(|| AppError::from("No camera is opened"))
+  }
+}
@@ -0,0 +1,86 @@
+//! Writing settings to the camera, with a result for every setting
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{MessageFromThread, SettingOutcome, SettingResult},
+};
+use gcam_lib::{
+  camera::{
+    history::{ChangeSource, ChangedSetting, SettingChange},
+    settings::CameraSettings,
+  },
+  error::AppResult,
+};
+use std::time::SystemTime;
+
+impl CameraThreadState {
+  /// Writes `settings` in order, a failed setting does not stop the others unless
+  /// `all_or_nothing` is set, then the settings written so far are restored
+  pub(crate) fn apply_config(
+    &mut self,
+    settings: Vec<CameraSettings>,
+    all_or_nothing: bool,
+  ) -> AppResult<Vec<SettingResult>> {
+    let camera = self.camera()?;
+    let previous = if all_or_nothing { Some(camera.config()?) } else { None };
+
+    let mut results: Vec<SettingResult> = Vec::with_capacity(settings.len());
+    let mut failed = false;
+
+    for setting in settings {
+      let outcome = if failed {
+        SettingOutcome::Skipped
+      } else {
+        match camera.set_config(&setting) {
+          Ok(()) => SettingOutcome::Applied,
+          Err(err) => {
+            log::warn!("Failed to set {}: {}", setting.name, err);
+            failed = all_or_nothing;
+            SettingOutcome::Failed(err.to_string())
+          }
+        }
+      };
+
+      results.push(SettingResult { setting, outcome });
+    }
+
+    if let (true, Some(previous)) = (failed, previous) {
+      for result in
+        results.iter_mut().rev().filter(|result| result.outcome == SettingOutcome::Applied)
+      {
+        let restored = match previous.find(&result.setting.name) {
+          Some(original) => camera.set_config(original),
+          None => Err(format!("{} is not in the config", result.setting.name).into()),
+        };
+
+        result.outcome = match restored {
+          Ok(()) => SettingOutcome::RolledBack,
+          Err(err) => {
+            log::error!("Failed to restore {}: {}", result.setting.name, err);
+            SettingOutcome::Failed(format!("Applied, but restoring it failed: {}", err))
+          }
+        };
+      }
+    }
+
+    Ok(results)
+  }
+
+  /// Writes a setting for a sequence and records the change for the history of the UI
+  pub(crate) fn write_sequence_setting(
+    &mut self,
+    sequence: &str,
+    old: &CameraSettings,
+    new: &CameraSettings,
+  ) -> AppResult<()> {
+    self.camera()?.set_config(new)?;
+
+    self.outbox.push(MessageFromThread::SettingChanged(SettingChange {
+      source: ChangeSource::Sequence(sequence.into()),
+      time: SystemTime::now(),
+      settings: vec![ChangedSetting { old: old.clone(), new: new.clone() }],
+    }));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,89 @@
+//! Listening to the events of the camera, eg. pictures taken with the shutter button on the body or
+//! a dial that was turned
+
+use super::{camera_loop::CameraThreadState, messages::MessageFromThread, MessageSender};
+use gcam_lib::{
+  backend::{CameraEvent, CameraFilePath},
+  error::AppResult,
+};
+use std::{
+  collections::VecDeque,
+  time::{Duration, Instant},
+};
+
+/// How often the camera is asked for events while the thread is idle
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Timeout of a single `wait_event` call, short so commands are not delayed
+const EVENT_TIMEOUT: Duration = Duration::from_millis(10);
+/// Events handled per poll, so a chatty camera does not block the thread
+const MAX_EVENTS_PER_POLL: usize = 32;
+/// Pictures downloaded by the camera thread itself that are remembered
+const RECENT_CAPTURES: usize = 16;
+
+pub struct EventListener {
+  next_poll: Instant,
+  /// Pictures taken by GCam, their file events are not reported to the UI again
+  recent_captures: VecDeque<CameraFilePath>,
+}
+
+impl Default for EventListener {
+  fn default() -> Self {
+    Self { next_poll: Instant::now(), recent_captures: VecDeque::new() }
+  }
+}
+
+impl EventListener {
+  pub fn time_until_poll(&self) -> Duration {
+    self.next_poll.saturating_duration_since(Instant::now())
+  }
+
+  /// Remembers a picture that was downloaded by the camera thread
+  pub fn captured(&mut self, path: CameraFilePath) {
+    if self.recent_captures.len() >= RECENT_CAPTURES {
+      self.recent_captures.pop_front();
+    }
+
+    self.recent_captures.push_back(path);
+  }
+}
+
+impl CameraThreadState {
+  /// Whether nothing else waits for events of the camera or keeps it busy
+  pub(crate) fn listens_to_events(&self) -> bool {
+    self.camera.is_some()
+      && self.intervalometer.is_none()
+      && self.focus_stack.is_none()
+      && self.bulb.is_none()
+      && self.import.is_none()
+  }
+
+  pub(crate) fn run_events(&mut self, send: &MessageSender) -> AppResult<()> {
+    if !self.listens_to_events() || !self.events.time_until_poll().is_zero() {
+      return Ok(());
+    }
+
+    self.events.next_poll = Instant::now() + EVENT_POLL_INTERVAL;
+
+    for _ in 0..MAX_EVENTS_PER_POLL {
+      let event = match self.camera()?.wait_event(EVENT_TIMEOUT)? {
+        Some(event) => event,
+        None => break,
+      };
+
+      log::debug!("Camera event: {:?}", event);
+
+      let message = match event {
+        CameraEvent::NewFile(path) if self.events.recent_captures.contains(&path) => continue,
+        CameraEvent::NewFile(path) => MessageFromThread::FileAdded(path),
+        CameraEvent::NewFolder(path) => MessageFromThread::FolderAdded(path),
+        CameraEvent::CaptureComplete => MessageFromThread::CaptureComplete,
+        CameraEvent::FileChanged(_) => continue,
+        CameraEvent::Unknown(event) => MessageFromThread::UnknownEvent(event),
+      };
+
+      send.send(message)?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,175 @@
+//! Focus stacking, takes pictures while moving the focus by a fixed amount between them
+//!
+//! The step index of every frame is written to an XMP sidecar next to the downloaded picture, so
+//! stacking software (or the user) can sort the frames even if the file names do not.
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{FocusStackOptions, FocusStackStatus, MessageFromThread, SequenceState},
+  MessageSender,
+};
+use gcam_lib::{
+  camera::{
+    focus::{focus_drive, FocusStep, MANUAL_FOCUS_SETTING},
+    settings::CameraSettings,
+  },
+  error::AppResult,
+  utils::sidecar,
+};
+use std::time::{Duration, Instant};
+
+/// Time between two focus drive steps, cameras ignore steps that arrive while the lens moves
+const DRIVE_STEP_DELAY: Duration = Duration::from_millis(100);
+
+pub struct FocusStack {
+  options: FocusStackOptions,
+  /// The `manualfocusdrive` setting that moves the focus by one step
+  drive: CameraSettings,
+  next_frame: u32,
+  next_frame_at: Instant,
+  frames_taken: u32,
+  failed_frames: u32,
+}
+
+impl FocusStack {
+  pub fn finished(&self) -> bool {
+    self.next_frame >= self.options.frames
+  }
+
+  pub fn time_until_frame(&self) -> Option<Duration> {
+    if self.finished() {
+      None
+    } else {
+      Some(self.next_frame_at.saturating_duration_since(Instant::now()))
+    }
+  }
+
+  pub fn status(&self, cancelled: bool) -> FocusStackStatus {
+    FocusStackStatus {
+      state: if cancelled {
+        SequenceState::Cancelled
+      } else if self.finished() {
+        SequenceState::Finished
+      } else {
+        SequenceState::Running
+      },
+      frames_taken: self.frames_taken,
+      total_frames: self.options.frames,
+      failed_frames: self.failed_frames,
+    }
+  }
+}
+
+impl CameraThreadState {
+  pub(crate) fn drive_focus(&mut self, step: FocusStep) -> AppResult<()> {
+    let setting = self.focus_drive_setting()?;
+    let drive = focus_drive(&setting, step)?;
+
+    self.camera()?.set_config(&drive)
+  }
+
+  fn focus_drive_setting(&mut self) -> AppResult<CameraSettings> {
+    Ok(
+      self
+        .camera()?
+        .config()?
+        .find(MANUAL_FOCUS_SETTING)
+        .cloned()
+        .ok_or("The camera does not support driving the focus manually")?,
+    )
+  }
+
+  pub(crate) fn start_focus_stack(
+    &mut self,
+    options: FocusStackOptions,
+  ) -> AppResult<FocusStackStatus> {
+    if self.focus_stack.is_some() {
+      Err("A focus stack is already running")?
+    }
+
+    if options.frames == 0 {
+      Err("The focus stack has to take at least one frame")?
+    }
+
+    let drive = focus_drive(&self.focus_drive_setting()?, options.step)?;
+    let focus_stack = FocusStack {
+      options,
+      drive,
+      next_frame: 0,
+      next_frame_at: Instant::now(),
+      frames_taken: 0,
+      failed_frames: 0,
+    };
+    let status = focus_stack.status(false);
+    self.focus_stack = Some(focus_stack);
+
+    Ok(status)
+  }
+
+  /// Takes the next frame of the focus stack and moves the focus for the following one
+  pub(crate) fn run_focus_stack(&mut self, send: &MessageSender) -> AppResult<()> {
+    let mut focus_stack = match self.focus_stack.take() {
+      Some(focus_stack) if focus_stack.time_until_frame() == Some(Duration::ZERO) => focus_stack,
+      focus_stack => {
+        self.focus_stack = focus_stack;
+        return Ok(());
+      }
+    };
+
+    let step_index = focus_stack.next_frame;
+    focus_stack.next_frame += 1;
+
+    match self.capture_image(&focus_stack.options.capture) {
+      Ok(captured) => {
+        focus_stack.frames_taken += 1;
+        focus_stack.options.capture.sequence = captured.sequence + 1;
+
+        if let Err(err) = sidecar::write_sidecar(
+          &captured.local_path,
+          &[
+            ("FocusStackStep", step_index.to_string()),
+            ("FocusStackFrames", focus_stack.options.frames.to_string()),
+            ("FocusStackDirection", focus_stack.options.step.direction.label().to_string()),
+            ("FocusStackStepSize", focus_stack.options.step.size.level().to_string()),
+            ("FocusStackStepsPerFrame", focus_stack.options.steps_per_frame.to_string()),
+          ],
+        ) {
+          send.send(MessageFromThread::Error { id: None, error: err })?;
+        }
+
+        send.send(MessageFromThread::ImageCaptured(captured))?;
+      }
+      Err(err) => {
+        focus_stack.failed_frames += 1;
+        send.send(MessageFromThread::Error { id: None, error: err })?;
+      }
+    }
+
+    if !focus_stack.finished() {
+      for step in 0..focus_stack.options.steps_per_frame {
+        if step > 0 {
+          std::thread::sleep(DRIVE_STEP_DELAY);
+        }
+
+        // Also ends the stack when the camera is gone, the UI would show it running otherwise
+        let driven = self.camera().and_then(|camera| camera.set_config(&focus_stack.drive));
+        if let Err(err) = driven {
+          // Without moving the focus the remaining frames would all be the same
+          send.send(MessageFromThread::Error { id: None, error: err })?;
+          send.send(MessageFromThread::FocusStack(focus_stack.status(true)))?;
+          return Ok(());
+        }
+      }
+
+      focus_stack.next_frame_at = Instant::now() + focus_stack.options.settle;
+    }
+
+    send.send(MessageFromThread::FocusStack(focus_stack.status(false)))?;
+
+    if !focus_stack.finished() {
+      self.focus_stack = Some(focus_stack);
+    }
+
+    Ok(())
+  }
+}
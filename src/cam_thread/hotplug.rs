@@ -0,0 +1,98 @@
+//! Port polling, detects connected and disconnected cameras and reopens the current camera when
+//! it comes back
+
+use super::{camera_loop::CameraThreadState, messages::MessageFromThread, MessageSender};
+use gcam_lib::{backend::CameraDescriptor, error::AppResult};
+use std::time::{Duration, Instant};
+
+pub(crate) const PORT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct Hotplug {
+  known_cameras: Vec<CameraDescriptor>,
+  last_poll: Option<Instant>,
+  /// The opened camera got disconnected, it will be reopened once it is connected again
+  pub(crate) lost_camera: Option<LostCamera>,
+}
+
+pub(crate) struct LostCamera {
+  pub descriptor: CameraDescriptor,
+  /// Whether live view should be resumed after reconnecting
+  pub live_view: bool,
+}
+
+impl Hotplug {
+  /// Time until the ports should be polled again
+  pub(crate) fn time_until_poll(&self) -> Duration {
+    self
+      .last_poll
+      .map(|last_poll| PORT_POLL_INTERVAL.saturating_sub(last_poll.elapsed()))
+      .unwrap_or_default()
+  }
+}
+
+impl CameraThreadState {
+  pub(crate) fn poll_ports(&mut self, send: &MessageSender) -> AppResult<()> {
+    self.hotplug.last_poll = Some(Instant::now());
+
+    let cameras = self.backend.list_cameras()?;
+
+    for disconnected in self.hotplug.known_cameras.iter().filter(|known| !cameras.contains(known)) {
+      log::info!("Camera disconnected: {} ({})", disconnected.model, disconnected.port);
+      send.send(MessageFromThread::CameraDisconnected(disconnected.clone()))?;
+    }
+
+    for connected in cameras.iter().filter(|camera| !self.hotplug.known_cameras.contains(camera)) {
+      log::info!("Camera connected: {} ({})", connected.model, connected.port);
+      send.send(MessageFromThread::CameraConnected(connected.clone()))?;
+    }
+
+    if let Some(descriptor) = &self.camera_descriptor {
+      if !cameras.contains(descriptor) {
+        self.hotplug.lost_camera =
+          Some(LostCamera { descriptor: descriptor.clone(), live_view: self.capturing_live_view });
+        self.capturing_live_view = false;
+        self.camera_descriptor = None;
+        drop(self.camera.take());
+      }
+    }
+
+    if let Some(lost_camera) = &self.hotplug.lost_camera {
+      // The port usually changes when a USB camera is plugged in again, the model is only enough
+      // when there is a single camera of it, otherwise another body could be taken for it
+      let found = cameras.iter().find(|camera| **camera == lost_camera.descriptor).or_else(|| {
+        let mut same_model =
+          cameras.iter().filter(|camera| camera.model == lost_camera.descriptor.model);
+
+        match (same_model.next(), same_model.next()) {
+          (Some(camera), None) => Some(camera),
+          _ => None,
+        }
+      });
+
+      if let Some(descriptor) = found {
+        match self.backend.open_camera(descriptor).and_then(|camera| Ok((camera.info()?, camera))) {
+          Ok((info, camera)) => {
+            log::info!("Reconnected to {} ({})", descriptor.model, descriptor.port);
+
+            self.camera = Some(camera);
+            self.camera_descriptor = Some(descriptor.clone());
+            self.capturing_live_view = lost_camera.live_view;
+            self.hotplug.lost_camera = None;
+
+            send.send(MessageFromThread::CameraReconnected {
+              descriptor: descriptor.clone(),
+              info,
+            })?;
+          }
+          // The camera might still be starting up, try again on the next poll
+          Err(err) => log::warn!("Failed to reopen {}: {}", descriptor.model, err),
+        }
+      }
+    }
+
+    self.hotplug.known_cameras = cameras;
+
+    Ok(())
+  }
+}
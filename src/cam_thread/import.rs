@@ -0,0 +1,198 @@
+//! Bulk import of the camera storages, one file per iteration of the camera loop so the import can
+//! be cancelled and live view keeps running
+//!
+//! A file that fails is retried a few times before it is skipped. When the camera is unplugged the
+//! hotplug poll drops it in the meantime, the import then waits and continues once the camera is
+//! reconnected.
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{
+    ImportMode, ImportOptions, ImportStatus, MediaFile, MessageFromThread, SequenceState,
+  },
+  MessageSender,
+};
+use gcam_lib::{
+  error::AppResult,
+  utils::import::{import_path, write_file, ImportIndex, ImportKey},
+};
+use std::{
+  path::PathBuf,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_ATTEMPTS: u32 = 5;
+
+pub struct Import {
+  directory: PathBuf,
+  index: ImportIndex,
+  files: Vec<MediaFile>,
+  next_file: usize,
+  attempts: u32,
+  retry_at: Option<Instant>,
+  waiting_for_camera: bool,
+  imported: u32,
+  skipped: u32,
+  failed: u32,
+  bytes_done: u64,
+  bytes_total: u64,
+}
+
+fn import_key(file: &MediaFile) -> ImportKey {
+  ImportKey { name: file.path.name.clone(), size: file.info.size, mtime: file.info.mtime }
+}
+
+impl Import {
+  pub fn finished(&self) -> bool {
+    self.next_file >= self.files.len()
+  }
+
+  /// Time until the next file should be imported
+  pub fn time_until_file(&self) -> Option<Duration> {
+    if self.finished() {
+      None
+    } else {
+      Some(self.retry_at.map(|at| at.saturating_duration_since(Instant::now())).unwrap_or_default())
+    }
+  }
+
+  pub fn status(&self, state: SequenceState) -> ImportStatus {
+    ImportStatus {
+      state,
+      total_files: self.files.len() as u32,
+      imported: self.imported,
+      skipped: self.skipped,
+      failed: self.failed,
+      bytes_done: self.bytes_done,
+      bytes_total: self.bytes_total,
+      current_file: self.files.get(self.next_file).map(|file| file.path.name.clone()),
+      current_size: self.files.get(self.next_file).and_then(|file| file.info.size),
+    }
+  }
+}
+
+impl CameraThreadState {
+  pub(crate) fn start_import(&mut self, options: ImportOptions) -> AppResult<ImportStatus> {
+    if self.import.is_some() {
+      Err("An import is already running")?
+    }
+
+    let index = ImportIndex::open(&options.directory)?;
+    let mut files = self.list_media()?;
+
+    let listed = files.len();
+    if options.mode == ImportMode::New {
+      files.retain(|file| !index.contains(&import_key(file)));
+    }
+
+    // Oldest first, so the files of an interrupted import are the oldest ones
+    files.sort_by_key(|file| file.info.mtime);
+
+    log::info!("Importing {} of {} files to {}", files.len(), listed, options.directory.display());
+
+    let import = Import {
+      directory: options.directory,
+      index,
+      bytes_total: files.iter().filter_map(|file| file.info.size).sum(),
+      skipped: (listed - files.len()) as u32,
+      files,
+      next_file: 0,
+      attempts: 0,
+      retry_at: None,
+      waiting_for_camera: false,
+      imported: 0,
+      failed: 0,
+      bytes_done: 0,
+    };
+
+    let status = import.status(SequenceState::Running);
+    self.import = Some(import);
+
+    Ok(status)
+  }
+
+  pub(crate) fn cancel_import(&mut self) -> AppResult<ImportStatus> {
+    let import = self.import.take().ok_or("No import is running")?;
+
+    Ok(import.status(SequenceState::Cancelled))
+  }
+
+  /// Imports the next file if the camera is available
+  pub(crate) fn run_import(&mut self, send: &MessageSender) -> AppResult<()> {
+    let mut import = match self.import.take() {
+      Some(import) if import.time_until_file() == Some(Duration::ZERO) => import,
+      import => {
+        self.import = import;
+        return Ok(());
+      }
+    };
+
+    if self.camera.is_none() {
+      // Waits for the camera to be reconnected
+      import.retry_at = Some(Instant::now() + RETRY_DELAY);
+      if !import.waiting_for_camera {
+        import.waiting_for_camera = true;
+        send.send(MessageFromThread::Import(import.status(SequenceState::Paused)))?;
+      }
+
+      self.import = Some(import);
+      return Ok(());
+    }
+
+    import.waiting_for_camera = false;
+
+    let file = import.files[import.next_file].clone();
+
+    match self.import_file(&mut import, &file) {
+      Ok(()) => {
+        import.imported += 1;
+        import.bytes_done += file.info.size.unwrap_or_default();
+        import.next_file += 1;
+        import.attempts = 0;
+        import.retry_at = None;
+      }
+      Err(err) if import.attempts + 1 < MAX_ATTEMPTS => {
+        log::warn!("Failed to import {}, retrying: {}", file.path.name, err);
+        import.attempts += 1;
+        import.retry_at = Some(Instant::now() + RETRY_DELAY);
+      }
+      Err(err) => {
+        import.failed += 1;
+        import.bytes_done += file.info.size.unwrap_or_default();
+        import.next_file += 1;
+        import.attempts = 0;
+        import.retry_at = None;
+        send.send(MessageFromThread::Error {
+          id: None,
+          error: format!("Failed to import {}: {}", file.path.name, err).into(),
+        })?;
+      }
+    }
+
+    let state = if import.finished() { SequenceState::Finished } else { SequenceState::Running };
+    send.send(MessageFromThread::Import(import.status(state)))?;
+
+    if !import.finished() {
+      self.import = Some(import);
+    }
+
+    Ok(())
+  }
+
+  fn import_file(&mut self, import: &mut Import, file: &MediaFile) -> AppResult<()> {
+    let data = self.camera()?.download(&file.path)?;
+
+    let timestamp = file.info.mtime.unwrap_or_else(|| {
+      SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() as i64).unwrap_or(0)
+    });
+    let path = import_path(&import.directory, timestamp, &file.path.name);
+
+    write_file(&path, &data)?;
+    import.index.insert(import_key(file), &path)?;
+
+    log::debug!("Imported {}/{} to {}", file.path.folder, file.path.name, path.display());
+
+    Ok(())
+  }
+}
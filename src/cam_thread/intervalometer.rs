@@ -0,0 +1,319 @@
+//! Intervalometer, takes pictures at a fixed interval
+//!
+//! Frames are scheduled relative to the start of the sequence, so a slow capture or download does
+//! not shift the following frames. If a frame takes longer than the interval, the frames that
+//! could not be taken in time are skipped.
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{
+    IntervalometerLimit, IntervalometerOptions, IntervalometerStatus, MessageFromThread,
+    SequenceState,
+  },
+  MessageSender,
+};
+use gcam_lib::error::AppResult;
+use std::time::{Duration, Instant, SystemTime};
+
+pub struct Intervalometer {
+  options: IntervalometerOptions,
+  /// When the first frame is due, moved forward while paused
+  start: Instant,
+  total_frames: u32,
+  /// Index of the next scheduled frame
+  next_frame: u32,
+  frames_taken: u32,
+  failed_frames: u32,
+  skipped_frames: u32,
+  last_drift: Option<Duration>,
+  paused_at: Option<Instant>,
+}
+
+impl Intervalometer {
+  pub fn new(options: IntervalometerOptions) -> AppResult<Self> {
+    if options.interval.is_zero() {
+      Err("The intervalometer interval can not be zero")?
+    }
+
+    let total_frames = match options.limit {
+      IntervalometerLimit::Frames(frames) => frames,
+      IntervalometerLimit::Duration(duration) => {
+        (duration.as_secs_f64() / options.interval.as_secs_f64()) as u32 + 1
+      }
+      IntervalometerLimit::Until(end) => {
+        let until_end = end
+          .duration_since(SystemTime::now())
+          .map_err(|_| "The end time of the intervalometer has already passed")?;
+        let duration = until_end
+          .checked_sub(options.start_delay)
+          .ok_or("The end time of the intervalometer is before the start delay ends")?;
+
+        (duration.as_secs_f64() / options.interval.as_secs_f64()) as u32 + 1
+      }
+    };
+
+    if total_frames == 0 {
+      Err("The intervalometer has to take at least one frame")?
+    }
+
+    Ok(Self {
+      start: Instant::now() + options.start_delay,
+      options,
+      total_frames,
+      next_frame: 0,
+      frames_taken: 0,
+      failed_frames: 0,
+      skipped_frames: 0,
+      last_drift: None,
+      paused_at: None,
+    })
+  }
+
+  fn scheduled(&self, frame: u32) -> Instant {
+    self.start + self.options.interval * frame
+  }
+
+  /// Moves on to the frame that is due at `now`, the frames whose time has already passed are
+  /// skipped. Returns the index of the frame.
+  fn advance(&mut self, now: Instant) -> u32 {
+    let elapsed_frames = (now.saturating_duration_since(self.start).as_secs_f64()
+      / self.options.interval.as_secs_f64()) as u32;
+    let frame = self.next_frame.max(elapsed_frames).min(self.total_frames - 1);
+
+    self.skipped_frames += frame - self.next_frame;
+    self.last_drift = Some(now.saturating_duration_since(self.scheduled(frame)));
+    self.next_frame = frame + 1;
+
+    frame
+  }
+
+  pub fn finished(&self) -> bool {
+    self.next_frame >= self.total_frames
+  }
+
+  /// Whether the end time has passed, frames are moved back by pauses but the end time is not
+  fn past_end_time(&self) -> bool {
+    matches!(self.options.limit, IntervalometerLimit::Until(end) if SystemTime::now() > end)
+  }
+
+  /// Time until the next frame is due, `None` while paused
+  pub fn time_until_frame(&self) -> Option<Duration> {
+    if self.paused_at.is_some() || self.finished() {
+      None
+    } else {
+      Some(self.scheduled(self.next_frame).saturating_duration_since(Instant::now()))
+    }
+  }
+
+  pub fn set_paused(&mut self, paused: bool) {
+    match (self.paused_at, paused) {
+      (None, true) => self.paused_at = Some(Instant::now()),
+      (Some(paused_at), false) => {
+        self.start += paused_at.elapsed();
+        self.paused_at = None;
+      }
+      _ => {}
+    }
+  }
+
+  pub fn status(&self, cancelled: bool) -> IntervalometerStatus {
+    let now = self.paused_at.unwrap_or_else(Instant::now);
+
+    IntervalometerStatus {
+      state: if cancelled {
+        SequenceState::Cancelled
+      } else if self.finished() {
+        SequenceState::Finished
+      } else if self.paused_at.is_some() {
+        SequenceState::Paused
+      } else if now < self.start {
+        SequenceState::Waiting
+      } else {
+        SequenceState::Running
+      },
+      frames_taken: self.frames_taken,
+      total_frames: self.total_frames,
+      failed_frames: self.failed_frames,
+      skipped_frames: self.skipped_frames,
+      next_frame_in: if self.finished() || cancelled {
+        None
+      } else {
+        Some(self.scheduled(self.next_frame).saturating_duration_since(now))
+      },
+      remaining: if cancelled {
+        Duration::ZERO
+      } else {
+        self.scheduled(self.total_frames.saturating_sub(1)).saturating_duration_since(now)
+      },
+      last_drift: self.last_drift,
+    }
+  }
+}
+
+impl CameraThreadState {
+  pub(crate) fn start_intervalometer(
+    &mut self,
+    options: IntervalometerOptions,
+  ) -> AppResult<IntervalometerStatus> {
+    if self.intervalometer.is_some() {
+      Err("The intervalometer is already running")?
+    }
+
+    let intervalometer = Intervalometer::new(options)?;
+    let status = intervalometer.status(false);
+    self.intervalometer = Some(intervalometer);
+
+    Ok(status)
+  }
+
+  /// Takes the next frame if it is due
+  pub(crate) fn run_intervalometer(&mut self, send: &MessageSender) -> AppResult<()> {
+    let mut intervalometer = match self.intervalometer.take() {
+      Some(intervalometer) if intervalometer.time_until_frame() == Some(Duration::ZERO) => {
+        intervalometer
+      }
+      intervalometer => {
+        self.intervalometer = intervalometer;
+        return Ok(());
+      }
+    };
+
+    if intervalometer.past_end_time() {
+      log::info!("The intervalometer reached its end time");
+      intervalometer.total_frames = intervalometer.next_frame;
+      send.send(MessageFromThread::Intervalometer(intervalometer.status(false)))?;
+      return Ok(());
+    }
+
+    intervalometer.advance(Instant::now());
+
+    match self.capture_image(&intervalometer.options.capture) {
+      Ok(captured) => {
+        intervalometer.frames_taken += 1;
+        intervalometer.options.capture.sequence = captured.sequence + 1;
+        send.send(MessageFromThread::ImageCaptured(captured))?;
+      }
+      Err(err) => {
+        intervalometer.failed_frames += 1;
+        send.send(MessageFromThread::Error { id: None, error: err })?;
+      }
+    }
+
+    send.send(MessageFromThread::Intervalometer(intervalometer.status(false)))?;
+
+    if !intervalometer.finished() {
+      self.intervalometer = Some(intervalometer);
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cam_thread::messages::CaptureOptions;
+
+  fn options(interval_secs: u64, limit: IntervalometerLimit) -> IntervalometerOptions {
+    IntervalometerOptions {
+      interval: Duration::from_secs(interval_secs),
+      limit,
+      start_delay: Duration::ZERO,
+      capture: CaptureOptions {
+        directory: Default::default(),
+        filename_template: String::new(),
+        keep_on_camera: false,
+        sequence: 1,
+      },
+    }
+  }
+
+  #[test]
+  fn schedules_frames_relative_to_the_start() {
+    let intervalometer = Intervalometer::new(IntervalometerOptions {
+      start_delay: Duration::from_secs(30),
+      ..options(10, IntervalometerLimit::Frames(3))
+    })
+    .unwrap();
+
+    assert_eq!(intervalometer.scheduled(2) - intervalometer.start, Duration::from_secs(20));
+    assert!(intervalometer.time_until_frame().unwrap() > Duration::from_secs(29));
+    assert_eq!(intervalometer.status(false).state, SequenceState::Waiting);
+  }
+
+  #[test]
+  fn skips_late_frames() {
+    let mut intervalometer =
+      Intervalometer::new(options(10, IntervalometerLimit::Frames(10))).unwrap();
+    let start = intervalometer.start;
+
+    assert_eq!(intervalometer.advance(start + Duration::from_secs(1)), 0);
+    assert_eq!(intervalometer.last_drift, Some(Duration::from_secs(1)));
+
+    // The capture took 25 seconds, frames 1 and 2 are skipped
+    assert_eq!(intervalometer.advance(start + Duration::from_secs(25)), 2);
+    assert_eq!(intervalometer.skipped_frames, 1);
+    assert_eq!(intervalometer.last_drift, Some(Duration::from_secs(5)));
+    assert_eq!(intervalometer.next_frame, 3);
+
+    // Late frames never skip past the last one
+    assert_eq!(intervalometer.advance(start + Duration::from_secs(500)), 9);
+    assert_eq!(intervalometer.skipped_frames, 7);
+    assert!(intervalometer.finished());
+  }
+
+  #[test]
+  fn pausing_moves_the_schedule() {
+    let mut intervalometer =
+      Intervalometer::new(options(10, IntervalometerLimit::Frames(5))).unwrap();
+    let start = intervalometer.start;
+
+    intervalometer.set_paused(true);
+    assert_eq!(intervalometer.time_until_frame(), None);
+    assert_eq!(intervalometer.status(false).state, SequenceState::Paused);
+
+    intervalometer.paused_at = Some(Instant::now() - Duration::from_secs(60));
+    intervalometer.set_paused(false);
+    assert!(intervalometer.start >= start + Duration::from_secs(60));
+    assert!(intervalometer.time_until_frame().unwrap() > Duration::from_secs(59));
+  }
+
+  #[test]
+  fn frame_and_duration_limits() {
+    let frames = Intervalometer::new(options(10, IntervalometerLimit::Frames(4))).unwrap();
+    assert_eq!(frames.total_frames, 4);
+
+    let duration = Duration::from_secs(60);
+    let duration = Intervalometer::new(options(10, IntervalometerLimit::Duration(duration)));
+    assert_eq!(duration.unwrap().total_frames, 7);
+
+    assert!(Intervalometer::new(options(10, IntervalometerLimit::Frames(0))).is_err());
+  }
+
+  #[test]
+  fn end_time_limit() {
+    let end = SystemTime::now() + Duration::from_secs(65);
+    let mut intervalometer =
+      Intervalometer::new(options(10, IntervalometerLimit::Until(end))).unwrap();
+    assert_eq!(intervalometer.total_frames, 7);
+    assert!(!intervalometer.past_end_time());
+
+    intervalometer.options.limit =
+      IntervalometerLimit::Until(SystemTime::now() - Duration::from_secs(1));
+    assert!(intervalometer.past_end_time());
+
+    let past = SystemTime::now() - Duration::from_secs(1);
+    assert!(Intervalometer::new(options(10, IntervalometerLimit::Until(past))).is_err());
+
+    let before_delay = IntervalometerOptions {
+      start_delay: Duration::from_secs(120),
+      ..options(10, IntervalometerLimit::Until(end))
+    };
+    assert!(Intervalometer::new(before_delay).is_err());
+  }
+
+  #[test]
+  fn rejects_a_zero_interval() {
+    assert!(Intervalometer::new(options(0, IntervalometerLimit::Frames(3))).is_err());
+  }
+}
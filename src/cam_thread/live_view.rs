@@ -0,0 +1,227 @@
+//! Live view frame pipeline
+//!
+//! The camera thread only downloads previews and hands them to a decoder thread, so slow decoding
+//! never delays the USB transfers. Both hand-overs are single-slot mailboxes that only keep the
+//! latest frame: a frame that is replaced before it is picked up is dropped and counted, so a slow
+//! decoder or UI never makes frames pile up in memory.
+
+use super::{
+  messages::{MessageFromThread, PreviewAnalysis, PreviewFrame, PreviewImage},
+  MessageSender,
+};
+use gcam_lib::{
+  error::AppResult,
+  utils::{
+    exposure,
+    histogram::Histogram,
+    image::decode_image,
+    peaking::focus_peaking,
+    scopes::{Vectorscope, Waveform, VECTORSCOPE_SIZE, WAVEFORM_COLUMNS},
+  },
+};
+use std::{
+  sync::{Arc, Condvar, Mutex, MutexGuard},
+  thread::JoinHandle,
+  time::{Duration, Instant},
+};
+
+pub const DEFAULT_TARGET_FPS: u32 = 25;
+/// Weight of the newest value in the running averages
+const AVERAGE_WEIGHT: f64 = 0.1;
+
+struct RawFrame {
+  data: Vec<u8>,
+  analysis: PreviewAnalysis,
+  captured: Instant,
+}
+
+/// Decoded frame waiting for the UI
+pub struct LiveViewFrame {
+  pub frame: PreviewFrame,
+  pub captured: Instant,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LiveViewStats {
+  pub captured: u64,
+  pub displayed: u64,
+  /// Frames that were replaced by a newer one before they were decoded
+  pub dropped_before_decoding: u64,
+  /// Frames that were replaced by a newer one before the UI showed them
+  pub dropped_before_display: u64,
+  /// Frames that could not be decoded, only the first error of a series is shown
+  pub decode_errors: u64,
+  /// Time from the end of the download until the UI picked the frame up, running average
+  pub latency: Duration,
+  pub decode_time: Duration,
+  /// Frames shown per second, running average
+  pub display_fps: f64,
+  last_displayed: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Slots {
+  raw: Option<RawFrame>,
+  decoded: Option<LiveViewFrame>,
+  stats: LiveViewStats,
+  stopped: bool,
+}
+
+/// Handle to the mailboxes shared by the camera thread, the decoder thread and the UI
+#[derive(Clone, Default)]
+pub struct LiveViewPipeline {
+  shared: Arc<(Mutex<Slots>, Condvar)>,
+}
+
+fn average(old: Duration, new: Duration) -> Duration {
+  if old.is_zero() {
+    new
+  } else {
+    old.mul_f64(1. - AVERAGE_WEIGHT) + new.mul_f64(AVERAGE_WEIGHT)
+  }
+}
+
+impl LiveViewPipeline {
+  fn slots(&self) -> MutexGuard<'_, Slots> {
+    self.shared.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Hands a downloaded preview to the decoder, replacing a frame that was not decoded yet
+  pub fn submit(&self, data: Vec<u8>, analysis: PreviewAnalysis) {
+    let mut slots = self.slots();
+
+    slots.stats.captured += 1;
+    if slots.raw.replace(RawFrame { data, analysis, captured: Instant::now() }).is_some() {
+      slots.stats.dropped_before_decoding += 1;
+    }
+
+    self.shared.1.notify_one();
+  }
+
+  /// Waits for the next frame to decode, `None` once the pipeline is stopped
+  fn next_raw(&self) -> Option<RawFrame> {
+    let mut slots = self.slots();
+
+    loop {
+      if slots.stopped {
+        return None;
+      }
+
+      if let Some(raw) = slots.raw.take() {
+        return Some(raw);
+      }
+
+      slots = self.shared.1.wait(slots).unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+  }
+
+  fn publish(&self, frame: LiveViewFrame, decode_time: Duration) {
+    let mut slots = self.slots();
+
+    slots.stats.decode_time = average(slots.stats.decode_time, decode_time);
+    if slots.decoded.replace(frame).is_some() {
+      slots.stats.dropped_before_display += 1;
+    }
+  }
+
+  /// Takes the latest decoded frame, if there is a new one
+  pub fn take_frame(&self) -> Option<LiveViewFrame> {
+    let mut slots = self.slots();
+    let frame = slots.decoded.take()?;
+    let now = Instant::now();
+    let stats = &mut slots.stats;
+
+    stats.displayed += 1;
+    stats.latency = average(stats.latency, now - frame.captured);
+    if let Some(last) = stats.last_displayed {
+      let fps = 1. / (now - last).as_secs_f64().max(f64::EPSILON);
+      stats.display_fps = if stats.display_fps == 0. {
+        fps
+      } else {
+        stats.display_fps * (1. - AVERAGE_WEIGHT) + fps * AVERAGE_WEIGHT
+      };
+    }
+    stats.last_displayed = Some(now);
+
+    Some(frame)
+  }
+
+  pub fn stats(&self) -> LiveViewStats {
+    self.slots().stats.clone()
+  }
+
+  /// Starts counting from zero, eg. when live view is started again
+  pub fn reset_stats(&self) {
+    self.slots().stats = Default::default();
+  }
+
+  pub fn stop(&self) {
+    self.slots().stopped = true;
+    self.shared.1.notify_all();
+  }
+}
+
+/// Decodes a preview and computes the live view overlays from it
+pub(crate) fn decode_preview(data: &[u8], analysis: &PreviewAnalysis) -> AppResult<PreviewFrame> {
+  let image = decode_image(data)?;
+  let histogram = Box::new(Histogram::from_image(&image));
+  let peaking =
+    analysis.focus_peaking.map(|threshold| PreviewImage(focus_peaking(&image, threshold)));
+  let zebra = analysis.zebra.map(|level| PreviewImage(exposure::zebra(&image, level)));
+  let false_color = analysis.false_color.then(|| PreviewImage(exposure::false_color(&image)));
+  let waveform = analysis
+    .waveform
+    .then(|| PreviewImage(Waveform::from_image(&image, WAVEFORM_COLUMNS).to_image()));
+  let vectorscope = analysis
+    .vectorscope
+    .then(|| PreviewImage(Vectorscope::from_image(&image, VECTORSCOPE_SIZE).to_image()));
+
+  Ok(PreviewFrame {
+    image: PreviewImage(image),
+    histogram,
+    peaking,
+    zebra,
+    false_color,
+    waveform,
+    vectorscope,
+  })
+}
+
+/// Starts the thread decoding the live view frames, it runs until the pipeline is stopped
+pub fn spawn_decoder(pipeline: LiveViewPipeline, message_sender: MessageSender) -> JoinHandle<()> {
+  std::thread::spawn(move || {
+    // Set while frames fail to decode, a format that can not be decoded fails on every frame
+    let mut failing = false;
+
+    while let Some(raw) = pipeline.next_raw() {
+      let started = Instant::now();
+
+      match decode_preview(&raw.data, &raw.analysis) {
+        Ok(frame) => {
+          failing = false;
+          pipeline.publish(LiveViewFrame { frame, captured: raw.captured }, started.elapsed());
+          message_sender.request_repaint();
+        }
+        Err(error) => {
+          let first_error = {
+            let mut slots = pipeline.slots();
+            slots.stats.decode_errors += 1;
+            slots.stats.decode_errors == 1
+          };
+
+          // Only the first error after decoded frames (or since the live view was started) is
+          // shown, the others are counted in the stats
+          if failing && !first_error {
+            log::debug!("Failed to decode a live view frame: {}", error);
+            continue;
+          }
+          failing = true;
+
+          if message_sender.send(MessageFromThread::Error { id: None, error }).is_err() {
+            break;
+          }
+        }
+      }
+    }
+  })
+}
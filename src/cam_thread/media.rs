@@ -0,0 +1,111 @@
+//! Browsing the files on the camera storages
+
+use super::{
+  camera_loop::CameraThreadState,
+  messages::{CaptureOptions, CapturedImage, MediaFile, PreviewImage},
+};
+use gcam_lib::{
+  backend::{BackendCamera, CameraFilePath},
+  error::AppResult,
+  utils::image::{can_decode_mime_type, decode_image, decode_scaled},
+};
+
+/// Longest side of the previews of selected files
+const MEDIA_PREVIEW_SIZE: u32 = 1280;
+
+impl CameraThreadState {
+  /// Lists the files of every storage of the camera, recursively
+  pub(crate) fn list_media(&mut self) -> AppResult<Vec<MediaFile>> {
+    let camera = self.camera()?;
+    let storages = camera.info()?.storages;
+
+    let mut files = vec![];
+    for base_directory in storages.iter().filter_map(|storage| storage.base_directory.as_deref()) {
+      walk_folder(camera.as_ref(), base_directory, &mut files)?;
+    }
+
+    Ok(files)
+  }
+
+  pub(crate) fn load_thumbnail(&mut self, path: &CameraFilePath) -> AppResult<PreviewImage> {
+    Ok(PreviewImage(decode_image(&self.camera()?.download_thumbnail(path)?)?))
+  }
+
+  /// Loads a larger preview, files that can not be decoded (eg. RAW files and movies) use their
+  /// thumbnail without downloading them
+  pub(crate) fn load_media_preview(&mut self, path: &CameraFilePath) -> AppResult<PreviewImage> {
+    let camera = self.camera()?;
+
+    let decodable = match camera.file_info(path) {
+      Ok(info) => info.mime_type.is_some_and(|mime_type| can_decode_mime_type(&mime_type)),
+      Err(err) => {
+        log::debug!("Failed to read the file info of {}: {}", path.name, err);
+        false
+      }
+    };
+    if !decodable {
+      return Ok(PreviewImage(decode_image(&camera.download_thumbnail(path)?)?));
+    }
+
+    match decode_scaled(&camera.download(path)?, MEDIA_PREVIEW_SIZE) {
+      Ok(image) => Ok(PreviewImage(image)),
+      Err(err) => {
+        log::debug!("Using the thumbnail of {} as preview: {}", path.name, err);
+        Ok(PreviewImage(decode_image(&camera.download_thumbnail(path)?)?))
+      }
+    }
+  }
+
+  pub(crate) fn download_files(
+    &mut self,
+    files: &[CameraFilePath],
+    options: &CaptureOptions,
+  ) -> AppResult<Vec<CapturedImage>> {
+    let mut options = options.clone();
+    let mut downloaded = Vec::with_capacity(files.len());
+
+    for path in files {
+      let image = self.download_capture(path.clone(), &options)?;
+      options.sequence = image.sequence + 1;
+      downloaded.push(image);
+    }
+
+    Ok(downloaded)
+  }
+
+  pub(crate) fn delete_files(&mut self, files: &[CameraFilePath]) -> AppResult<()> {
+    let camera = self.camera()?;
+
+    for path in files {
+      camera.delete_file(path)?;
+      log::info!("Deleted {}/{} from the camera", path.folder, path.name);
+    }
+
+    Ok(())
+  }
+}
+
+fn walk_folder(
+  camera: &dyn BackendCamera,
+  folder: &str,
+  files: &mut Vec<MediaFile>,
+) -> AppResult<()> {
+  for name in camera.list_files(folder)? {
+    let path = CameraFilePath::new(folder, name);
+    let info = camera.file_info(&path)?;
+
+    files.push(MediaFile { path, info });
+  }
+
+  for subfolder in camera.list_folders(folder)? {
+    let subfolder = if folder.ends_with('/') {
+      format!("{}{}", folder, subfolder)
+    } else {
+      format!("{}/{}", folder, subfolder)
+    };
+
+    walk_folder(camera, &subfolder, files)?;
+  }
+
+  Ok(())
+}
@@ -1,24 +1,367 @@
-use crate::{cam_thread::camera_loop::CameraThreadState, ui::state::AppState};
 use epaint::ColorImage;
-use gcam_lib::error::{AppError, AppResult};
-use std::fmt;
+use gcam_lib::{
+  backend::{CameraDescriptor, CameraFileInfo, CameraFilePath},
+  camera::{
+    bracketing::BracketSetting, focus::FocusStep, history::SettingChange, info::CameraInfo,
+    settings::CameraSettings,
+  },
+  error::AppError,
+  utils::histogram::Histogram,
+};
+use std::{
+  fmt,
+  path::PathBuf,
+  time::{Duration, SystemTime},
+};
 
-pub type FromCameraThreadClosure = Box<dyn FnOnce(&mut AppState) + Send>;
-pub type ToCameraThreadClosure =
-  Box<dyn FnOnce(&mut CameraThreadState) -> AppResult<FromCameraThreadClosure> + Send>;
+/// Identifies a command sent to the camera thread, responses carry the id of their command
+pub type RequestId = u64;
 
 #[derive(PartialEq)]
 pub struct PreviewImage(pub ColorImage);
 
+/// A live view frame with the data computed from it in the camera thread
+#[derive(Debug, PartialEq)]
+pub struct PreviewFrame {
+  pub image: PreviewImage,
+  pub histogram: Box<Histogram>,
+  /// Mask of the in-focus edges, see [`gcam_lib::utils::peaking`]
+  pub peaking: Option<PreviewImage>,
+  pub zebra: Option<PreviewImage>,
+  pub false_color: Option<PreviewImage>,
+  /// Rendered scopes, see [`gcam_lib::utils::scopes`]
+  pub waveform: Option<PreviewImage>,
+  pub vectorscope: Option<PreviewImage>,
+}
+
+/// What the camera thread computes from every preview besides the histogram
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreviewAnalysis {
+  /// Threshold of the focus peaking, `None` disables it
+  pub focus_peaking: Option<u8>,
+  /// Zebra level in IRE, `None` disables the zebra stripes
+  pub zebra: Option<u8>,
+  pub false_color: bool,
+  pub waveform: bool,
+  pub vectorscope: bool,
+}
+
+/// Commands executed by the camera thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraCommand {
+  ListCameras,
+  OpenCamera(CameraDescriptor),
+  CloseCamera,
+  ReloadConfig,
+  /// Writes the given settings to the camera in order, then reads the whole config again
+  SetConfig {
+    settings: Vec<CameraSettings>,
+    /// Restore the written settings when one of them fails
+    all_or_nothing: bool,
+  },
+  SetLiveView(bool),
+  /// Live view frames to download per second
+  SetLiveViewFps(u32),
+  SetPreviewAnalysis(PreviewAnalysis),
+  CapturePreview,
+  /// Takes a picture and downloads it
+  CaptureImage(CaptureOptions),
+  StartIntervalometer(IntervalometerOptions),
+  SetIntervalometerPaused(bool),
+  CancelIntervalometer,
+  /// Takes all frames of an exposure bracket, the command blocks until the bracket is done
+  CaptureBracket(BracketOptions),
+  /// Moves the focus of the lens, the lens has to be in manual focus mode
+  DriveFocus(FocusStep),
+  StartFocusStack(FocusStackOptions),
+  CancelFocusStack,
+  /// Opens the shutter in bulb mode, it is closed by the camera thread after the duration
+  StartBulb(BulbOptions),
+  /// Closes the shutter of a running bulb exposure early
+  StopBulb,
+  /// Downloads a picture taken on the camera body like a capture of GCam
+  DownloadCapture {
+    path: CameraFilePath,
+    options: CaptureOptions,
+  },
+  ListFolders(String),
+  ListFiles(String),
+  /// Lists the files of all storages
+  ListMedia,
+  LoadThumbnail(CameraFilePath),
+  LoadMediaPreview(CameraFilePath),
+  /// Downloads files from the camera storage, they are kept on the camera
+  DownloadFiles {
+    files: Vec<CameraFilePath>,
+    options: CaptureOptions,
+  },
+  DeleteFiles(Vec<CameraFilePath>),
+  /// Imports the files of all storages into a dated folder tree, runs in the background
+  StartImport(ImportOptions),
+  CancelImport,
+}
+
+/// Successful results of a [`CameraCommand`]
+#[derive(Debug)]
+pub enum CameraResponse {
+  Cameras(Vec<CameraDescriptor>),
+  CameraOpened {
+    descriptor: CameraDescriptor,
+    info: CameraInfo,
+  },
+  CameraClosed,
+  Config(CameraSettings),
+  ConfigApplied {
+    /// Outcome of every setting, in the order they were written
+    results: Vec<SettingResult>,
+    /// Config read after writing, `None` if it could not be read
+    config: Option<CameraSettings>,
+  },
+  LiveView(bool),
+  LiveViewFps(u32),
+  Preview(PreviewFrame),
+  ImageCaptured(CapturedImage),
+  Intervalometer(IntervalometerStatus),
+  /// Pictures of a bracket, in capture order
+  BracketCaptured(Vec<CapturedImage>),
+  PreviewAnalysis(PreviewAnalysis),
+  FocusDriven,
+  FocusStack(FocusStackStatus),
+  Bulb(BulbStatus),
+  Folders {
+    folder: String,
+    folders: Vec<String>,
+  },
+  Files {
+    folder: String,
+    files: Vec<String>,
+  },
+  Media(Vec<MediaFile>),
+  Thumbnail {
+    path: CameraFilePath,
+    image: PreviewImage,
+  },
+  MediaPreview {
+    path: CameraFilePath,
+    image: PreviewImage,
+  },
+  FilesDownloaded(Vec<CapturedImage>),
+  FilesDeleted(Vec<CameraFilePath>),
+  Import(ImportStatus),
+}
+
+/// Where and how captured pictures are downloaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureOptions {
+  pub directory: PathBuf,
+  pub filename_template: String,
+  pub keep_on_camera: bool,
+  /// First sequence number to try, it is increased until the file name is not taken
+  pub sequence: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingOutcome {
+  Applied,
+  Failed(String),
+  /// Written, but restored because another setting failed
+  RolledBack,
+  /// Not written because an earlier setting failed
+  Skipped,
+}
+
+/// Outcome of writing a single setting
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingResult {
+  pub setting: CameraSettings,
+  pub outcome: SettingOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedImage {
+  pub camera_path: CameraFilePath,
+  pub local_path: PathBuf,
+  /// Sequence number used in the file name
+  pub sequence: u32,
+}
+
+/// A file on the camera storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaFile {
+  pub path: CameraFilePath,
+  pub info: CameraFileInfo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+  All,
+  /// Skips the files that are in the import index of the destination
+  New,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+  pub directory: PathBuf,
+  pub mode: ImportMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportStatus {
+  /// `Paused` while waiting for the camera to be reconnected
+  pub state: SequenceState,
+  pub total_files: u32,
+  pub imported: u32,
+  /// Files that were already imported before
+  pub skipped: u32,
+  pub failed: u32,
+  pub bytes_done: u64,
+  pub bytes_total: u64,
+  /// File that is imported next
+  pub current_file: Option<String>,
+  pub current_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntervalometerLimit {
+  Frames(u32),
+  /// Stop after this time has passed since the first frame
+  Duration(Duration),
+  /// Stop at a wall-clock time, no frame is taken after it even if the sequence was paused
+  Until(SystemTime),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalometerOptions {
+  pub interval: Duration,
+  pub limit: IntervalometerLimit,
+  pub start_delay: Duration,
+  pub capture: CaptureOptions,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketOptions {
+  pub setting: BracketSetting,
+  pub frames: u32,
+  /// Stops between two frames, rounded to the step size of the camera
+  pub spacing: f32,
+  pub capture: CaptureOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusStackOptions {
+  pub frames: u32,
+  pub step: FocusStep,
+  /// Focus steps driven between two frames
+  pub steps_per_frame: u32,
+  /// Time for the lens to settle after moving the focus
+  pub settle: Duration,
+  pub capture: CaptureOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulbOptions {
+  pub duration: Duration,
+  pub capture: CaptureOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulbState {
+  Exposing,
+  /// Closing the shutter failed and is retried, the shutter may still be open
+  CloseFailed,
+  /// The shutter is closed, waiting for the camera to save the picture
+  Downloading,
+  Finished,
+  Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulbStatus {
+  pub state: BulbState,
+  pub duration: Duration,
+  /// How long the shutter has been open when the status was sent
+  pub exposed: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceState {
+  /// Waiting for the start delay to pass
+  Waiting,
+  Running,
+  Paused,
+  Finished,
+  Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalometerStatus {
+  pub state: SequenceState,
+  pub frames_taken: u32,
+  pub total_frames: u32,
+  /// Frames that could not be captured or downloaded
+  pub failed_frames: u32,
+  /// Frames that were skipped because the previous frame took longer than the interval
+  pub skipped_frames: u32,
+  pub next_frame_in: Option<Duration>,
+  pub remaining: Duration,
+  /// How late the last frame was started compared to its schedule
+  pub last_drift: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusStackStatus {
+  pub state: SequenceState,
+  pub frames_taken: u32,
+  pub total_frames: u32,
+  pub failed_frames: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraRequest {
+  pub id: RequestId,
+  pub command: CameraCommand,
+}
+
+#[derive(Debug)]
 pub enum MessageFromThread {
-  PreviewCapture(PreviewImage),
-  Closure(FromCameraThreadClosure),
-  Error(AppError),
+  Response {
+    id: RequestId,
+    response: CameraResponse,
+  },
+  /// `id` is the request that failed, `None` for errors outside of a command (eg. live view)
+  Error {
+    id: Option<RequestId>,
+    error: AppError,
+  },
+  /// Live view was stopped by the camera thread
+  LiveViewStopped,
+  CameraConnected(CameraDescriptor),
+  CameraDisconnected(CameraDescriptor),
+  /// A picture was taken by a sequence running in the camera thread
+  ImageCaptured(CapturedImage),
+  Intervalometer(IntervalometerStatus),
+  FocusStack(FocusStackStatus),
+  Bulb(BulbStatus),
+  Import(ImportStatus),
+  /// The opened camera was disconnected and has been reopened
+  CameraReconnected {
+    descriptor: CameraDescriptor,
+    info: CameraInfo,
+  },
+  /// A file appeared on the camera storage without GCam taking it, eg. the shutter button was
+  /// pressed on the body
+  FileAdded(CameraFilePath),
+  FolderAdded(CameraFilePath),
+  CaptureComplete,
+  /// A sequence wrote a setting, for the history of setting changes
+  SettingChanged(SettingChange),
+  /// An event the backend does not understand, usually a setting changed on the camera
+  UnknownEvent(String),
 }
 
+#[derive(Debug)]
 pub enum MessageToThread {
-  SetLiveView(bool),
-  Closure(ToCameraThreadClosure),
+  Command(CameraRequest),
   Break,
 }
 
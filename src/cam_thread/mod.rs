@@ -1,30 +1,82 @@
+mod bracketing;
+mod bulb;
 mod camera_loop;
+mod capture;
+mod commands;
+mod config;
+mod events;
+mod focus_stack;
+mod hotplug;
+mod import;
+mod intervalometer;
+pub mod live_view;
+mod media;
 pub mod messages;
-pub mod settings;
 
 use crossbeam_channel::{unbounded, Receiver, SendError, Sender};
+use eframe::egui::Context;
 use gcam_lib::error::AppResult;
 use std::thread::JoinHandle;
 
-use self::messages::ToCameraThreadClosure;
+use self::{
+  live_view::LiveViewPipeline,
+  messages::{CameraCommand, CameraRequest, MessageFromThread, RequestId},
+};
+
+/// Sends messages to the UI and wakes it up, so it does not have to poll the camera thread
+#[derive(Clone)]
+pub struct MessageSender {
+  sender: Sender<MessageFromThread>,
+  ctx: Context,
+}
+
+impl MessageSender {
+  pub fn send(&self, message: MessageFromThread) -> AppResult<()> {
+    self.sender.send(message)?;
+    self.ctx.request_repaint();
+    Ok(())
+  }
+
+  /// Wakes the UI up without a message, eg. when a live view frame was decoded
+  pub fn request_repaint(&self) {
+    self.ctx.request_repaint();
+  }
+}
 
 pub struct CameraThread {
   handle: Option<JoinHandle<()>>,
-  receiver: Receiver<messages::MessageFromThread>,
+  decoder: Option<JoinHandle<()>>,
+  live_view: LiveViewPipeline,
+  receiver: Receiver<MessageFromThread>,
   sender: Sender<messages::MessageToThread>,
+  next_request_id: RequestId,
 }
 
 impl CameraThread {
-  pub fn start() -> Self {
+  /// Starts the camera thread, `simulated` selects the simulated camera backend instead of gphoto2
+  ///
+  /// `ctx` is repainted whenever the thread has something new for the UI.
+  pub fn start(simulated: bool, ctx: Context) -> Self {
     let (to_thread_send, to_thread_recv) = unbounded();
     let (from_thread_send, from_thread_recv) = unbounded();
+    let from_thread_send = MessageSender { sender: from_thread_send, ctx };
+    let live_view = LiveViewPipeline::default();
 
+    let decoder = live_view::spawn_decoder(live_view.clone(), from_thread_send.clone());
+    let pipeline = live_view.clone();
     let handle = std::thread::spawn(move || {
-      camera_loop::camera_loop(to_thread_recv, from_thread_send)
+      camera_loop::camera_loop(simulated, to_thread_recv, from_thread_send, pipeline)
         .expect("Failed to start camera loop"); // TODO handle this error
     });
 
-    Self { handle: Some(handle), receiver: from_thread_recv, sender: to_thread_send }
+    Self {
+      handle: Some(handle),
+      decoder: Some(decoder),
+      live_view,
+      receiver: from_thread_recv,
+      sender: to_thread_send,
+      next_request_id: 0,
+    }
   }
 
   pub fn stop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -34,6 +86,11 @@ impl CameraThread {
       join_handle.join().unwrap();
     }
 
+    self.live_view.stop();
+    if let Some(decoder) = self.decoder.take() {
+      decoder.join().unwrap();
+    }
+
     Ok(())
   }
 
@@ -44,12 +101,21 @@ impl CameraThread {
     self.sender.send(message)
   }
 
-  pub fn receiver(&mut self) -> &mut Receiver<messages::MessageFromThread> {
+  pub fn receiver(&mut self) -> &mut Receiver<MessageFromThread> {
     &mut self.receiver
   }
 
-  pub fn send_fn(&self, _fn: ToCameraThreadClosure) -> AppResult<()> {
-    self.send(messages::MessageToThread::Closure(_fn))?;
-    Ok(())
+  /// Mailbox of the decoded live view frames
+  pub fn live_view(&self) -> &LiveViewPipeline {
+    &self.live_view
+  }
+
+  /// Sends a command to the camera thread, returns the id of the request
+  pub fn send_command(&mut self, command: CameraCommand) -> AppResult<RequestId> {
+    let id = self.next_request_id;
+    self.next_request_id += 1;
+
+    self.send(messages::MessageToThread::Command(CameraRequest { id, command }))?;
+    Ok(id)
   }
 }
@@ -1,2 +0,0 @@
-pub mod info;
-pub mod settings;
@@ -1,6 +1,5 @@
 pub mod app;
 pub mod cam_thread;
-pub mod camera;
 pub mod error;
 pub mod settings;
 pub mod ui;
@@ -10,8 +10,11 @@ fn main() -> AppResult<()> {
   run_native(
     "camera_gui",
     window_config,
-    Box::new(move |_| {
-      Box::new(gcam::ui::state::AppState::new().expect("Failed to create app state"))
+    Box::new(move |creation_context| {
+      Box::new(
+        gcam::ui::state::AppState::new(creation_context.egui_ctx.clone())
+          .expect("Failed to create app state"),
+      )
     }),
   );
 
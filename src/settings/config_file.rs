@@ -1,7 +1,9 @@
-use directories::ProjectDirs;
-use gcam_lib::error::AppResult;
+use super::GuideSettings;
+use crate::cam_thread::live_view::DEFAULT_TARGET_FPS;
+use directories::{ProjectDirs, UserDirs};
+use gcam_lib::{error::AppResult, utils::filename};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, fs};
+use std::{collections::HashSet, fs, path::PathBuf};
 
 const APP_ORG: &str = "maxicarlos08";
 const APP_NAME: &str = "GCam";
@@ -11,21 +13,94 @@ const CONFIG_FILENAME: &str = "config.yaml";
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DevSettings {
   pub exclude_settings: HashSet<String>,
+  /// Use the simulated camera backend instead of gphoto2
+  #[serde(default)]
+  pub simulated_camera: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CaptureSettings {
+  /// Directory captured pictures are downloaded to
+  pub target_directory: PathBuf,
+  /// See [`gcam_lib::utils::filename`] for the available placeholders
+  pub filename_template: String,
+  /// Keep captured pictures on the camera storage after downloading them
+  pub keep_on_camera: bool,
+  /// Sequence number of the next capture
+  pub next_sequence: u32,
+  /// Download pictures taken with the shutter button of the camera
+  #[serde(default = "default_download_camera_shots")]
+  pub download_camera_shots: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ImportSettings {
+  /// Root of the dated folder tree files are imported into
+  pub directory: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LiveViewSettings {
+  /// Live view frames downloaded from the camera per second
+  pub target_fps: u32,
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct Settings {
   pub dev_settings: DevSettings,
+  #[serde(default)]
+  pub capture: CaptureSettings,
+  #[serde(default)]
+  pub import: ImportSettings,
+  #[serde(default)]
+  pub guides: GuideSettings,
+  #[serde(default)]
+  pub live_view: LiveViewSettings,
+}
+
+fn default_download_camera_shots() -> bool {
+  true
 }
 
 impl Default for DevSettings {
   fn default() -> Self {
     Self {
       exclude_settings: HashSet::from(include!("defaults/exclude_cam_settings").map(Into::into)),
+      simulated_camera: false,
     }
   }
 }
 
+impl Default for CaptureSettings {
+  fn default() -> Self {
+    Self {
+      target_directory: UserDirs::new()
+        .and_then(|dirs| dirs.picture_dir().map(|dir| dir.join(APP_NAME)))
+        .unwrap_or_else(|| PathBuf::from(APP_NAME)),
+      filename_template: filename::DEFAULT_TEMPLATE.to_string(),
+      keep_on_camera: true,
+      next_sequence: 1,
+      download_camera_shots: true,
+    }
+  }
+}
+
+impl Default for ImportSettings {
+  fn default() -> Self {
+    Self {
+      directory: UserDirs::new()
+        .and_then(|dirs| dirs.picture_dir().map(|dir| dir.join(APP_NAME).join("Import")))
+        .unwrap_or_else(|| PathBuf::from(APP_NAME).join("Import")),
+    }
+  }
+}
+
+impl Default for LiveViewSettings {
+  fn default() -> Self {
+    Self { target_fps: DEFAULT_TARGET_FPS }
+  }
+}
+
 impl Settings {
   pub fn project_directories() -> Option<ProjectDirs> {
     ProjectDirs::from("com", APP_ORG, APP_NAME)
@@ -61,4 +136,12 @@ impl Settings {
       Err("Could not get project config directory")?
     }
   }
+
+  /// Writes only the sequence number of the next capture to the configuration file, so settings
+  /// that are still being edited are not saved with it
+  pub fn save_next_sequence(next_sequence: u32) -> AppResult<()> {
+    let mut settings = Self::get_user_settings()?.unwrap_or_default();
+    settings.capture.next_sequence = next_sequence;
+    settings.save()
+  }
 }
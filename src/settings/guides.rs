@@ -0,0 +1,84 @@
+//! Composition guides and aspect ratio masks drawn over the preview
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CompositionGuide {
+  None,
+  Thirds,
+  GoldenRatio,
+  CenterCross,
+  Diagonals,
+  /// Grid with the columns and rows of [`GuideSettings`]
+  Grid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AspectMask {
+  None,
+  Square,
+  Portrait,
+  Widescreen,
+  Anamorphic,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GuideSettings {
+  pub guide: CompositionGuide,
+  pub grid_columns: u8,
+  pub grid_rows: u8,
+  pub aspect_mask: AspectMask,
+}
+
+impl CompositionGuide {
+  pub const ALL: [Self; 6] =
+    [Self::None, Self::Thirds, Self::GoldenRatio, Self::CenterCross, Self::Diagonals, Self::Grid];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::None => "No guides",
+      Self::Thirds => "Rule of thirds",
+      Self::GoldenRatio => "Golden ratio",
+      Self::CenterCross => "Center cross",
+      Self::Diagonals => "Diagonals",
+      Self::Grid => "Grid",
+    }
+  }
+}
+
+impl AspectMask {
+  pub const ALL: [Self; 5] =
+    [Self::None, Self::Square, Self::Portrait, Self::Widescreen, Self::Anamorphic];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::None => "No mask",
+      Self::Square => "1:1",
+      Self::Portrait => "4:5",
+      Self::Widescreen => "16:9",
+      Self::Anamorphic => "2.39:1",
+    }
+  }
+
+  /// Width divided by height
+  pub fn ratio(&self) -> Option<f32> {
+    match self {
+      Self::None => None,
+      Self::Square => Some(1.),
+      Self::Portrait => Some(4. / 5.),
+      Self::Widescreen => Some(16. / 9.),
+      Self::Anamorphic => Some(2.39),
+    }
+  }
+}
+
+impl Default for GuideSettings {
+  fn default() -> Self {
+    Self {
+      guide: CompositionGuide::None,
+      grid_columns: 4,
+      grid_rows: 4,
+      aspect_mask: AspectMask::None,
+    }
+  }
+}
@@ -1,3 +1,7 @@
 mod config_file;
+mod guides;
+mod presets;
 
-pub use config_file::Settings;
+pub use config_file::{CaptureSettings, ImportSettings, LiveViewSettings, Settings};
+pub use guides::{AspectMask, CompositionGuide, GuideSettings};
+pub use presets::SettingPreset;
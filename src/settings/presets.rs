@@ -0,0 +1,125 @@
+//! Named snapshots of the camera settings, stored as YAML files in the `presets` directory next to
+//! the configuration file
+
+use super::Settings;
+use gcam_lib::error::AppResult;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+const PRESETS_DIRECTORY: &str = "presets";
+const PRESET_EXTENSION: &str = "yaml";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SettingPreset {
+  pub name: String,
+  /// Model of the camera the preset was taken from
+  pub model: String,
+  /// Values by setting name, see [`gcam_lib::camera::presets`]
+  pub settings: BTreeMap<String, String>,
+}
+
+impl SettingPreset {
+  fn directory() -> AppResult<PathBuf> {
+    match Settings::project_directories() {
+      Some(project_dirs) => Ok(project_dirs.config_dir().join(PRESETS_DIRECTORY)),
+      None => Err("Could not get project config directory")?,
+    }
+  }
+
+  /// File for a new preset, characters that are not safe in file names are replaced
+  ///
+  /// As different names can end up with the same file name, a hash of the name is appended when
+  /// characters were replaced or the file is taken by another preset.
+  fn new_path(name: &str) -> AppResult<PathBuf> {
+    let file_name: String = name
+      .chars()
+      .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') { c } else { '_' })
+      .collect();
+    let directory = Self::directory()?;
+
+    let path = directory.join(&file_name).with_extension(PRESET_EXTENSION);
+    if file_name == name && !path.exists() {
+      return Ok(path);
+    }
+
+    Ok(
+      directory
+        .join(format!("{}-{:08x}", file_name, name_hash(name)))
+        .with_extension(PRESET_EXTENSION),
+    )
+  }
+
+  /// File of a saved preset, found by the name stored in it
+  fn find(name: &str) -> AppResult<Option<PathBuf>> {
+    Ok(Self::read_all()?.into_iter().find(|(_, preset)| preset.name == name).map(|(path, _)| path))
+  }
+
+  fn read_all() -> AppResult<Vec<(PathBuf, Self)>> {
+    let directory = Self::directory()?;
+    if !directory.is_dir() {
+      return Ok(vec![]);
+    }
+
+    let mut presets = vec![];
+    for entry in fs::read_dir(directory)? {
+      let path = entry?.path();
+
+      if path.extension().and_then(|extension| extension.to_str()) != Some(PRESET_EXTENSION) {
+        continue;
+      }
+
+      match Self::read(&path) {
+        Ok(preset) => presets.push((path, preset)),
+        Err(err) => log::warn!("Skipping preset {}: {}", path.display(), err),
+      }
+    }
+
+    Ok(presets)
+  }
+
+  /// Names of all saved presets, sorted
+  pub fn list() -> AppResult<Vec<String>> {
+    let mut names: Vec<_> = Self::read_all()?.into_iter().map(|(_, preset)| preset.name).collect();
+
+    names.sort();
+    Ok(names)
+  }
+
+  fn read(path: &PathBuf) -> AppResult<Self> {
+    serde_yaml::from_str(&fs::read_to_string(path)?)
+      .map_err(|err| format!("Failed to parse preset {}: {}", path.display(), err).into())
+  }
+
+  pub fn load(name: &str) -> AppResult<Self> {
+    match Self::find(name)? {
+      Some(path) => Self::read(&path),
+      None => Err(format!("There is no preset named {}", name))?,
+    }
+  }
+
+  /// Saves the preset, a preset with the same name is replaced
+  pub fn save(&self) -> AppResult<()> {
+    fs::create_dir_all(Self::directory()?)?;
+    let path = match Self::find(&self.name)? {
+      Some(path) => path,
+      None => Self::new_path(&self.name)?,
+    };
+    let file = fs::File::create(path)?;
+
+    serde_yaml::to_writer(file, self).map_err(|err| format!("Failed to write preset: {}", err))?;
+
+    Ok(())
+  }
+
+  pub fn delete(name: &str) -> AppResult<()> {
+    match Self::find(name)? {
+      Some(path) => Ok(fs::remove_file(path)?),
+      None => Err(format!("There is no preset named {}", name))?,
+    }
+  }
+}
+
+/// FNV-1a hash, stable between runs unlike the hasher of the standard library
+fn name_hash(name: &str) -> u32 {
+  name.bytes().fold(0x811c9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+}
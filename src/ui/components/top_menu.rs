@@ -67,6 +67,7 @@ pub fn show(ctx: &Context, frame: &mut eframe::Frame, state: &mut AppState) {
             ui.toggle_value(&mut state.panes.camera_info, "Camera info");
             ui.toggle_value(&mut state.panes.camera_settings, "Camera settings");
             ui.toggle_value(&mut state.panes.camera_media, "Camera media");
+            ui.toggle_value(&mut state.panes.settings_history, "Settings history");
           })
         })
       });
@@ -1,6 +1,27 @@
+use crate::cam_thread::messages::{
+  BulbStatus, FocusStackStatus, IntervalometerStatus, RequestId, SettingOutcome, SettingResult,
+};
 use epaint::ahash::HashMap;
+use gcam_lib::camera::{
+  focus::MANUAL_FOCUS_SETTING,
+  history::{ChangeSource, ChangedSetting, HistoryStep, SettingChange, SettingsHistory},
+  info::CameraInfo,
+  settings::{CameraSettings, StaticWidget},
+  zoom::LIVE_VIEW_ZOOM_SETTING,
+};
+use std::{
+  path::PathBuf,
+  time::{Duration, Instant, SystemTime},
+};
 
-use crate::{cam_thread::settings::CameraSettings, camera::info::CameraInfo};
+/// How long a setting that changed on the camera is highlighted
+pub const CHANGE_HIGHLIGHT: Duration = Duration::from_secs(3);
+
+/// Settings that trigger an action instead of holding a value, or that follow the state of the
+/// preview. GCam writes them (eg. the focus stack or the sensor zoom) so their changes are never
+/// taken as changes made on the camera.
+const ACTION_SETTINGS: [&str; 3] =
+  [MANUAL_FOCUS_SETTING, "eosremoterelease", LIVE_VIEW_ZOOM_SETTING];
 
 pub type ModifiedSettingsMap = HashMap<i32, (i32, CameraSettings)>;
 
@@ -10,15 +31,235 @@ pub struct UICamera {
   pub settings: Option<CameraSettings>,
   pub block_config: bool,
   pub modified_settings: ModifiedSettingsMap,
+  /// Request that applies the modified settings
+  pub apply_request: Option<RequestId>,
+  /// What the running apply is for
+  pub apply_kind: Option<ApplyKind>,
+  /// Outcome of every setting of the last apply
+  pub apply_results: Vec<SettingResult>,
+  /// Restore all settings of an apply when one of them fails
+  pub all_or_nothing: bool,
   pub live_view_enabled: bool,
+  /// `false` while the camera is unplugged, it will be reopened when it comes back
+  pub connected: bool,
+  /// Last status of the intervalometer and when it was received, kept after it finished
+  pub intervalometer: Option<(IntervalometerStatus, Instant)>,
+  /// Request of the bracket that is being captured
+  pub bracket_request: Option<RequestId>,
+  /// Last status of the focus stack, kept after it finished
+  pub focus_stack: Option<FocusStackStatus>,
+  /// Last status of the bulb exposure and when it was received, used for the countdown
+  pub bulb: Option<(BulbStatus, Instant)>,
+  /// A camera event hinted that a setting was changed on the body
+  pub settings_outdated: bool,
+  /// When the settings were last reloaded in the background, because of camera events or polling
+  pub settings_reloaded_at: Option<Instant>,
+  /// Values of the settings when the camera was opened, by name
+  pub initial_settings: HashMap<String, StaticWidget>,
+  /// Settings that changed on the camera, by name, with when the change was noticed
+  pub external_changes: HashMap<String, Instant>,
+  /// Edits of settings that changed on the camera meanwhile, by id, with the camera setting
+  pub conflicts: HashMap<i32, CameraSettings>,
+  /// Settings written to the camera since it was opened
+  pub history: SettingsHistory,
+  /// File the history was last exported to
+  pub history_export: Option<PathBuf>,
+}
+
+/// Why settings are written to the camera, decides how they end up in the history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyKind {
+  Change(ChangeSource),
+  Step(HistoryStep),
+}
+
+/// Search and filters of the camera settings panel
+#[derive(Debug, Default)]
+pub struct SettingsFilter {
+  /// Matches the name, label or value of a setting, ignoring case
+  pub query: String,
+  pub modified_only: bool,
+  pub writable_only: bool,
+  pub changed_only: bool,
 }
 
 impl UICamera {
   pub fn modify_setting(&mut self, section_id: i32, setting: CameraSettings) {
+    // Editing again after a conflict means the user saw the camera value
+    self.conflicts.remove(&setting.id);
     self.modified_settings.insert(setting.id, (section_id, setting));
   }
 
   pub fn discard_settings(&mut self) {
     self.modified_settings.clear();
+    self.conflicts.clear();
+  }
+
+  /// Drops the edit of a conflicting setting and keeps the value of the camera
+  pub fn use_camera_value(&mut self, id: i32) {
+    self.modified_settings.remove(&id);
+    self.conflicts.remove(&id);
+  }
+
+  /// Keeps the edit of a conflicting setting, it overwrites the camera value when applied
+  pub fn keep_edit(&mut self, id: i32) {
+    self.conflicts.remove(&id);
+  }
+
+  /// Highlight of a setting that changed on the camera, fading from 1 to 0
+  pub fn change_highlight(&self, name: &str) -> Option<f32> {
+    let age = self.external_changes.get(name)?.elapsed();
+    (age < CHANGE_HIGHLIGHT).then(|| 1. - age.as_secs_f32() / CHANGE_HIGHLIGHT.as_secs_f32())
+  }
+
+  /// Last apply outcome of a setting, if it was not applied
+  pub fn apply_failure(&self, id: i32) -> Option<&SettingOutcome> {
+    self
+      .apply_results
+      .iter()
+      .find(|result| result.setting.id == id && result.outcome != SettingOutcome::Applied)
+      .map(|result| &result.outcome)
+  }
+
+  /// Takes the results of applying settings, edits that were applied are no longer modified
+  pub fn on_config_applied(&mut self, id: RequestId, results: Vec<SettingResult>) {
+    for result in &results {
+      let applied = result.outcome == SettingOutcome::Applied
+        && matches!(
+          self.modified_settings.get(&result.setting.id),
+          Some((_, modified)) if modified.widget == result.setting.widget
+        );
+
+      if applied {
+        self.modified_settings.remove(&result.setting.id);
+        self.conflicts.remove(&result.setting.id);
+      }
+    }
+
+    if self.apply_request == Some(id) {
+      self.apply_request = None;
+      self.block_config = false;
+
+      match self.apply_kind.take() {
+        Some(ApplyKind::Change(source)) => self.record_applied(source, &results),
+        Some(ApplyKind::Step(step))
+          if results.iter().all(|result| result.outcome == SettingOutcome::Applied) =>
+        {
+          self.history.finish_step(step)
+        }
+        _ => {}
+      }
+
+      self.apply_results = results;
+    }
+  }
+
+  /// Adds the applied settings to the history, the old values are the cached settings
+  fn record_applied(&mut self, source: ChangeSource, results: &[SettingResult]) {
+    let settings = results
+      .iter()
+      .filter(|result| result.outcome == SettingOutcome::Applied)
+      .filter_map(|result| {
+        let old = self.settings.as_ref()?.find(&result.setting.name)?;
+
+        (old.widget != result.setting.widget)
+          .then(|| ChangedSetting { old: old.clone(), new: result.setting.clone() })
+      })
+      .collect();
+
+    self.history.push(SettingChange { source, time: SystemTime::now(), settings });
+  }
+
+  /// Takes a setting written by a sequence of GCam, the cached value is updated so the next reload
+  /// does not take it for a change made on the camera
+  pub fn on_sequence_change(&mut self, change: SettingChange) {
+    if let Some(settings) = &mut self.settings {
+      for changed in &change.settings {
+        if let Some(setting) = settings.find_mut(&changed.new.name) {
+          setting.widget = changed.new.widget.clone();
+        }
+      }
+    }
+
+    self.history.push(change);
+  }
+
+  /// Sets the settings loaded from the camera, the first ones are kept to find changes later
+  pub fn set_settings(&mut self, settings: CameraSettings) {
+    if self.initial_settings.is_empty() {
+      settings.for_each_leaf(&mut |setting| {
+        self.initial_settings.insert(setting.name.clone(), setting.widget.clone());
+      });
+    }
+
+    if let Some(previous) = self.settings.take() {
+      self.find_external_changes(&previous, &settings);
+    }
+
+    self.settings = Some(settings);
+  }
+
+  /// Remembers the settings that differ from the previous ones, edits of them become conflicts
+  /// unless they already match the camera
+  fn find_external_changes(&mut self, previous: &CameraSettings, settings: &CameraSettings) {
+    let mut previous_values = HashMap::default();
+    previous.for_each_leaf(&mut |setting| {
+      previous_values.insert(setting.name.as_str(), &setting.widget);
+    });
+
+    let now = Instant::now();
+    settings.for_each_leaf(&mut |setting| {
+      if ACTION_SETTINGS.contains(&setting.name.as_str())
+        || !matches!(previous_values.get(setting.name.as_str()), Some(widget) if **widget != setting.widget)
+      {
+        return;
+      }
+
+      log::debug!("{} changed on the camera", setting.name);
+      self.external_changes.insert(setting.name.clone(), now);
+
+      match self.modified_settings.get(&setting.id) {
+        Some((_, edit)) if edit.widget == setting.widget => self.use_camera_value(setting.id),
+        Some(_) => {
+          self.conflicts.insert(setting.id, setting.clone());
+        }
+        None => {}
+      }
+    });
+
+    self.external_changes.retain(|_, noticed| noticed.elapsed() < CHANGE_HIGHLIGHT);
+  }
+
+  /// Whether the value on the camera differs from the one it had when it was opened, edits that
+  /// were not applied yet do not count
+  pub fn changed_since_connect(&self, name: &str) -> bool {
+    let current = self.settings.as_ref().and_then(|settings| settings.find(name));
+
+    match (self.initial_settings.get(name), current) {
+      (Some(initial), Some(current)) => *initial != current.widget,
+      (initial, current) => initial.is_some() != current.is_some(),
+    }
+  }
+}
+
+impl SettingsFilter {
+  pub fn is_active(&self) -> bool {
+    !self.query.trim().is_empty() || self.modified_only || self.writable_only || self.changed_only
+  }
+
+  /// `setting` is the value shown in the panel, ie. the modified one if there is one
+  pub fn matches(&self, setting: &CameraSettings, camera: &UICamera) -> bool {
+    if (self.writable_only && setting.readonly)
+      || (self.modified_only && !camera.modified_settings.contains_key(&setting.id))
+      || (self.changed_only && !camera.changed_since_connect(&setting.name))
+    {
+      return false;
+    }
+
+    let query = self.query.trim().to_lowercase();
+    query.is_empty()
+      || setting.name.to_lowercase().contains(&query)
+      || setting.label.to_lowercase().contains(&query)
+      || matches!(setting.widget.value_text(), Some(value) if value.to_lowercase().contains(&query))
   }
 }
@@ -0,0 +1,111 @@
+//! The live view preview, the overlays drawn over it and its zoom
+
+use crate::cam_thread::messages::PreviewAnalysis;
+use epaint::{pos2, Color32, TextureHandle};
+use gcam_lib::utils::{exposure, geom::ViewTransform, histogram::Histogram, peaking};
+
+/// Last preview with the overlays that were computed for it
+#[derive(Default)]
+pub struct LivePreview {
+  pub texture: Option<TextureHandle>,
+  pub histogram: Option<Box<Histogram>>,
+  /// Focus peaking mask, tinted when it is drawn
+  pub peaking: Option<TextureHandle>,
+  pub zebra: Option<TextureHandle>,
+  /// The preview in false color, drawn over the preview so the preview itself stays untouched
+  pub false_color: Option<TextureHandle>,
+  pub waveform: Option<TextureHandle>,
+  pub vectorscope: Option<TextureHandle>,
+}
+
+/// Where a scope is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeDisplay {
+  Off,
+  /// In a panel next to the preview
+  Panel,
+  /// Picture in picture over the preview
+  Overlay,
+}
+
+impl ScopeDisplay {
+  pub const ALL: [Self; 3] = [Self::Off, Self::Panel, Self::Overlay];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Self::Off => "Off",
+      Self::Panel => "Panel",
+      Self::Overlay => "Overlay",
+    }
+  }
+}
+
+pub struct LiveViewOverlays {
+  pub histogram: bool,
+  pub focus_peaking: bool,
+  pub peaking_color: Color32,
+  /// Minimum luminance gradient of a highlighted edge, lower values highlight more edges
+  pub peaking_threshold: u8,
+  pub zebra: bool,
+  /// Zebra level in IRE
+  pub zebra_level: u8,
+  pub false_color: bool,
+  pub waveform: ScopeDisplay,
+  pub vectorscope: ScopeDisplay,
+}
+
+impl Default for LiveViewOverlays {
+  fn default() -> Self {
+    Self {
+      histogram: false,
+      focus_peaking: false,
+      peaking_color: Color32::RED,
+      peaking_threshold: peaking::DEFAULT_THRESHOLD,
+      zebra: false,
+      zebra_level: exposure::DEFAULT_ZEBRA_LEVEL,
+      false_color: false,
+      waveform: ScopeDisplay::Off,
+      vectorscope: ScopeDisplay::Off,
+    }
+  }
+}
+
+impl LiveViewOverlays {
+  /// What the camera thread has to compute for the enabled overlays
+  pub fn analysis(&self) -> PreviewAnalysis {
+    PreviewAnalysis {
+      focus_peaking: self.focus_peaking.then_some(self.peaking_threshold),
+      zebra: self.zebra.then_some(self.zebra_level),
+      false_color: self.false_color,
+      waveform: self.waveform != ScopeDisplay::Off,
+      vectorscope: self.vectorscope != ScopeDisplay::Off,
+    }
+  }
+}
+
+pub struct LiveViewZoom {
+  pub view: ViewTransform,
+  /// Zoom with the camera (`liveviewimagezoomratio`) so the preview shows real sensor pixels
+  pub sensor_zoom: bool,
+  /// Zoom ratio that was last set on the camera, the preview is only magnified by the rest
+  pub sensor_ratio: f32,
+}
+
+impl Default for LiveViewZoom {
+  fn default() -> Self {
+    Self { view: Default::default(), sensor_zoom: false, sensor_ratio: 1. }
+  }
+}
+
+impl LiveViewZoom {
+  /// View the preview is drawn with, without the part of the zoom that is done by the camera
+  ///
+  /// The camera magnifies around its own zoom position, not around the panned center, so the rest
+  /// of the zoom is centered on the frame while the camera zooms.
+  pub fn display_view(&self) -> ViewTransform {
+    let center = if self.sensor_ratio > 1. { pos2(0.5, 0.5) } else { self.view.center };
+
+    ViewTransform { zoom: (self.view.zoom / self.sensor_ratio).max(1.), center, ..self.view }
+      .with_layout(self.view.image_size, self.view.viewport)
+  }
+}
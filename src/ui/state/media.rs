@@ -0,0 +1,113 @@
+//! State of the media browser, the files on the camera and their thumbnails
+
+use crate::cam_thread::messages::{ImportStatus, MediaFile, RequestId};
+use epaint::{
+  ahash::{HashMap, HashSet},
+  TextureHandle,
+};
+use gcam_lib::backend::CameraFilePath;
+
+/// Thumbnails requested from the camera thread at the same time, so scrolling through a large
+/// storage does not delay other commands
+pub const MAX_PENDING_THUMBNAILS: usize = 4;
+
+pub enum Thumbnail {
+  Loading,
+  Loaded(TextureHandle),
+  /// The camera has no thumbnail for the file (or it could not be decoded)
+  Unavailable,
+}
+
+#[derive(Default)]
+pub struct MediaBrowser {
+  /// Files of all storages, newest first
+  pub files: Vec<MediaFile>,
+  pub loaded: bool,
+  /// Listing the files failed, they are only listed again when the user refreshes
+  pub list_failed: bool,
+  /// Request listing the files, set while the storages are walked
+  pub list_request: Option<RequestId>,
+  pub selected: HashSet<CameraFilePath>,
+  /// File shown in the preview, the last one that was clicked
+  pub focused: Option<CameraFilePath>,
+  pub preview: Option<(CameraFilePath, TextureHandle)>,
+  pub preview_request: Option<(RequestId, CameraFilePath)>,
+  /// Loading the preview of this file failed, it is only loaded again when another file is focused
+  /// or the user refreshes
+  pub preview_failed: Option<CameraFilePath>,
+  pub thumbnails: HashMap<CameraFilePath, Thumbnail>,
+  pub(crate) thumbnail_requests: HashMap<RequestId, CameraFilePath>,
+  pub confirm_delete: bool,
+  /// Status of the running (or last) import
+  pub import: Option<ImportStatus>,
+}
+
+impl MediaBrowser {
+  pub fn set_files(&mut self, mut files: Vec<MediaFile>) {
+    files
+      .sort_by(|a, b| b.info.mtime.cmp(&a.info.mtime).then_with(|| b.path.name.cmp(&a.path.name)));
+
+    self.selected.retain(|path| files.iter().any(|file| &file.path == path));
+    self.thumbnails.retain(|path, _| files.iter().any(|file| &file.path == path));
+    if !matches!(&self.focused, Some(path) if files.iter().any(|file| &file.path == path)) {
+      self.focused = None;
+    }
+
+    self.files = files;
+    self.loaded = true;
+    self.list_failed = false;
+    self.list_request = None;
+  }
+
+  /// Lists the files again, unless listing them failed before
+  pub fn mark_outdated(&mut self) {
+    if !self.list_failed {
+      self.loaded = false;
+    }
+  }
+
+  /// File whose preview should be loaded, if it is not loaded (or loading) yet
+  pub fn missing_preview(&self) -> Option<&CameraFilePath> {
+    self.focused.as_ref().filter(|focused| {
+      self.preview_request.is_none()
+        && self.preview.as_ref().map(|(path, _)| path) != Some(focused)
+        && self.preview_failed.as_ref() != Some(focused)
+    })
+  }
+
+  /// Whether another thumbnail can be requested
+  pub fn can_request_thumbnail(&self) -> bool {
+    self.thumbnail_requests.len() < MAX_PENDING_THUMBNAILS
+  }
+
+  pub fn selected_files(&self) -> Vec<CameraFilePath> {
+    self
+      .files
+      .iter()
+      .filter(|file| self.selected.contains(&file.path))
+      .map(|file| file.path.clone())
+      .collect()
+  }
+
+  /// Handles a failed request, returns `true` if the request belonged to the media browser and the
+  /// error should not be shown to the user
+  pub fn request_failed(&mut self, id: RequestId) -> bool {
+    if let Some(path) = self.thumbnail_requests.remove(&id) {
+      self.thumbnails.insert(path, Thumbnail::Unavailable);
+      return true;
+    }
+
+    if matches!(&self.preview_request, Some((request, _)) if *request == id) {
+      self.preview_failed = self.preview_request.take().map(|(_, path)| path);
+    }
+
+    if self.list_request == Some(id) {
+      // Not retried on its own, a camera that can not list its files would fail every frame
+      self.list_request = None;
+      self.loaded = true;
+      self.list_failed = true;
+    }
+
+    false
+  }
+}
@@ -1,29 +1,66 @@
 pub mod camera;
 pub mod dialogs;
-
-use self::{camera::UICamera, dialogs::Dialogs};
+pub mod live_view;
+pub mod media;
+pub mod presets;
+pub mod sequences;
+
+use self::{
+  camera::{ApplyKind, SettingsFilter, UICamera},
+  dialogs::Dialogs,
+  live_view::{LivePreview, LiveViewOverlays, LiveViewZoom},
+  media::{MediaBrowser, Thumbnail},
+  presets::{LoadedPreset, PresetsState},
+  sequences::{BracketForm, BulbForm, FocusStackForm, IntervalometerForm},
+};
 use crate::{
   cam_thread::{
-    messages::{FromCameraThreadClosure, MessageFromThread, MessageToThread},
-    settings::{display_radio_choice, StaticWidget},
+    live_view::LiveViewStats,
+    messages::{
+      CameraCommand, CameraResponse, CaptureOptions, CapturedImage, ImportMode, ImportOptions,
+      ImportStatus, MessageFromThread, PreviewFrame, RequestId, SequenceState,
+    },
     CameraThread,
   },
-  camera::info::CameraInfo,
   error::{ToUIError, UiError},
-  settings::Settings,
+  settings::{SettingPreset, Settings},
 };
 use eframe::egui::Context;
-use epaint::TextureHandle;
-use gcam_lib::error::AppResult;
-use gphoto2::{
-  list::CameraDescriptor,
-  widget::{ButtonWidget, DateWidget, RadioWidget, RangeWidget, TextWidget, ToggleWidget, Widget},
+use epaint::textures::TextureFilter;
+use gcam_lib::{
+  backend::{CameraDescriptor, CameraFilePath},
+  camera::{
+    focus::FocusStep,
+    history::{ChangeSource, HistoryStep},
+    presets as setting_presets,
+    settings::CameraSettings,
+    zoom::{zoom_setting, LIVE_VIEW_ZOOM_SETTING},
+  },
+  error::AppResult,
+};
+use std::{
+  fs,
+  path::PathBuf,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+/// Setting this environment variable forces the simulated camera backend
+const SIMULATED_CAMERA_ENV: &str = "GCAM_SIMULATED_CAMERA";
+/// Least time between two reloads of the settings caused by camera events
+const SETTINGS_RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+/// Time between two reloads of the settings when the camera reports no changes
+const SETTINGS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Least time between two reloads caused by camera events during live view, reading the whole
+/// config delays the frames
+const LIVE_VIEW_RELOAD_INTERVAL: Duration = Duration::from_secs(3);
+/// Time between two polls of the settings during live view
+const LIVE_VIEW_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct VisiblePanes {
   pub camera_info: bool,
   pub camera_settings: bool,
   pub camera_media: bool,
+  pub settings_history: bool,
 }
 
 pub struct AppState {
@@ -32,8 +69,20 @@ pub struct AppState {
   pub camera: Option<UICamera>,
   pub panes: VisiblePanes,
   pub open_dialogs: Dialogs,
+  pub intervalometer_form: IntervalometerForm,
+  pub bracket_form: BracketForm,
+  pub focus_stack_form: FocusStackForm,
+  pub bulb_form: BulbForm,
+  pub media: MediaBrowser,
+  pub presets: PresetsState,
+  pub settings_filter: SettingsFilter,
   pub settings: Settings,
-  pub last_preview_capture: Option<TextureHandle>,
+  /// Last preview and its overlays
+  pub preview: LivePreview,
+  pub overlays: LiveViewOverlays,
+  pub zoom: LiveViewZoom,
+  /// Local path of the last downloaded picture
+  pub last_capture: Option<PathBuf>,
   pub errors: Vec<UiError>,
   camera_thread: CameraThread,
   first_load: bool,
@@ -41,14 +90,13 @@ pub struct AppState {
 
 impl Default for VisiblePanes {
   fn default() -> Self {
-    Self { camera_info: true, camera_settings: true, camera_media: true }
+    Self { camera_info: true, camera_settings: true, camera_media: true, settings_history: false }
   }
 }
 
 impl AppState {
-  pub fn new() -> AppResult<Self> {
-    let camera_thread = CameraThread::start();
-
+  /// `ctx` is woken up by the camera thread when it has news for the UI
+  pub fn new(ctx: Context) -> AppResult<Self> {
     let settings = if let Ok(Some(settings)) = Settings::get_user_settings() {
       settings
     } else {
@@ -57,46 +105,48 @@ impl AppState {
       settings
     };
 
-    let _self = Self {
+    let camera_thread = CameraThread::start(
+      settings.dev_settings.simulated_camera || std::env::var_os(SIMULATED_CAMERA_ENV).is_some(),
+      ctx,
+    );
+
+    let mut _self = Self {
       first_load: true,
       camera_thread,
       current_camera: Default::default(),
       camera: Default::default(),
       camera_list: Default::default(),
       open_dialogs: Default::default(),
+      intervalometer_form: Default::default(),
+      bracket_form: Default::default(),
+      focus_stack_form: Default::default(),
+      bulb_form: Default::default(),
+      media: Default::default(),
+      presets: Default::default(),
+      settings_filter: Default::default(),
       panes: Default::default(),
-      last_preview_capture: None,
+      preview: Default::default(),
+      zoom: Default::default(),
+      overlays: Default::default(),
+      last_capture: None,
       errors: vec![],
       settings,
     };
 
     _self.update_cameras()?;
+    _self.update_live_view_fps()?;
 
     Ok(_self)
   }
 
-  pub fn update_cameras(&self) -> AppResult<()> {
-    self.camera_thread.send_fn(Box::new(|cam_state| {
-      let cameras: Vec<_> = cam_state.context.list_cameras()?.collect();
-
-      Ok(Box::new(move |state| {
-        if state.first_load && !cameras.is_empty() {
-          state.use_camera(cameras[0].clone()).unwrap();
-        }
-        state.camera_list = cameras;
-      }))
-    }))
+  pub fn update_cameras(&mut self) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::ListCameras)?;
+    Ok(())
   }
 
   pub fn close_camera(&mut self) -> AppResult<()> {
-    self.camera_thread.send_fn(Box::new(|cam_state| {
-      drop(cam_state.camera.take()); // Goodbye camera
-
-      Ok(Box::new(|state| {
-        state.camera = None;
-        state.current_camera = None;
-      }))
-    }))
+    self.camera_thread.send_command(CameraCommand::CloseCamera)?;
+    Ok(())
   }
 
   pub fn use_camera(&mut self, descriptor: CameraDescriptor) -> AppResult<()> {
@@ -105,114 +155,350 @@ impl AppState {
     }
 
     self.camera = None;
-    self.camera_thread.send_fn(Box::new(move |cam_state| {
-      let new_camera = cam_state.context.get_camera(&descriptor)?;
-      let info = CameraInfo {
-        abilities: new_camera.abilities(),
-        about: new_camera.about().ok(),
-        manual: new_camera.manual().ok(),
-        model: descriptor.model.clone(),
-        port: descriptor.port.clone(),
-        storages: new_camera.storages()?,
-        summary: new_camera.summary().ok(),
-      };
-
-      cam_state.camera = Some(new_camera);
-
-      Ok(Box::new(move |state| {
-        state.current_camera = Some(descriptor.clone());
-        state.camera = Some(UICamera {
-          info,
-          settings: None,
-          block_config: false,
-          modified_settings: Default::default(),
-          live_view_enabled: false,
-        });
-      }))
-    }))?;
+    self.camera_thread.send_command(CameraCommand::OpenCamera(descriptor))?;
     self.reload_settings()?;
     Ok(())
   }
 
-  pub fn reload_settings(&self) -> AppResult<()> {
-    self.camera_thread.send_fn(Box::new(|cam_state| {
-      if let Some(camera) = &cam_state.camera {
-        let config = Widget::Group(camera.config()?).try_into()?;
+  pub fn reload_settings(&mut self) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::ReloadConfig)?;
+    Ok(())
+  }
+
+  /// Writes the modified settings to the camera, they stay modified until the camera accepted
+  /// them
+  pub fn apply_settings(&mut self) -> AppResult<()> {
+    let (settings, all_or_nothing) = match &self.camera {
+      Some(camera) => {
+        let mut settings: Vec<_> =
+          camera.modified_settings.values().map(|(_, setting)| setting.clone()).collect();
+        settings.sort_by_key(|setting| setting.id);
 
-        Ok(Box::new(move |state| {
-          if let Some(camera) = &mut state.camera {
-            camera.settings = Some(config);
-          }
-        }))
-      } else {
-        Err("There as no camera to reload configuration")?
+        (settings, camera.all_or_nothing)
       }
-    }))
+      None => return Ok(()),
+    };
+
+    self.write_settings(settings, all_or_nothing, ApplyKind::Change(ChangeSource::User))
   }
 
-  pub fn apply_settings(&mut self) -> AppResult<()> {
-    if let Some(UICamera { modified_settings, block_config, .. }) = &mut self.camera {
-      let modified_settings = modified_settings.drain().collect::<Vec<_>>();
-      *block_config = true;
-      self.camera_thread.send_fn(Box::new(move |cam_state| {
-        if let Some(camera) = &cam_state.camera {
-          for (_, (_, modified)) in modified_settings {
-            let cam_widget: Widget = camera.config_key(&modified.name)?;
-
-            // TODO: Clean this code up using macros maybe?
-            let widget = match modified.widget {
-              StaticWidget::Group { .. } => unreachable!(),
-              StaticWidget::Text(text) => {
-                let text_widget = cam_widget.try_into::<TextWidget>()?;
-                text_widget.set_value(&text)?;
-                Widget::Text(text_widget)
-              }
-              StaticWidget::Range { value, .. } => {
-                let range_widget = cam_widget.try_into::<RangeWidget>()?;
-                range_widget.set_value(value);
-                Widget::Range(range_widget)
-              }
-              StaticWidget::Toggle { value, .. } => {
-                let toggle_widget = cam_widget.try_into::<ToggleWidget>()?;
-                toggle_widget.set_toggled(value);
-                Widget::Toggle(toggle_widget)
-              }
-              StaticWidget::Radio { ref choice, ref choices } => {
-                let radio_widget = cam_widget.try_into::<RadioWidget>()?;
-                radio_widget.set_choice(display_radio_choice(choices, choice))?;
-                Widget::Radio(radio_widget)
-              }
-              StaticWidget::Button => {
-                let button = cam_widget.try_into::<ButtonWidget>()?;
-                button.press(camera)?;
-                Widget::Button(button)
-              }
-              StaticWidget::Date { timestamp } => {
-                let time_widget = cam_widget.try_into::<DateWidget>()?;
-                time_widget.set_timestamp(timestamp);
-                Widget::Date(time_widget)
-              }
-            };
-
-            camera.set_config(&widget)?;
-          }
-        }
+  fn write_settings(
+    &mut self,
+    settings: Vec<CameraSettings>,
+    all_or_nothing: bool,
+    kind: ApplyKind,
+  ) -> AppResult<()> {
+    let camera = self.camera.as_mut().ok_or("No camera is opened")?;
+    if camera.block_config {
+      Err("The settings are being applied already")?
+    }
 
-        Ok(Box::new(|state| {
-          if let Some(camera) = &mut state.camera {
-            camera.block_config = false
-          }
-        }))
-      }))
-    } else {
-      Ok(())
+    let id =
+      self.camera_thread.send_command(CameraCommand::SetConfig { settings, all_or_nothing })?;
+    camera.block_config = true;
+    camera.apply_request = Some(id);
+    camera.apply_kind = Some(kind);
+    camera.apply_results.clear();
+
+    Ok(())
+  }
+
+  /// Reverts the last change of the user or a preset on the camera
+  pub fn undo_setting_change(&mut self) -> AppResult<()> {
+    let camera = self.camera.as_ref().ok_or("No camera is opened")?;
+    let index = camera.history.undo_target().ok_or("There is no setting change to undo")?;
+
+    self.run_history_step(HistoryStep::Undo(index))
+  }
+
+  /// Applies the last undone change again
+  pub fn redo_setting_change(&mut self) -> AppResult<()> {
+    let camera = self.camera.as_ref().ok_or("No camera is opened")?;
+    let index = camera.history.redo_target().ok_or("There is no setting change to redo")?;
+
+    self.run_history_step(HistoryStep::Redo(index))
+  }
+
+  /// Undo and redo write all settings of an entry or none of them
+  fn run_history_step(&mut self, step: HistoryStep) -> AppResult<()> {
+    let camera = self.camera.as_ref().ok_or("No camera is opened")?;
+    let settings = camera.history.step_settings(step);
+
+    self.write_settings(settings, true, ApplyKind::Step(step))
+  }
+
+  /// Writes the history of setting changes as CSV next to the captured pictures
+  pub fn export_setting_history(&mut self) -> AppResult<()> {
+    let camera = self.camera.as_mut().ok_or("No camera is opened")?;
+    let timestamp =
+      SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or_default();
+    let directory = &self.settings.capture.target_directory;
+    let path = directory.join(format!("settings-history-{}.csv", timestamp));
+
+    fs::create_dir_all(directory)?;
+    fs::write(&path, camera.history.to_csv())?;
+    log::info!("Exported the settings history to {}", path.display());
+    camera.history_export = Some(path);
+
+    Ok(())
+  }
+
+  pub fn list_presets(&mut self) -> AppResult<()> {
+    self.presets.names = Some(SettingPreset::list()?);
+    Ok(())
+  }
+
+  /// Saves the writable settings of the camera as a preset
+  pub fn save_preset(&mut self, name: String) -> AppResult<()> {
+    let camera = self.camera.as_ref().ok_or("No camera is opened")?;
+    let settings = camera.settings.as_ref().ok_or("The camera settings are not loaded yet")?;
+
+    SettingPreset {
+      name,
+      model: camera.info.model.clone(),
+      settings: setting_presets::snapshot(settings, &self.settings.dev_settings.exclude_settings),
+    }
+    .save()?;
+
+    self.presets.new_name.clear();
+    self.list_presets()
+  }
+
+  /// Loads a preset and compares it with the camera, it is applied by [`AppState::apply_preset`]
+  pub fn load_preset(&mut self, name: &str) -> AppResult<()> {
+    let camera = self.camera.as_ref().ok_or("No camera is opened")?;
+    let settings = camera.settings.as_ref().ok_or("The camera settings are not loaded yet")?;
+    let preset = SettingPreset::load(name)?;
+
+    self.presets.loaded = Some(LoadedPreset {
+      entries: setting_presets::diff(&preset.settings, settings),
+      camera_model: camera.info.model.clone(),
+      preset,
+    });
+
+    Ok(())
+  }
+
+  /// Applies the changed values of the loaded preset, the preset stays loaded if it can not be
+  /// applied
+  pub fn apply_preset(&mut self) -> AppResult<()> {
+    let loaded = self.presets.loaded.as_ref().ok_or("No preset is loaded")?;
+    let source = ChangeSource::Preset(loaded.preset.name.clone());
+    let camera = self.camera.as_ref().ok_or("No camera is opened")?;
+
+    if loaded.model_mismatch() {
+      log::warn!(
+        "Applying preset {} of a {} to a {}",
+        loaded.preset.name,
+        loaded.preset.model,
+        loaded.camera_model
+      );
+    }
+
+    // Pending edits of the user stay pending, they are not part of the preset
+    let mut settings: Vec<_> = loaded
+      .entries
+      .iter()
+      .filter_map(|entry| entry.setting.as_ref())
+      .map(|(_, setting)| setting.clone())
+      .collect();
+    settings.sort_by_key(|setting| setting.id);
+
+    self.write_settings(settings, camera.all_or_nothing, ApplyKind::Change(source))?;
+    self.presets.loaded = None;
+
+    Ok(())
+  }
+
+  pub fn delete_preset(&mut self, name: &str) -> AppResult<()> {
+    SettingPreset::delete(name)?;
+    self.list_presets()
+  }
+
+  pub fn capture_image(&mut self) -> AppResult<()> {
+    let options = self.capture_options();
+    self.camera_thread.send_command(CameraCommand::CaptureImage(options))?;
+    Ok(())
+  }
+
+  pub fn capture_options(&self) -> CaptureOptions {
+    CaptureOptions {
+      directory: self.settings.capture.target_directory.clone(),
+      filename_template: self.settings.capture.filename_template.clone(),
+      keep_on_camera: self.settings.capture.keep_on_camera,
+      sequence: self.settings.capture.next_sequence,
+    }
+  }
+
+  pub fn start_intervalometer(&mut self) -> AppResult<()> {
+    let options = self.intervalometer_form.options(self.capture_options());
+    self.camera_thread.send_command(CameraCommand::StartIntervalometer(options))?;
+    Ok(())
+  }
+
+  pub fn set_intervalometer_paused(&mut self, paused: bool) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::SetIntervalometerPaused(paused))?;
+    Ok(())
+  }
+
+  pub fn cancel_intervalometer(&mut self) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::CancelIntervalometer)?;
+    Ok(())
+  }
+
+  pub fn capture_bracket(&mut self) -> AppResult<()> {
+    let options = self.bracket_form.options(self.capture_options());
+    let id = self.camera_thread.send_command(CameraCommand::CaptureBracket(options))?;
+
+    if let Some(camera) = &mut self.camera {
+      camera.bracket_request = Some(id);
+    }
+
+    Ok(())
+  }
+
+  pub fn drive_focus(&mut self, step: FocusStep) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::DriveFocus(step))?;
+    Ok(())
+  }
+
+  pub fn start_focus_stack(&mut self) -> AppResult<()> {
+    let options = self.focus_stack_form.options(self.capture_options());
+    self.camera_thread.send_command(CameraCommand::StartFocusStack(options))?;
+    Ok(())
+  }
+
+  pub fn cancel_focus_stack(&mut self) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::CancelFocusStack)?;
+    Ok(())
+  }
+
+  pub fn start_bulb(&mut self) -> AppResult<()> {
+    // Most cameras can not expose in bulb mode while live view is running
+    if let Some(UICamera { live_view_enabled: true, .. }) = &self.camera {
+      self.set_live_view(false)?;
+    }
+
+    let options = self.bulb_form.options(self.capture_options());
+    self.camera_thread.send_command(CameraCommand::StartBulb(options))?;
+    Ok(())
+  }
+
+  pub fn stop_bulb(&mut self) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::StopBulb)?;
+    Ok(())
+  }
+
+  pub fn refresh_media(&mut self) -> AppResult<()> {
+    let id = self.camera_thread.send_command(CameraCommand::ListMedia)?;
+    self.media.list_request = Some(id);
+    self.media.preview_failed = None;
+    Ok(())
+  }
+
+  pub fn load_thumbnail(&mut self, path: CameraFilePath) -> AppResult<()> {
+    let id = self.camera_thread.send_command(CameraCommand::LoadThumbnail(path.clone()))?;
+    self.media.thumbnails.insert(path.clone(), Thumbnail::Loading);
+    self.media.thumbnail_requests.insert(id, path);
+    Ok(())
+  }
+
+  pub fn load_media_preview(&mut self, path: CameraFilePath) -> AppResult<()> {
+    let id = self.camera_thread.send_command(CameraCommand::LoadMediaPreview(path.clone()))?;
+    self.media.preview_request = Some((id, path));
+    Ok(())
+  }
+
+  pub fn download_selected_media(&mut self) -> AppResult<()> {
+    let files = self.media.selected_files();
+    // Files that are browsed are never removed by downloading them
+    let options = CaptureOptions { keep_on_camera: true, ..self.capture_options() };
+
+    self.camera_thread.send_command(CameraCommand::DownloadFiles { files, options })?;
+    Ok(())
+  }
+
+  pub fn delete_selected_media(&mut self) -> AppResult<()> {
+    let files = self.media.selected_files();
+
+    self.camera_thread.send_command(CameraCommand::DeleteFiles(files))?;
+    // Also picks up the files that were deleted before an error
+    self.refresh_media()
+  }
+
+  pub fn start_import(&mut self, mode: ImportMode) -> AppResult<()> {
+    let options = ImportOptions { directory: self.settings.import.directory.clone(), mode };
+    self.camera_thread.send_command(CameraCommand::StartImport(options))?;
+    Ok(())
+  }
+
+  pub fn cancel_import(&mut self) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::CancelImport)?;
+    Ok(())
+  }
+
+  fn on_import_status(&mut self, status: ImportStatus) {
+    if status.state == SequenceState::Finished {
+      log::info!(
+        "Import finished: {} imported, {} skipped, {} failed",
+        status.imported,
+        status.skipped,
+        status.failed
+      );
     }
+
+    self.media.import = Some(status);
   }
 
   pub fn set_live_view(&mut self, live_view: bool) -> AppResult<()> {
     if let Some(UICamera { live_view_enabled, .. }) = &mut self.camera {
       *live_view_enabled = live_view;
-      self.camera_thread.send(MessageToThread::SetLiveView(live_view))?;
+      self.camera_thread.send_command(CameraCommand::SetLiveView(live_view))?;
+    }
+
+    Ok(())
+  }
+
+  /// Tells the camera thread which overlays to compute after they were changed
+  pub fn update_preview_analysis(&mut self) -> AppResult<()> {
+    self.camera_thread.send_command(CameraCommand::SetPreviewAnalysis(self.overlays.analysis()))?;
+    Ok(())
+  }
+
+  pub fn update_live_view_fps(&mut self) -> AppResult<()> {
+    self
+      .camera_thread
+      .send_command(CameraCommand::SetLiveViewFps(self.settings.live_view.target_fps))?;
+    Ok(())
+  }
+
+  /// Counters of the live view pipeline
+  pub fn live_view_stats(&self) -> LiveViewStats {
+    self.camera_thread.live_view().stats()
+  }
+
+  /// Sets the live view zoom of the camera to the zoom of the preview, or back to no zoom when the
+  /// sensor zoom is disabled
+  pub fn update_sensor_zoom(&mut self) -> AppResult<()> {
+    let setting = match self
+      .camera
+      .as_ref()
+      .and_then(|camera| camera.settings.as_ref())
+      .and_then(|settings| settings.find(LIVE_VIEW_ZOOM_SETTING))
+    {
+      Some(setting) => setting,
+      None => return Ok(()),
+    };
+
+    let wanted = if self.zoom.sensor_zoom { self.zoom.view.zoom } else { 1. };
+    let (setting, ratio) = zoom_setting(setting, wanted)?;
+
+    if ratio != self.zoom.sensor_ratio {
+      self.camera_thread.send_command(CameraCommand::SetConfig {
+        settings: vec![setting],
+        all_or_nothing: false,
+      })?;
+      self.zoom.sensor_ratio = ratio;
     }
 
     Ok(())
@@ -227,32 +513,134 @@ impl AppState {
   pub(crate) fn process_events_from_camera_thread(&mut self, ctx: &Context) -> AppResult<()> {
     let mut errors: Option<Vec<UiError>> = None;
 
-    let mut closures: Option<Vec<FromCameraThreadClosure>> = None;
-    for event in self.camera_thread.receiver().try_iter() {
+    let mut responses: Option<Vec<(RequestId, CameraResponse)>> = None;
+    if let Some(frame) = self.camera_thread.live_view().take_frame() {
+      self.on_preview(ctx, frame.frame);
+    }
+
+    let events: Vec<_> = self.camera_thread.receiver().try_iter().collect();
+    for event in events {
       match event {
-        MessageFromThread::Error(err) => {
-          log::error!("Got error from camera thread: {:?}", err);
+        MessageFromThread::Error { id, error } => {
+          log::error!("Got error from camera thread (request {:?}): {:?}", id, error);
+
+          if let Some(camera) = &mut self.camera {
+            if id.is_some() && camera.bracket_request == id {
+              camera.bracket_request = None;
+            }
+
+            if id.is_some() && camera.apply_request == id {
+              camera.apply_request = None;
+              camera.apply_kind = None;
+              camera.block_config = false;
+            }
+          }
+
+          if matches!(id, Some(id) if self.media.request_failed(id)) {
+            log::debug!("No thumbnail available: {}", error);
+            continue;
+          }
 
-          let ui_error = err.to_ui_error();
+          let ui_error = error.to_ui_error();
           if let Some(errors) = &mut errors {
             errors.push(ui_error)
           } else {
             errors = Some(vec![ui_error])
           }
         }
-        MessageFromThread::PreviewCapture(capture) => {
-          let texture_handle = ctx.load_texture("preview_image", capture.0, Default::default());
-          self.last_preview_capture = Some(texture_handle);
+        MessageFromThread::Response { id, response } => {
+          responses.get_or_insert(vec![]).push((id, response));
+        }
+        MessageFromThread::ImageCaptured(capture) => {
+          if let Err(error) = self.on_image_captured(capture) {
+            errors.get_or_insert(vec![]).push(error.to_ui_error());
+          }
+        }
+        MessageFromThread::Intervalometer(status) => {
+          if let Some(camera) = &mut self.camera {
+            camera.intervalometer = Some((status, Instant::now()));
+          }
+        }
+        MessageFromThread::FocusStack(status) => {
+          if let Some(camera) = &mut self.camera {
+            camera.focus_stack = Some(status);
+          }
+        }
+        MessageFromThread::Bulb(status) => {
+          if let Some(camera) = &mut self.camera {
+            camera.bulb = Some((status, Instant::now()));
+          }
+        }
+        MessageFromThread::Import(status) => self.on_import_status(status),
+        MessageFromThread::LiveViewStopped => {
+          if let Some(camera) = &mut self.camera {
+            camera.live_view_enabled = false;
+          }
+        }
+        MessageFromThread::CameraConnected(descriptor) => {
+          if !self.camera_list.contains(&descriptor) {
+            self.camera_list.push(descriptor);
+          }
+        }
+        MessageFromThread::CameraDisconnected(descriptor) => {
+          self.camera_list.retain(|camera| camera != &descriptor);
+
+          if self.current_camera.as_ref() == Some(&descriptor) {
+            if let Some(camera) = &mut self.camera {
+              camera.connected = false;
+            }
+          }
+        }
+        MessageFromThread::CameraReconnected { descriptor, info } => {
+          self.current_camera = Some(descriptor);
+
+          if let Some(camera) = &mut self.camera {
+            // Modified settings and the live view state are kept, so the user can continue
+            camera.info = info;
+            camera.connected = true;
+            camera.block_config = false;
+            camera.apply_request = None;
+            camera.apply_kind = None;
+          }
+
+          if let Err(error) = self.reload_settings() {
+            errors.get_or_insert(vec![]).push(error.to_ui_error());
+          }
+        }
+        MessageFromThread::FileAdded(path) => {
+          if let Err(error) = self.on_file_added(path) {
+            errors.get_or_insert(vec![]).push(error.to_ui_error());
+          }
         }
-        MessageFromThread::Closure(cls) => {
-          closures.get_or_insert(vec![]).push(cls);
+        MessageFromThread::FolderAdded(path) => {
+          log::debug!("Folder {}/{} was added on the camera", path.folder, path.name);
+          self.media.mark_outdated();
+        }
+        MessageFromThread::SettingChanged(change) => {
+          if let Some(camera) = &mut self.camera {
+            camera.on_sequence_change(change);
+          }
+        }
+        MessageFromThread::CaptureComplete => log::debug!("The camera completed a capture"),
+        MessageFromThread::UnknownEvent(event) => {
+          log::debug!("Camera event: {}", event);
+
+          if let Some(camera) = &mut self.camera {
+            camera.settings_outdated = true;
+          }
         }
       }
     }
 
-    if let Some(closures) = closures {
-      for closure in closures {
-        closure(self);
+    if let Err(error) = self.reload_outdated_settings(ctx) {
+      errors.get_or_insert(vec![]).push(error.to_ui_error());
+    }
+
+    if let Some(responses) = responses {
+      for (id, response) in responses {
+        if let Err(error) = self.handle_response(ctx, id, response) {
+          errors.get_or_insert(vec![]).push(error.to_ui_error());
+        }
       }
     }
 
@@ -265,6 +653,232 @@ impl AppState {
     Ok(())
   }
 
+  fn handle_response(
+    &mut self,
+    ctx: &Context,
+    id: RequestId,
+    response: CameraResponse,
+  ) -> AppResult<()> {
+    log::trace!("Handling response to request #{}", id);
+
+    match response {
+      CameraResponse::Cameras(cameras) => {
+        let first_camera = cameras.first().cloned();
+        self.camera_list = cameras;
+
+        if self.first_load {
+          self.first_load = false;
+
+          if let Some(camera) = first_camera {
+            self.use_camera(camera)?;
+          }
+        }
+      }
+      CameraResponse::CameraOpened { descriptor, info } => {
+        self.current_camera = Some(descriptor);
+        self.media = Default::default();
+        self.camera = Some(UICamera {
+          info,
+          settings: None,
+          block_config: false,
+          modified_settings: Default::default(),
+          live_view_enabled: false,
+          connected: true,
+          intervalometer: None,
+          bracket_request: None,
+          focus_stack: None,
+          bulb: None,
+          settings_outdated: false,
+          settings_reloaded_at: None,
+          initial_settings: Default::default(),
+          apply_request: None,
+          apply_kind: None,
+          apply_results: vec![],
+          all_or_nothing: false,
+          external_changes: Default::default(),
+          conflicts: Default::default(),
+          history: Default::default(),
+          history_export: None,
+        });
+      }
+      CameraResponse::CameraClosed => {
+        self.camera = None;
+        self.current_camera = None;
+      }
+      CameraResponse::Config(config) => {
+        if let Some(camera) = &mut self.camera {
+          camera.set_settings(config);
+        }
+      }
+      CameraResponse::ConfigApplied { results, config } => {
+        if let Some(camera) = &mut self.camera {
+          let written: Vec<_> = results.iter().map(|result| result.setting.name.clone()).collect();
+          camera.on_config_applied(id, results);
+
+          match config {
+            Some(config) => {
+              camera.set_settings(config);
+              // Only changes made on the camera itself are highlighted
+              camera.external_changes.retain(|name, _| !written.contains(name));
+            }
+            None => self.reload_settings()?,
+          }
+        }
+      }
+      CameraResponse::LiveView(live_view) => {
+        if let Some(camera) = &mut self.camera {
+          camera.live_view_enabled = live_view;
+        }
+      }
+      CameraResponse::LiveViewFps(_) => {}
+      CameraResponse::ImageCaptured(capture) => self.on_image_captured(capture)?,
+      CameraResponse::Intervalometer(status) => {
+        if let Some(camera) = &mut self.camera {
+          camera.intervalometer = Some((status, Instant::now()));
+        }
+      }
+      CameraResponse::BracketCaptured(captures) => {
+        if let Some(camera) = &mut self.camera {
+          camera.bracket_request = None;
+        }
+
+        for capture in captures {
+          self.on_image_captured(capture)?;
+        }
+      }
+      CameraResponse::PreviewAnalysis(_) | CameraResponse::FocusDriven => {}
+      CameraResponse::FocusStack(status) => {
+        if let Some(camera) = &mut self.camera {
+          camera.focus_stack = Some(status);
+        }
+      }
+      CameraResponse::Bulb(status) => {
+        if let Some(camera) = &mut self.camera {
+          camera.bulb = Some((status, Instant::now()));
+        }
+      }
+      CameraResponse::Preview(frame) => self.on_preview(ctx, frame),
+      // The media browser lists all storages at once with `ListMedia`
+      CameraResponse::Folders { .. } | CameraResponse::Files { .. } => {}
+      CameraResponse::Media(files) => self.media.set_files(files),
+      CameraResponse::Thumbnail { path, image } => {
+        self.media.thumbnail_requests.remove(&id);
+        let texture = ctx.load_texture(
+          format!("thumbnail_{}/{}", path.folder, path.name),
+          image.0,
+          Default::default(),
+        );
+        self.media.thumbnails.insert(path, Thumbnail::Loaded(texture));
+      }
+      CameraResponse::MediaPreview { path, image } => {
+        if matches!(&self.media.preview_request, Some((request, _)) if *request == id) {
+          self.media.preview_request = None;
+        }
+
+        let texture = ctx.load_texture("media_preview", image.0, Default::default());
+        self.media.preview = Some((path, texture));
+      }
+      CameraResponse::FilesDownloaded(files) => {
+        for file in files {
+          self.on_image_captured(file)?;
+        }
+      }
+      CameraResponse::FilesDeleted(files) => {
+        self.media.selected.retain(|path| !files.contains(path));
+      }
+      CameraResponse::Import(status) => self.on_import_status(status),
+    }
+
+    Ok(())
+  }
+
+  fn on_preview(&mut self, ctx: &Context, frame: PreviewFrame) {
+    let texture_handle = ctx.load_texture("preview_image", frame.image.0, Default::default());
+    self.preview = LivePreview {
+      texture: Some(texture_handle),
+      histogram: Some(frame.histogram),
+      peaking: frame
+        .peaking
+        .map(|mask| ctx.load_texture("focus_peaking", mask.0, Default::default())),
+      zebra: frame.zebra.map(|mask| ctx.load_texture("zebra", mask.0, TextureFilter::Nearest)),
+      false_color: frame
+        .false_color
+        .map(|image| ctx.load_texture("false_color", image.0, Default::default())),
+      waveform: frame
+        .waveform
+        .map(|image| ctx.load_texture("waveform", image.0, Default::default())),
+      vectorscope: frame
+        .vectorscope
+        .map(|image| ctx.load_texture("vectorscope", image.0, Default::default())),
+    };
+  }
+
+  fn on_image_captured(&mut self, capture: CapturedImage) -> AppResult<()> {
+    self.settings.capture.next_sequence =
+      self.settings.capture.next_sequence.max(capture.sequence + 1);
+    self.last_capture = Some(capture.local_path);
+    Settings::save_next_sequence(self.settings.capture.next_sequence)
+  }
+
+  /// Downloads a picture that was taken with the shutter button of the camera
+  fn on_file_added(&mut self, path: CameraFilePath) -> AppResult<()> {
+    log::info!("{}/{} was added on the camera", path.folder, path.name);
+    self.media.mark_outdated();
+
+    if self.settings.capture.download_camera_shots {
+      let options = self.capture_options();
+      self.camera_thread.send_command(CameraCommand::DownloadCapture { path, options })?;
+    }
+
+    Ok(())
+  }
+
+  /// Reloads the settings after camera events, at most once per [`SETTINGS_RELOAD_INTERVAL`] as
+  /// turning a dial can cause a burst of events
+  ///
+  /// Cameras that do not report setting changes are polled every [`SETTINGS_POLL_INTERVAL`] while
+  /// the settings panel is open. Reading the whole config stalls the frames of the live view, so
+  /// both happen less often while it runs.
+  fn reload_outdated_settings(&mut self, ctx: &Context) -> AppResult<()> {
+    let poll = self.panes.camera_settings;
+    let camera = match &mut self.camera {
+      Some(camera) if camera.connected && camera.settings.is_some() && !camera.block_config => {
+        camera
+      }
+      _ => return Ok(()),
+    };
+
+    let (reload_interval, poll_interval) = if camera.live_view_enabled {
+      (LIVE_VIEW_RELOAD_INTERVAL, LIVE_VIEW_POLL_INTERVAL)
+    } else {
+      (SETTINGS_RELOAD_INTERVAL, SETTINGS_POLL_INTERVAL)
+    };
+
+    if poll && !camera.settings_outdated {
+      let since_reload = camera.settings_reloaded_at.get_or_insert_with(Instant::now).elapsed();
+
+      if since_reload >= poll_interval {
+        camera.settings_outdated = true;
+      } else {
+        ctx.request_repaint_after(poll_interval - since_reload);
+      }
+    }
+
+    if !camera.settings_outdated {
+      return Ok(());
+    }
+
+    let since_reload = camera.settings_reloaded_at.map(|reloaded_at| reloaded_at.elapsed());
+    if let Some(since_reload) = since_reload.filter(|since| *since < reload_interval) {
+      ctx.request_repaint_after(reload_interval - since_reload);
+      return Ok(());
+    }
+
+    camera.settings_outdated = false;
+    camera.settings_reloaded_at = Some(Instant::now());
+    self.reload_settings()
+  }
+
   pub(crate) fn stop_camera_thread(&mut self) {
     self.camera_thread.stop().unwrap()
   }
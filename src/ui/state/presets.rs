@@ -0,0 +1,32 @@
+//! Setting presets shown in the camera settings panel
+
+use crate::settings::SettingPreset;
+use gcam_lib::camera::presets::{PresetEntry, PresetStatus};
+
+#[derive(Debug, Default)]
+pub struct PresetsState {
+  /// Names of the saved presets, `None` until they are listed
+  pub names: Option<Vec<String>>,
+  /// Name entered for a new preset
+  pub new_name: String,
+  /// Preset that is compared with the camera before it is applied
+  pub loaded: Option<LoadedPreset>,
+}
+
+#[derive(Debug)]
+pub struct LoadedPreset {
+  pub preset: SettingPreset,
+  pub entries: Vec<PresetEntry>,
+  /// Model of the camera the preset is compared with
+  pub camera_model: String,
+}
+
+impl LoadedPreset {
+  pub fn model_mismatch(&self) -> bool {
+    self.preset.model != self.camera_model
+  }
+
+  pub fn count(&self, matches: impl Fn(&PresetStatus) -> bool) -> usize {
+    self.entries.iter().filter(|entry| matches(&entry.status)).count()
+  }
+}
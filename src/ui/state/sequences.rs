@@ -0,0 +1,144 @@
+//! Input values of the capture sequences, kept between frames while the user edits them
+
+use crate::cam_thread::messages::{
+  BracketOptions, BulbOptions, CaptureOptions, FocusStackOptions, IntervalometerLimit,
+  IntervalometerOptions,
+};
+use gcam_lib::{
+  camera::{
+    bracketing::BracketSetting,
+    focus::{FocusDirection, FocusStep, FocusStepSize},
+  },
+  utils::time::{local_utc_offset, next_time_of_day},
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalometerLimitKind {
+  Frames,
+  Duration,
+  EndTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct IntervalometerForm {
+  pub interval_secs: f64,
+  pub limit: IntervalometerLimitKind,
+  pub frames: u32,
+  pub duration_mins: f64,
+  /// Local time of day the sequence stops at, the next time it comes
+  pub end_hour: u32,
+  pub end_minute: u32,
+  pub start_delay_secs: f64,
+}
+
+impl Default for IntervalometerForm {
+  fn default() -> Self {
+    Self {
+      interval_secs: 5.,
+      limit: IntervalometerLimitKind::Frames,
+      frames: 100,
+      duration_mins: 60.,
+      end_hour: 6,
+      end_minute: 0,
+      start_delay_secs: 0.,
+    }
+  }
+}
+
+impl IntervalometerForm {
+  pub fn options(&self, capture: CaptureOptions) -> IntervalometerOptions {
+    IntervalometerOptions {
+      interval: Duration::from_secs_f64(self.interval_secs),
+      limit: match self.limit {
+        IntervalometerLimitKind::Frames => IntervalometerLimit::Frames(self.frames),
+        IntervalometerLimitKind::Duration => {
+          IntervalometerLimit::Duration(Duration::from_secs_f64(self.duration_mins * 60.))
+        }
+        IntervalometerLimitKind::EndTime => IntervalometerLimit::Until(self.end_time()),
+      },
+      start_delay: Duration::from_secs_f64(self.start_delay_secs),
+      capture,
+    }
+  }
+
+  pub fn end_time(&self) -> SystemTime {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let seconds_of_day = self.end_hour as i64 * 3600 + self.end_minute as i64 * 60;
+
+    let end = next_time_of_day(now, seconds_of_day, local_utc_offset(now));
+    // The offset changes when daylight saving time starts or ends before the end time
+    let end = next_time_of_day(now, seconds_of_day, local_utc_offset(end));
+
+    UNIX_EPOCH + Duration::from_secs(end as u64)
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct BracketForm {
+  pub setting: BracketSetting,
+  pub frames: u32,
+  pub spacing: f32,
+}
+
+impl Default for BracketForm {
+  fn default() -> Self {
+    Self { setting: BracketSetting::ShutterSpeed, frames: 3, spacing: 1. }
+  }
+}
+
+impl BracketForm {
+  pub fn options(&self, capture: CaptureOptions) -> BracketOptions {
+    BracketOptions { setting: self.setting, frames: self.frames, spacing: self.spacing, capture }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct FocusStackForm {
+  pub frames: u32,
+  pub direction: FocusDirection,
+  pub step_size: FocusStepSize,
+  pub steps_per_frame: u32,
+  pub settle_ms: u64,
+}
+
+impl Default for FocusStackForm {
+  fn default() -> Self {
+    Self {
+      frames: 20,
+      direction: FocusDirection::Far,
+      step_size: FocusStepSize::Small,
+      steps_per_frame: 1,
+      settle_ms: 500,
+    }
+  }
+}
+
+impl FocusStackForm {
+  pub fn options(&self, capture: CaptureOptions) -> FocusStackOptions {
+    FocusStackOptions {
+      frames: self.frames,
+      step: FocusStep { direction: self.direction, size: self.step_size },
+      steps_per_frame: self.steps_per_frame,
+      settle: Duration::from_millis(self.settle_ms),
+      capture,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct BulbForm {
+  pub duration_secs: f64,
+}
+
+impl Default for BulbForm {
+  fn default() -> Self {
+    Self { duration_secs: 60. }
+  }
+}
+
+impl BulbForm {
+  pub fn options(&self, capture: CaptureOptions) -> BulbOptions {
+    BulbOptions { duration: Duration::from_secs_f64(self.duration_secs), capture }
+  }
+}
@@ -0,0 +1,87 @@
+use crate::ui::state::{camera::UICamera, sequences::BracketForm};
+use eframe::{
+  egui::{Button, ComboBox, DragValue, Grid, RichText, Ui},
+  epaint::Color32,
+};
+use gcam_lib::camera::{
+  bracketing::{plan_bracket, BracketSetting},
+  settings::StaticWidget,
+};
+
+/// Returns `true` when the bracket should be captured
+pub fn show(ui: &mut Ui, form: &mut BracketForm, camera: &UICamera, can_capture: bool) -> bool {
+  let mut capture = false;
+
+  ui.collapsing("± Exposure bracketing", |ui| {
+    Grid::new("bracketing_options_grid").num_columns(2).show(ui, |ui| {
+      ui.label("Setting");
+      ComboBox::from_id_source("bracketing_setting").selected_text(form.setting.label()).show_ui(
+        ui,
+        |ui| {
+          for setting in BracketSetting::ALL {
+            ui.selectable_value(&mut form.setting, setting, setting.label());
+          }
+        },
+      );
+      ui.end_row();
+
+      ui.label("Frames");
+      ui.add(DragValue::new(&mut form.frames).clamp_range(2..=15));
+      ui.end_row();
+
+      ui.label("Spacing");
+      ui.add(DragValue::new(&mut form.spacing).clamp_range(0.3..=3.).speed(0.05).suffix(" EV"));
+      ui.end_row();
+    });
+
+    let setting =
+      camera.settings.as_ref().and_then(|settings| settings.find(form.setting.setting_name()));
+    let plan = match setting {
+      Some(setting) => plan_bracket(setting, form.setting, form.frames, form.spacing),
+      None => Err(format!("The camera has no {} setting", form.setting.setting_name()).into()),
+    };
+
+    match (&plan, setting.map(|setting| &setting.widget)) {
+      (Ok(plan), Some(StaticWidget::Radio { choices, .. })) => {
+        ui.label(format!("Camera steps: {}", step_label(plan.step)));
+        ui.label(format!(
+          "Frames: {}",
+          plan
+            .frames
+            .iter()
+            .map(|(choice, offset)| format!("{} ({:+.1})", choices[*choice], offset))
+            .collect::<Vec<_>>()
+            .join(", ")
+        ));
+      }
+      (Err(err), _) => {
+        ui.label(RichText::new(err.to_string()).color(Color32::YELLOW));
+      }
+      _ => {}
+    }
+
+    let running = camera.bracket_request.is_some();
+    if ui
+      .add_enabled(can_capture && !running && plan.is_ok(), Button::new("📷 Capture bracket"))
+      .clicked()
+    {
+      capture = true;
+    }
+
+    if running {
+      ui.label("Capturing bracket...");
+    }
+  });
+
+  capture
+}
+
+fn step_label(step: f32) -> &'static str {
+  if step < 0.4 {
+    "1/3 stop"
+  } else if step < 0.75 {
+    "1/2 stop"
+  } else {
+    "1 stop"
+  }
+}
@@ -0,0 +1,85 @@
+use crate::{
+  cam_thread::messages::{BulbState, BulbStatus},
+  ui::state::sequences::BulbForm,
+};
+use eframe::{
+  egui::{Button, DragValue, ProgressBar, Ui},
+  epaint::Color32,
+};
+use gcam_lib::utils::time::format_duration;
+use std::time::{Duration, Instant};
+
+/// How often the local countdown is redrawn
+const COUNTDOWN_REPAINT_INTERVAL: Duration = Duration::from_millis(200);
+
+pub enum BulbAction {
+  Start,
+  Stop,
+}
+
+pub fn show(
+  ui: &mut Ui,
+  form: &mut BulbForm,
+  status: Option<&(BulbStatus, Instant)>,
+  connected: bool,
+) -> Option<BulbAction> {
+  let mut action = None;
+
+  ui.collapsing("🌙 Bulb exposure", |ui| match status {
+    Some((status, received)) if status.state == BulbState::Exposing => {
+      // The camera thread only reports the start, the countdown runs locally
+      let exposed = (status.exposed + received.elapsed()).min(status.duration);
+      let remaining = status.duration.saturating_sub(exposed);
+      ui.ctx().request_repaint_after(COUNTDOWN_REPAINT_INTERVAL);
+
+      ui.add(
+        ProgressBar::new(exposed.as_secs_f32() / status.duration.as_secs_f32().max(f32::EPSILON))
+          .text(format!("{} remaining", format_duration(remaining))),
+      );
+
+      if ui.button("⏹ Close shutter now").clicked() {
+        action = Some(BulbAction::Stop);
+      }
+    }
+    Some((status, _)) if status.state == BulbState::CloseFailed => {
+      ui.horizontal(|ui| {
+        ui.spinner();
+        ui.colored_label(
+          Color32::RED,
+          "Failed to close the shutter, it may still be open. Trying again...",
+        );
+      });
+    }
+    Some((status, _)) if status.state == BulbState::Downloading => {
+      ui.horizontal(|ui| {
+        ui.spinner();
+        ui.label(format!(
+          "Exposed for {}, waiting for the camera to save the picture...",
+          format_duration(status.exposed)
+        ));
+      });
+    }
+    _ => {
+      if let Some((status, _)) = status {
+        ui.label(match status.state {
+          BulbState::Failed => "The last bulb exposure failed".to_string(),
+          _ => format!("Last exposure: {}", format_duration(status.exposed)),
+        });
+      }
+
+      ui.horizontal(|ui| {
+        ui.label("Duration");
+        ui.add(
+          DragValue::new(&mut form.duration_secs).clamp_range(1.0..=86400.).speed(1.).suffix(" s"),
+        );
+        ui.label(format_duration(Duration::from_secs_f64(form.duration_secs)));
+      });
+
+      if ui.add_enabled(connected, Button::new("⏺ Open shutter")).clicked() {
+        action = Some(BulbAction::Start);
+      }
+    }
+  });
+
+  action
+}
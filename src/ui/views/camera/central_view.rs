@@ -1,24 +1,58 @@
+use super::{
+  bracketing,
+  bulb::{self, BulbAction},
+  focus_stack::{self, FocusStackAction},
+  guides,
+  intervalometer::{self, IntervalometerAction},
+  preview, scopes,
+};
 use crate::{
-  cam_thread::settings::StaticWidget,
-  error::CatchAppResult,
-  ui::state::{camera::UICamera, AppState},
+  cam_thread::live_view::LiveViewStats,
+  error::{CatchAppResult, ToUIError},
+  ui::state::{camera::UICamera, live_view::LiveViewOverlays, AppState},
 };
 use eframe::{
-  egui::{Button, CentralPanel, Context, Direction, Frame, Layout, TopBottomPanel},
+  egui::{
+    Button, CentralPanel, Context, Direction, Frame, Grid, Layout, RichText, SidePanel, Slider,
+    TopBottomPanel, Ui,
+  },
   emath::Align,
+  epaint::Color32,
+};
+use gcam_lib::{
+  camera::{
+    focus::{FocusDirection, FocusStep, FocusStepSize, MANUAL_FOCUS_SETTING},
+    settings::StaticWidget,
+    zoom::LIVE_VIEW_ZOOM_SETTING,
+  },
+  utils::exposure::FALSE_COLOR_BANDS,
 };
-use gcam_lib::{error::AppResult, utils::geom::fit_size_into};
 
 pub fn show(ctx: &Context, state: &mut AppState) {
+  let live_view_stats = state.live_view_stats();
   let camera = state.camera.as_mut().unwrap();
   let mut set_live_view = None;
+  let mut capture_image = false;
+  let mut intervalometer_action = None;
+  let mut capture_bracket = false;
+  let mut focus_stack_action = None;
+  let mut focus_step = None;
+  let mut bulb_action = None;
+  let mut analysis_changed = false;
+  let mut guides_changed = false;
+  let mut zoom_changed = false;
+  let sensor_zoom_available = matches!(
+    camera.settings.as_ref().and_then(|settings| settings.find(LIVE_VIEW_ZOOM_SETTING)),
+    Some(setting) if !setting.readonly
+  );
+  let can_capture = camera.info.abilities.capture_image && camera.connected;
 
   CentralPanel::default().show(ctx, |ui| {
     ui.with_layout(Layout::bottom_up(Align::Center), |ui| {
       ui.horizontal(|ui| {
         if ui
           .add_enabled(
-            camera.info.abilities.camera_operations().capture_preview(),
+            camera.info.abilities.capture_preview,
             Button::new(if camera.live_view_enabled {
               "⏸ Stop live view"
             } else {
@@ -29,17 +63,71 @@ pub fn show(ctx: &Context, state: &mut AppState) {
         {
           set_live_view = Some(!camera.live_view_enabled);
         }
+
+        if camera.live_view_enabled {
+          show_live_view_stats(ui, &live_view_stats);
+        }
+
+        if ui.add_enabled(can_capture, Button::new("📷 Capture")).clicked() {
+          capture_image = true;
+        }
+
+        if let Some(last_capture) = &state.last_capture {
+          ui.label(format!("Saved {}", last_capture.display()));
+        }
+
+        if !camera.connected {
+          ui.label(
+            RichText::new("Camera disconnected, waiting for it to be reconnected...")
+              .color(Color32::YELLOW),
+          );
+        }
+      });
+
+      ui.vertical(|ui| {
+        intervalometer_action = intervalometer::show(
+          ui,
+          &mut state.intervalometer_form,
+          camera.intervalometer.as_ref(),
+          can_capture,
+        );
+        capture_bracket = bracketing::show(ui, &mut state.bracket_form, camera, can_capture);
+        focus_stack_action = focus_stack::show(
+          ui,
+          &mut state.focus_stack_form,
+          camera.focus_stack.as_ref(),
+          can_capture,
+        );
+        bulb_action = bulb::show(ui, &mut state.bulb_form, camera.bulb.as_ref(), camera.connected);
       });
 
       ui.with_layout(Layout::centered_and_justified(Direction::LeftToRight), |ui| {
         Frame::dark_canvas(ui.style()).show(ui, |ui| {
+          TopBottomPanel::top("live_view_toolbar").show_inside(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+              analysis_changed = live_view_toolbar(ui, &mut state.overlays);
+              analysis_changed |= scopes::toolbar(ui, &mut state.overlays);
+              ui.separator();
+              guides_changed = guides::toolbar(ui, &mut state.settings.guides);
+              ui.separator();
+              zoom_changed = preview::zoom_toolbar(ui, &mut state.zoom, sensor_zoom_available);
+            });
+          });
           TopBottomPanel::bottom("camera_preview_config")
-            .show_inside(ui, |_| camera_focus_ui(camera));
-          if let Some(preview) = &state.last_preview_capture {
-            ui.image(preview, fit_size_into(preview.size_vec2(), ui.available_size()));
-          } else {
-            ui.label("No preview has been captured");
+            .show_inside(ui, |ui| focus_step = camera_focus_ui(ui, camera));
+          if scopes::has_panel(&state.overlays) {
+            SidePanel::right("scopes_panel")
+              .resizable(true)
+              .default_width(300.)
+              .show_inside(ui, |ui| scopes::panel(ui, &state.preview, &state.overlays));
           }
+          zoom_changed |= preview::show(
+            ui,
+            &state.preview,
+            &state.overlays,
+            &state.settings.guides,
+            &mut state.zoom,
+          );
         });
       });
     })
@@ -48,26 +136,188 @@ pub fn show(ctx: &Context, state: &mut AppState) {
   if let Some(live_view_enabled) = set_live_view {
     let _ = state.set_live_view(live_view_enabled).catch(state);
   }
+
+  if analysis_changed {
+    let _ = state.update_preview_analysis().catch(state);
+  }
+
+  if zoom_changed {
+    let _ = state.update_sensor_zoom().catch(state);
+  }
+
+  if guides_changed {
+    if let Err(err) = state.settings.save() {
+      state.show_error(err.to_ui_error());
+    }
+  }
+
+  if capture_image {
+    let _ = state.capture_image().catch(state);
+  }
+
+  if capture_bracket {
+    let _ = state.capture_bracket().catch(state);
+  }
+
+  if let Some(step) = focus_step {
+    let _ = state.drive_focus(step).catch(state);
+  }
+
+  match bulb_action {
+    Some(BulbAction::Start) => {
+      let _ = state.start_bulb().catch(state);
+    }
+    Some(BulbAction::Stop) => {
+      let _ = state.stop_bulb().catch(state);
+    }
+    None => {}
+  }
+
+  match focus_stack_action {
+    Some(FocusStackAction::Start) => {
+      let _ = state.start_focus_stack().catch(state);
+    }
+    Some(FocusStackAction::Cancel) => {
+      let _ = state.cancel_focus_stack().catch(state);
+    }
+    None => {}
+  }
+
+  match intervalometer_action {
+    Some(IntervalometerAction::Start) => {
+      let _ = state.start_intervalometer().catch(state);
+    }
+    Some(IntervalometerAction::SetPaused(paused)) => {
+      let _ = state.set_intervalometer_paused(paused).catch(state);
+    }
+    Some(IntervalometerAction::Cancel) => {
+      let _ = state.cancel_intervalometer().catch(state);
+    }
+    None => {}
+  }
+}
+
+/// Overlay toggles above the preview, returns `true` if the camera thread has to compute
+/// different overlays
+fn live_view_toolbar(ui: &mut Ui, overlays: &mut LiveViewOverlays) -> bool {
+  let mut analysis_changed = false;
+
+  ui.toggle_value(&mut overlays.histogram, "📊 Histogram");
+
+  ui.separator();
+
+  analysis_changed |= ui.toggle_value(&mut overlays.focus_peaking, "🔍 Focus peaking").changed();
+  if overlays.focus_peaking {
+    ui.color_edit_button_srgba(&mut overlays.peaking_color);
+    analysis_changed |= ui
+      .add(Slider::new(&mut overlays.peaking_threshold, 1..=255).text("Threshold"))
+      .on_hover_text("Lower values highlight more edges")
+      .changed();
+  }
+
+  ui.separator();
+
+  analysis_changed |= ui.toggle_value(&mut overlays.zebra, "🦓 Zebras").changed();
+  if overlays.zebra {
+    analysis_changed |= ui
+      .add(Slider::new(&mut overlays.zebra_level, 50..=100).suffix(" IRE"))
+      .on_hover_text("Pixels at or above this level are striped")
+      .changed();
+  }
+
+  analysis_changed |= ui
+    .toggle_value(&mut overlays.false_color, "🌈 False color")
+    .on_hover_ui(false_color_legend)
+    .changed();
+
+  analysis_changed
 }
 
-fn camera_focus_ui(camera: &mut UICamera) -> AppResult<()> {
+fn false_color_legend(ui: &mut Ui) {
+  let mut lower = 0.;
+
+  for (upper, color) in FALSE_COLOR_BANDS {
+    if let Some(color) = color {
+      ui.label(RichText::new(format!("■ {} - {} IRE", lower, upper)).color(color));
+    }
+
+    lower = upper;
+  }
+}
+
+/// Manual focus buttons, returns the step to drive when one was clicked
+fn camera_focus_ui(ui: &mut Ui, camera: &UICamera) -> Option<FocusStep> {
   // TODO: Different camera models will have a different action for this
-  if let Some(manualfocusdrive_setting) = camera
+  let manualfocusdrive_setting = camera
     .settings
     .as_ref()
     .and_then(|settings| settings.get_child("actions"))
-    .and_then(|settings| settings.get_child("manualfocusdrive"))
-  {
-    if let StaticWidget::Range { .. } = &manualfocusdrive_setting.widget {
-      // TODO
-    } else {
-      // TODO: Don't spam da logs
-      log::error!(
-        "Invalid widget type for manualfocusdrive: {:?}, expected Range widget",
-        manualfocusdrive_setting.widget
-      );
-    }
+    .and_then(|settings| settings.get_child(MANUAL_FOCUS_SETTING))?;
+
+  if !matches!(
+    manualfocusdrive_setting.widget,
+    StaticWidget::Range { .. } | StaticWidget::Radio { .. }
+  ) {
+    ui.label(format!("Unsupported manual focus widget: {:?}", manualfocusdrive_setting.widget));
+    return None;
   }
 
-  Ok(())
+  let mut step = None;
+
+  ui.add_enabled_ui(camera.connected && !manualfocusdrive_setting.readonly, |ui| {
+    ui.horizontal(|ui| {
+      let buttons = [
+        ("⏪", FocusDirection::Near, FocusStepSize::Large),
+        ("◀◀", FocusDirection::Near, FocusStepSize::Medium),
+        ("◀", FocusDirection::Near, FocusStepSize::Small),
+        ("▶", FocusDirection::Far, FocusStepSize::Small),
+        ("▶▶", FocusDirection::Far, FocusStepSize::Medium),
+        ("⏩", FocusDirection::Far, FocusStepSize::Large),
+      ];
+
+      for (index, (text, direction, size)) in buttons.into_iter().enumerate() {
+        if index == 3 {
+          ui.label("Focus");
+        }
+
+        if ui
+          .button(text)
+          .on_hover_text(format!("{} ({} step)", direction.label(), size.label().to_lowercase()))
+          .clicked()
+        {
+          step = Some(FocusStep { direction, size });
+        }
+      }
+    });
+  });
+
+  step
+}
+
+fn show_live_view_stats(ui: &mut Ui, stats: &LiveViewStats) {
+  let dropped = stats.dropped_before_decoding + stats.dropped_before_display;
+
+  ui.label(format!(
+    "{:.1} fps, {} ms latency, {} dropped",
+    stats.display_fps,
+    stats.latency.as_millis(),
+    dropped
+  ))
+  .on_hover_ui(|ui| {
+    Grid::new("live_view_stats").num_columns(2).show(ui, |ui| {
+      for (label, value) in [
+        ("Frames downloaded", stats.captured.to_string()),
+        ("Frames shown", stats.displayed.to_string()),
+        ("Dropped before decoding", stats.dropped_before_decoding.to_string()),
+        ("Dropped before display", stats.dropped_before_display.to_string()),
+        ("Decoding errors", stats.decode_errors.to_string()),
+        ("Decoding time", format!("{} ms", stats.decode_time.as_millis())),
+        ("Latency", format!("{} ms", stats.latency.as_millis())),
+      ] {
+        ui.label(label);
+        ui.label(value);
+        ui.end_row();
+      }
+    });
+  });
 }
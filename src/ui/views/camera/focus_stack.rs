@@ -0,0 +1,88 @@
+use crate::{
+  cam_thread::messages::{FocusStackStatus, SequenceState},
+  ui::state::sequences::FocusStackForm,
+};
+use eframe::egui::{Button, DragValue, Grid, ProgressBar, Ui};
+use gcam_lib::camera::focus::{FocusDirection, FocusStepSize};
+
+pub enum FocusStackAction {
+  Start,
+  Cancel,
+}
+
+pub fn show(
+  ui: &mut Ui,
+  form: &mut FocusStackForm,
+  status: Option<&FocusStackStatus>,
+  can_capture: bool,
+) -> Option<FocusStackAction> {
+  let mut action = None;
+
+  ui.collapsing("🔍 Focus stacking", |ui| match status {
+    Some(status) if status.state == SequenceState::Running => {
+      ui.add(
+        ProgressBar::new(status.frames_taken as f32 / status.total_frames as f32)
+          .text(format!("{} / {} frames", status.frames_taken, status.total_frames)),
+      );
+
+      if status.failed_frames > 0 {
+        ui.label(format!("{} frames failed", status.failed_frames));
+      }
+
+      if ui.button("⏹ Cancel").clicked() {
+        action = Some(FocusStackAction::Cancel);
+      }
+    }
+    _ => {
+      if let Some(status) = status {
+        ui.label(format!(
+          "{} after {} of {} frames ({} failed)",
+          if status.state == SequenceState::Cancelled { "Cancelled" } else { "Finished" },
+          status.frames_taken,
+          status.total_frames,
+          status.failed_frames
+        ));
+      }
+
+      options(ui, form);
+
+      if ui.add_enabled(can_capture, Button::new("⏺ Start")).clicked() {
+        action = Some(FocusStackAction::Start);
+      }
+    }
+  });
+
+  action
+}
+
+#[inline]
+fn options(ui: &mut Ui, form: &mut FocusStackForm) {
+  Grid::new("focus_stack_options_grid").num_columns(2).show(ui, |ui| {
+    ui.label("Frames");
+    ui.add(DragValue::new(&mut form.frames).clamp_range(2..=1000));
+    ui.end_row();
+
+    ui.label("Direction");
+    ui.horizontal(|ui| {
+      ui.radio_value(&mut form.direction, FocusDirection::Near, "Near");
+      ui.radio_value(&mut form.direction, FocusDirection::Far, "Far");
+    });
+    ui.end_row();
+
+    ui.label("Step size");
+    ui.horizontal(|ui| {
+      for size in FocusStepSize::ALL {
+        ui.radio_value(&mut form.step_size, size, size.label());
+      }
+    });
+    ui.end_row();
+
+    ui.label("Steps per frame");
+    ui.add(DragValue::new(&mut form.steps_per_frame).clamp_range(1..=100));
+    ui.end_row();
+
+    ui.label("Settle time");
+    ui.add(DragValue::new(&mut form.settle_ms).clamp_range(0..=10000).speed(10.).suffix(" ms"));
+    ui.end_row();
+  });
+}
@@ -0,0 +1,120 @@
+//! Composition guides and aspect ratio masks over the preview
+
+use crate::settings::{AspectMask, CompositionGuide, GuideSettings};
+use eframe::{
+  egui::{ComboBox, DragValue, Painter, Rect, Stroke, Ui, Vec2},
+  emath::pos2,
+  epaint::Color32,
+};
+use gcam_lib::utils::geom::ViewTransform;
+
+const GOLDEN_SECTION: f32 = 0.381_966;
+/// Length of the arms of the center cross relative to the shorter side of the frame
+const CROSS_SIZE: f32 = 0.05;
+
+/// Guide and mask selection for the live view toolbar, returns `true` if the settings should be
+/// saved. Values that are dragged are only saved once the drag ends.
+pub fn toolbar(ui: &mut Ui, settings: &mut GuideSettings) -> bool {
+  let mut changed = false;
+
+  ComboBox::from_id_source("composition_guide").selected_text(settings.guide.label()).show_ui(
+    ui,
+    |ui| {
+      for guide in CompositionGuide::ALL {
+        changed |= ui.selectable_value(&mut settings.guide, guide, guide.label()).changed();
+      }
+    },
+  );
+
+  if settings.guide == CompositionGuide::Grid {
+    let columns = ui.add(DragValue::new(&mut settings.grid_columns).clamp_range(1..=32));
+    ui.label("×");
+    let rows = ui.add(DragValue::new(&mut settings.grid_rows).clamp_range(1..=32));
+
+    for response in [columns, rows] {
+      changed |= response.drag_released() || response.lost_focus();
+    }
+  }
+
+  ComboBox::from_id_source("aspect_mask").selected_text(settings.aspect_mask.label()).show_ui(
+    ui,
+    |ui| {
+      for mask in AspectMask::ALL {
+        changed |= ui.selectable_value(&mut settings.aspect_mask, mask, mask.label()).changed();
+      }
+    },
+  );
+
+  changed
+}
+
+/// Paints the mask and the guides, the guides are placed inside of the unmasked frame
+pub fn paint(painter: &Painter, image_rect: Rect, settings: &GuideSettings) {
+  let frame = match settings.aspect_mask.ratio() {
+    Some(ratio) => {
+      let frame = ViewTransform::fit(Vec2::new(ratio, 1.), image_rect).image_rect();
+      paint_mask(painter, image_rect, frame);
+      frame
+    }
+    None => image_rect,
+  };
+
+  let stroke = Stroke::new(1., Color32::from_white_alpha(150));
+  let vertical = |x: f32| [frame.lerp(Vec2::new(x, 0.)), frame.lerp(Vec2::new(x, 1.))];
+  let horizontal = |y: f32| [frame.lerp(Vec2::new(0., y)), frame.lerp(Vec2::new(1., y))];
+
+  let lines = match settings.guide {
+    CompositionGuide::None => vec![],
+    CompositionGuide::Thirds => {
+      vec![vertical(1. / 3.), vertical(2. / 3.), horizontal(1. / 3.), horizontal(2. / 3.)]
+    }
+    CompositionGuide::GoldenRatio => vec![
+      vertical(GOLDEN_SECTION),
+      vertical(1. - GOLDEN_SECTION),
+      horizontal(GOLDEN_SECTION),
+      horizontal(1. - GOLDEN_SECTION),
+    ],
+    CompositionGuide::CenterCross => {
+      let center = frame.center();
+      let arm = frame.width().min(frame.height()) * CROSS_SIZE;
+      vec![
+        [pos2(center.x - arm, center.y), pos2(center.x + arm, center.y)],
+        [pos2(center.x, center.y - arm), pos2(center.x, center.y + arm)],
+      ]
+    }
+    CompositionGuide::Diagonals => {
+      vec![[frame.left_top(), frame.right_bottom()], [frame.right_top(), frame.left_bottom()]]
+    }
+    CompositionGuide::Grid => {
+      let columns = settings.grid_columns.max(1);
+      let rows = settings.grid_rows.max(1);
+
+      (1..columns)
+        .map(|column| vertical(column as f32 / columns as f32))
+        .chain((1..rows).map(|row| horizontal(row as f32 / rows as f32)))
+        .collect()
+    }
+  };
+
+  for line in lines {
+    painter.line_segment(line, stroke);
+  }
+}
+
+/// Darkens the parts of `image_rect` outside of `frame`
+fn paint_mask(painter: &Painter, image_rect: Rect, frame: Rect) {
+  let color = Color32::from_black_alpha(180);
+
+  for rect in [
+    Rect::from_min_max(image_rect.min, pos2(image_rect.max.x, frame.min.y)),
+    Rect::from_min_max(pos2(image_rect.min.x, frame.max.y), image_rect.max),
+    Rect::from_min_max(pos2(image_rect.min.x, frame.min.y), pos2(frame.min.x, frame.max.y)),
+    Rect::from_min_max(pos2(frame.max.x, frame.min.y), pos2(image_rect.max.x, frame.max.y)),
+  ] {
+    if rect.is_positive() {
+      painter.rect_filled(rect, 0., color);
+    }
+  }
+
+  painter.rect_stroke(frame, 0., Stroke::new(1., Color32::from_white_alpha(100)));
+}
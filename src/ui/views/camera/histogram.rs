@@ -0,0 +1,82 @@
+//! Histogram overlay in a corner of the preview
+
+use eframe::{
+  egui::{Align2, FontId, Painter, Rect, Stroke, Vec2},
+  emath::pos2,
+  epaint::{Color32, Shape},
+};
+use gcam_lib::utils::histogram::{Histogram, BINS};
+
+const SIZE: Vec2 = Vec2::new(256., 110.);
+const MARGIN: f32 = 8.;
+const TEXT_HEIGHT: f32 = 14.;
+
+/// Paints the histogram into the bottom right corner of `image_rect`
+pub fn paint(painter: &Painter, image_rect: Rect, histogram: &Histogram) {
+  let size = Vec2::new(
+    SIZE.x.min(image_rect.width() - 2. * MARGIN),
+    SIZE.y.min(image_rect.height() - 2. * MARGIN),
+  );
+  if size.x < 64. || size.y < 48. {
+    return;
+  }
+
+  let rect = Rect::from_min_size(image_rect.max - size - Vec2::splat(MARGIN), size);
+  painter.rect_filled(rect, 4., Color32::from_black_alpha(160));
+
+  let plot =
+    Rect::from_min_max(rect.min + Vec2::new(4., 4.), rect.max - Vec2::new(4., TEXT_HEIGHT + 4.));
+  let peak = histogram.peak().max(1) as f32;
+  let bin_width = plot.width() / BINS as f32;
+  let height = |count: u32| (count as f32 / peak).min(1.) * plot.height();
+
+  // Luminance as a filled area, one bar per bin
+  for (bin, &count) in histogram.luma.iter().enumerate() {
+    let x = plot.min.x + (bin as f32 + 0.5) * bin_width;
+    painter.line_segment(
+      [pos2(x, plot.max.y), pos2(x, plot.max.y - height(count))],
+      Stroke::new(bin_width.max(1.), Color32::from_white_alpha(70)),
+    );
+  }
+
+  for (channel, color) in [
+    (&histogram.red, Color32::from_rgba_unmultiplied(255, 60, 60, 200)),
+    (&histogram.green, Color32::from_rgba_unmultiplied(60, 255, 60, 200)),
+    (&histogram.blue, Color32::from_rgba_unmultiplied(80, 120, 255, 200)),
+  ] {
+    let points = channel
+      .iter()
+      .enumerate()
+      .map(|(bin, &count)| {
+        pos2(plot.min.x + (bin as f32 + 0.5) * bin_width, plot.max.y - height(count))
+      })
+      .collect();
+    painter.add(Shape::line(points, Stroke::new(1., color)));
+  }
+
+  let clipping_color = |percentage: f32| {
+    if percentage >= 1. {
+      Color32::LIGHT_RED
+    } else {
+      Color32::LIGHT_GRAY
+    }
+  };
+  let font = FontId::proportional(11.);
+  let shadows = histogram.shadow_clipping();
+  let highlights = histogram.highlight_clipping();
+
+  painter.text(
+    pos2(plot.min.x, rect.max.y - 3.),
+    Align2::LEFT_BOTTOM,
+    format!("◀ {:.1}%", shadows),
+    font.clone(),
+    clipping_color(shadows),
+  );
+  painter.text(
+    pos2(plot.max.x, rect.max.y - 3.),
+    Align2::RIGHT_BOTTOM,
+    format!("{:.1}% ▶", highlights),
+    font,
+    clipping_color(highlights),
+  );
+}
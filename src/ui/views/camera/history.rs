@@ -0,0 +1,134 @@
+use crate::{error::CatchAppResult, ui::state::AppState};
+use eframe::{
+  egui::{Button, Context, Grid, Key, Modifiers, RichText, ScrollArea, TopBottomPanel, Ui},
+  epaint::Color32,
+};
+use gcam_lib::{
+  camera::history::{EntryState, HistoryEntry},
+  utils::time::format_time_of_day,
+};
+
+/// Ctrl+Z undoes the last setting change, Ctrl+Y or Ctrl+Shift+Z redoes it, unless a text field
+/// has the focus
+pub fn handle_shortcuts(ctx: &Context, state: &mut AppState) {
+  if ctx.memory().focus().is_some() {
+    return;
+  }
+
+  let (can_undo, can_redo) = match &state.camera {
+    Some(camera) if camera.connected && !camera.block_config => {
+      (camera.history.undo_target().is_some(), camera.history.redo_target().is_some())
+    }
+    _ => return,
+  };
+
+  let mut input = ctx.input_mut();
+  let undo = input.consume_key(Modifiers::COMMAND, Key::Z);
+  let redo = input.consume_key(Modifiers::COMMAND, Key::Y)
+    || input.consume_key(Modifiers { shift: true, ..Modifiers::COMMAND }, Key::Z);
+  drop(input);
+
+  if undo && can_undo {
+    let _ = state.undo_setting_change().catch(state);
+  } else if redo && can_redo {
+    let _ = state.redo_setting_change().catch(state);
+  }
+}
+
+pub fn show(ctx: &Context, state: &mut AppState) {
+  let camera = match &state.camera {
+    Some(camera) => camera,
+    None => return,
+  };
+  let ready = camera.connected && !camera.block_config;
+  let mut undo = false;
+  let mut redo = false;
+  let mut export = false;
+
+  TopBottomPanel::bottom("settings_history").min_height(100.).resizable(true).show(ctx, |ui| {
+    ui.horizontal(|ui| {
+      ui.strong("Settings history");
+
+      let undo_button = Button::new("Undo");
+      if ui
+        .add_enabled(ready && camera.history.undo_target().is_some(), undo_button)
+        .on_hover_text("Ctrl+Z")
+        .clicked()
+      {
+        undo = true;
+      }
+
+      let redo_button = Button::new("Redo");
+      if ui
+        .add_enabled(ready && camera.history.redo_target().is_some(), redo_button)
+        .on_hover_text("Ctrl+Y")
+        .clicked()
+      {
+        redo = true;
+      }
+
+      if ui.add_enabled(!camera.history.entries.is_empty(), Button::new("Export CSV")).clicked() {
+        export = true;
+      }
+
+      if let Some(path) = &camera.history_export {
+        ui.small(format!("Exported to {}", path.display()));
+      }
+    });
+    ui.separator();
+
+    if camera.history.entries.is_empty() {
+      ui.label("No settings were changed yet");
+      return;
+    }
+
+    ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+      Grid::new("settings_history_entries").num_columns(5).striped(true).show(ui, |ui| {
+        ui.strong("Time (UTC)");
+        ui.strong("Source");
+        ui.strong("Setting");
+        ui.strong("Old value");
+        ui.strong("New value");
+        ui.end_row();
+
+        for entry in camera.history.entries.iter().rev() {
+          display_entry(ui, entry);
+        }
+      });
+    });
+  });
+
+  if undo {
+    let _ = state.undo_setting_change().catch(state);
+  } else if redo {
+    let _ = state.redo_setting_change().catch(state);
+  } else if export {
+    let _ = state.export_setting_history().catch(state);
+  }
+}
+
+fn display_entry(ui: &mut Ui, entry: &HistoryEntry) {
+  let color = match entry.state {
+    EntryState::Applied => None,
+    EntryState::Undone | EntryState::Abandoned => Some(Color32::GRAY),
+  };
+  let text = |text: String| match color {
+    Some(color) => RichText::new(text).color(color).strikethrough(),
+    None => RichText::new(text),
+  };
+
+  for (index, setting) in entry.change.settings.iter().enumerate() {
+    if index == 0 {
+      ui.label(text(format_time_of_day(entry.change.timestamp())));
+      ui.label(text(entry.change.source.to_string()));
+    } else {
+      ui.label("");
+      ui.label("");
+    }
+
+    ui.label(text(setting.new.label.clone())).on_hover_text(&setting.new.name);
+    ui.label(text(setting.old_value()));
+    ui.label(text(setting.new_value()));
+    ui.end_row();
+  }
+}
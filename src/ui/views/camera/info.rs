@@ -1,15 +1,16 @@
-use crate::{
-  cam_thread::settings::{display_radio_choice, CameraSettings, StaticWidget},
-  ui::state::{camera::UICamera, AppState},
-};
+use crate::ui::state::{camera::UICamera, AppState};
 use eframe::{
   egui::{Context, Grid, ScrollArea, SidePanel, Ui, Window},
   emath::Align2,
   epaint::Vec2,
 };
+use gcam_lib::camera::{
+  info::StorageInfo,
+  settings::{display_radio_choice, CameraSettings, StaticWidget},
+};
 use gphoto2::{
   abilities::CameraDriverStatus,
-  filesys::{AccessType, FilesystemType, StorageInfo, StorageType},
+  filesys::{AccessType, FilesystemType, StorageType},
 };
 
 pub fn show(ctx: &Context, state: &mut AppState) {
@@ -89,19 +90,19 @@ fn storage_display(ui: &mut Ui, storage: &StorageInfo, index: usize) {
   ui.collapsing(format!("Storage #{}", index), |ui| {
     ScrollArea::horizontal().show(ui, |ui| {
       Grid::new("camera_storages_grid").striped(true).show(ui, |ui| {
-        if let Some(label) = storage.label() {
+        if let Some(label) = &storage.label {
           ui.label("Label");
           ui.label(label);
           ui.end_row();
         }
 
-        if let Some(description) = storage.description() {
+        if let Some(description) = &storage.description {
           ui.label("Description");
           ui.label(description);
           ui.end_row();
         }
 
-        if let Some(storage_type) = storage.storage_type() {
+        if let Some(storage_type) = &storage.storage_type {
           ui.label("Storage type");
           ui.label(match storage_type {
             StorageType::Unknown => "Unknown",
@@ -113,7 +114,7 @@ fn storage_display(ui: &mut Ui, storage: &StorageInfo, index: usize) {
           ui.end_row();
         }
 
-        if let Some(filesystem_type) = storage.filesystem_type() {
+        if let Some(filesystem_type) = &storage.filesystem_type {
           ui.label("Filesystem type");
           ui.label(match filesystem_type {
             FilesystemType::Dcf => "DCIM filesystem",
@@ -124,7 +125,7 @@ fn storage_display(ui: &mut Ui, storage: &StorageInfo, index: usize) {
           ui.end_row();
         }
 
-        if let Some(access) = storage.access_type() {
+        if let Some(access) = &storage.access_type {
           ui.label("Access permissions");
           ui.label(match access {
             AccessType::Ro => "Read only",
@@ -134,13 +135,13 @@ fn storage_display(ui: &mut Ui, storage: &StorageInfo, index: usize) {
           ui.end_row();
         }
 
-        if let Some(capacity) = storage.capacity_kb() {
+        if let Some(capacity) = storage.capacity_kb {
           ui.label("Capacity");
           ui.label(format!("{} Kb", capacity));
           ui.end_row();
         }
 
-        if let Some(free) = storage.free_kb() {
+        if let Some(free) = storage.free_kb {
           ui.label("Free");
           ui.label(format!("{} Kb", free));
           ui.end_row();
@@ -162,7 +163,7 @@ fn driver_information(ui: &mut Ui, additional_dialog_open: &mut bool, camera: &U
     ui.end_row();
 
     ui.label("Driver status");
-    ui.label(match camera.info.abilities.driver_status() {
+    ui.label(match camera.info.abilities.driver_status {
       CameraDriverStatus::Production => "Stable",
       CameraDriverStatus::Testing => "Testing",
       CameraDriverStatus::Experimental => "Experimental",
@@ -0,0 +1,170 @@
+use crate::{
+  cam_thread::messages::{IntervalometerStatus, SequenceState},
+  ui::state::sequences::{IntervalometerForm, IntervalometerLimitKind},
+};
+use eframe::egui::{Button, DragValue, Grid, ProgressBar, Ui};
+use gcam_lib::utils::time::format_duration;
+use std::time::{Duration, Instant};
+
+/// How often the countdown is redrawn between two statuses of the camera thread
+const COUNTDOWN_REPAINT_INTERVAL: Duration = Duration::from_millis(200);
+
+pub enum IntervalometerAction {
+  Start,
+  SetPaused(bool),
+  Cancel,
+}
+
+pub fn show(
+  ui: &mut Ui,
+  form: &mut IntervalometerForm,
+  status: Option<&(IntervalometerStatus, Instant)>,
+  can_capture: bool,
+) -> Option<IntervalometerAction> {
+  let mut action = None;
+
+  ui.collapsing("⏱ Intervalometer", |ui| match status {
+    Some((status, received))
+      if matches!(
+        status.state,
+        SequenceState::Waiting | SequenceState::Running | SequenceState::Paused
+      ) =>
+    {
+      progress(ui, status, *received);
+
+      ui.horizontal(|ui| {
+        let paused = status.state == SequenceState::Paused;
+
+        if ui.button(if paused { "▶ Resume" } else { "⏸ Pause" }).clicked() {
+          action = Some(IntervalometerAction::SetPaused(!paused));
+        }
+
+        if ui.button("⏹ Cancel").clicked() {
+          action = Some(IntervalometerAction::Cancel);
+        }
+      });
+    }
+    _ => {
+      if let Some((status, _)) = status {
+        ui.label(format!(
+          "{} after {} of {} frames ({} failed, {} skipped)",
+          if status.state == SequenceState::Cancelled { "Cancelled" } else { "Finished" },
+          status.frames_taken,
+          status.total_frames,
+          status.failed_frames,
+          status.skipped_frames
+        ));
+      }
+
+      options(ui, form);
+
+      if ui.add_enabled(can_capture, Button::new("⏺ Start")).clicked() {
+        action = Some(IntervalometerAction::Start);
+      }
+    }
+  });
+
+  action
+}
+
+#[inline]
+fn options(ui: &mut Ui, form: &mut IntervalometerForm) {
+  Grid::new("intervalometer_options_grid").num_columns(2).show(ui, |ui| {
+    ui.label("Interval");
+    ui.add(
+      DragValue::new(&mut form.interval_secs).clamp_range(0.5..=86400.).speed(0.1).suffix(" s"),
+    );
+    ui.end_row();
+
+    ui.label("Stop after");
+    ui.horizontal(|ui| {
+      ui.radio_value(&mut form.limit, IntervalometerLimitKind::Frames, "Frames");
+      ui.radio_value(&mut form.limit, IntervalometerLimitKind::Duration, "Time");
+      ui.radio_value(&mut form.limit, IntervalometerLimitKind::EndTime, "End time");
+    });
+    ui.end_row();
+
+    match form.limit {
+      IntervalometerLimitKind::Frames => {
+        ui.label("Frames");
+        ui.add(DragValue::new(&mut form.frames).clamp_range(1..=100000));
+      }
+      IntervalometerLimitKind::Duration => {
+        ui.label("Duration");
+        ui.add(
+          DragValue::new(&mut form.duration_mins)
+            .clamp_range(0.1..=10080.)
+            .speed(1.)
+            .suffix(" min"),
+        );
+      }
+      IntervalometerLimitKind::EndTime => {
+        ui.label("End time");
+        ui.horizontal(|ui| {
+          ui.add(DragValue::new(&mut form.end_hour).clamp_range(0..=23));
+          ui.label(":");
+          ui.add(
+            DragValue::new(&mut form.end_minute)
+              .clamp_range(0..=59)
+              .custom_formatter(|minute, _| format!("{:02}", minute)),
+          );
+        })
+        .response
+        .on_hover_text("The sequence stops at the next time it is this local time of day");
+      }
+    }
+    ui.end_row();
+
+    ui.label("Start delay");
+    ui.add(DragValue::new(&mut form.start_delay_secs).clamp_range(0.0..=86400.).suffix(" s"));
+    ui.end_row();
+  });
+}
+
+#[inline]
+fn progress(ui: &mut Ui, status: &IntervalometerStatus, received: Instant) {
+  // The camera thread only reports a status per frame, the countdown runs locally in between
+  let elapsed = if status.state == SequenceState::Paused {
+    Duration::ZERO
+  } else {
+    ui.ctx().request_repaint_after(COUNTDOWN_REPAINT_INTERVAL);
+    received.elapsed()
+  };
+
+  ui.add(
+    ProgressBar::new(status.frames_taken as f32 / status.total_frames as f32)
+      .text(format!("{} / {} frames", status.frames_taken, status.total_frames)),
+  );
+
+  Grid::new("intervalometer_status_grid").num_columns(2).striped(true).show(ui, |ui| {
+    ui.label("State");
+    ui.label(match status.state {
+      SequenceState::Waiting => "Waiting for the start delay",
+      SequenceState::Paused => "Paused",
+      _ => "Running",
+    });
+    ui.end_row();
+
+    if let Some(next_frame_in) = status.next_frame_in {
+      ui.label("Next frame in");
+      ui.label(format_duration(next_frame_in.saturating_sub(elapsed)));
+      ui.end_row();
+    }
+
+    ui.label("Remaining");
+    ui.label(format_duration(status.remaining.saturating_sub(elapsed)));
+    ui.end_row();
+
+    if let Some(drift) = status.last_drift {
+      ui.label("Drift");
+      ui.label(format!("{} ms", drift.as_millis()));
+      ui.end_row();
+    }
+
+    if status.failed_frames > 0 || status.skipped_frames > 0 {
+      ui.label("Failed / skipped");
+      ui.label(format!("{} / {}", status.failed_frames, status.skipped_frames));
+      ui.end_row();
+    }
+  });
+}
@@ -1,11 +1,396 @@
-use eframe::egui::{Context, TopBottomPanel};
+use crate::{
+  cam_thread::messages::{ImportMode, ImportStatus, MediaFile, SequenceState},
+  error::CatchAppResult,
+  ui::state::{
+    media::{MediaBrowser, Thumbnail},
+    AppState,
+  },
+};
+use eframe::{
+  egui::{
+    Align2, Button, Context, FontId, Grid, ProgressBar, Rect, ScrollArea, Sense, SidePanel,
+    TopBottomPanel, Ui, Vec2, Window,
+  },
+  emath::pos2,
+  epaint::{Color32, Shape},
+};
+use gcam_lib::{
+  backend::CameraFilePath,
+  utils::{geom::ViewTransform, size::format_size, time::format_timestamp},
+};
 
-use crate::ui::state::AppState;
+const CELL_SIZE: Vec2 = Vec2::new(150., 150.);
+const THUMBNAIL_HEIGHT: f32 = 100.;
 
-pub fn show(ctx: &Context, _state: &mut AppState) {
-  // TODO
-  TopBottomPanel::bottom("camera_media")
-    .min_height(100.)
-    .resizable(true)
-    .show(ctx, |ui| ui.centered_and_justified(|ui| ui.label("TODO")));
+pub fn show(ctx: &Context, state: &mut AppState) {
+  let connected = matches!(&state.camera, Some(camera) if camera.connected);
+  let media = &mut state.media;
+  let mut refresh = !media.loaded && media.list_request.is_none() && connected;
+  let mut download = false;
+  let mut delete = false;
+  let mut import = None;
+  let mut cancel_import = false;
+  let mut visible = vec![];
+
+  TopBottomPanel::bottom("camera_media").min_height(100.).resizable(true).show(ctx, |ui| {
+    ui.horizontal(|ui| {
+      ui.strong("Camera media");
+
+      if ui.add_enabled(media.list_request.is_none(), Button::new("🔄 Refresh")).clicked() {
+        refresh = true;
+      }
+
+      if media.list_request.is_some() {
+        ui.spinner();
+      }
+
+      ui.label(format!("{} files, {} selected", media.files.len(), media.selected.len()));
+
+      if ui.button("Select all").clicked() {
+        media.selected = media.files.iter().map(|file| file.path.clone()).collect();
+      }
+
+      if ui.add_enabled(!media.selected.is_empty(), Button::new("Select none")).clicked() {
+        media.selected.clear();
+      }
+
+      let has_selection = connected && !media.selected.is_empty();
+      if ui.add_enabled(has_selection, Button::new("⬇ Download")).clicked() {
+        download = true;
+      }
+
+      if ui.add_enabled(has_selection, Button::new("🗑 Delete")).clicked() {
+        media.confirm_delete = true;
+      }
+
+      ui.separator();
+
+      let importing = matches!(&media.import, Some(status) if is_running(status));
+      if ui
+        .add_enabled(connected && !importing, Button::new("📥 Import new"))
+        .on_hover_text("Imports the files that were not imported before")
+        .clicked()
+      {
+        import = Some(ImportMode::New);
+      }
+
+      if ui.add_enabled(connected && !importing, Button::new("Import all")).clicked() {
+        import = Some(ImportMode::All);
+      }
+    });
+
+    if let Some(status) = &media.import {
+      cancel_import = import_progress(ui, status);
+    }
+
+    ui.separator();
+
+    SidePanel::right("camera_media_preview")
+      .resizable(true)
+      .default_width(300.)
+      .show_inside(ui, |ui| preview(ui, media));
+
+    visible = file_grid(ui, media);
+  });
+
+  if media.confirm_delete {
+    let mut open = true;
+
+    Window::new("Delete files")
+      .collapsible(false)
+      .resizable(false)
+      .open(&mut open)
+      .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+      .show(ctx, |ui| {
+        ui.label(format!(
+          "Delete {} files from the camera? This can not be undone.",
+          media.selected.len()
+        ));
+
+        ui.horizontal(|ui| {
+          if ui.button("🗑 Delete").clicked() {
+            delete = true;
+            media.confirm_delete = false;
+          }
+
+          if ui.button("Cancel").clicked() {
+            media.confirm_delete = false;
+          }
+        });
+      });
+
+    if !open {
+      media.confirm_delete = false;
+    }
+  }
+
+  let load_preview = media.missing_preview().cloned().filter(|_| connected);
+
+  if refresh {
+    let _ = state.refresh_media().catch(state);
+  }
+
+  if download {
+    let _ = state.download_selected_media().catch(state);
+  }
+
+  if delete {
+    let _ = state.delete_selected_media().catch(state);
+  }
+
+  if let Some(mode) = import {
+    let _ = state.start_import(mode).catch(state);
+  }
+
+  if cancel_import {
+    let _ = state.cancel_import().catch(state);
+  }
+
+  if let Some(path) = load_preview {
+    let _ = state.load_media_preview(path).catch(state);
+  }
+
+  if connected {
+    for path in visible {
+      if !state.media.can_request_thumbnail() {
+        break;
+      }
+
+      let _ = state.load_thumbnail(path).catch(state);
+    }
+  }
+}
+
+fn is_running(status: &ImportStatus) -> bool {
+  matches!(status.state, SequenceState::Running | SequenceState::Paused)
+}
+
+/// Shows the progress of an import, returns `true` if it should be cancelled
+fn import_progress(ui: &mut Ui, status: &ImportStatus) -> bool {
+  let mut cancel = false;
+  let done = status.imported + status.failed;
+  let counts = format!(
+    "{} imported, {} already imported, {} failed",
+    status.imported, status.skipped, status.failed
+  );
+
+  if !is_running(status) {
+    let state =
+      if status.state == SequenceState::Cancelled { "Import cancelled" } else { "Import finished" };
+    ui.label(format!("{}: {}", state, counts));
+    return false;
+  }
+
+  ui.horizontal(|ui| {
+    if status.state == SequenceState::Paused {
+      ui.label("⏸ Waiting for the camera to be reconnected");
+    } else if let Some(name) = &status.current_file {
+      ui.label(match status.current_size {
+        Some(size) => format!("Importing {} ({})", name, format_size(size)),
+        None => format!("Importing {}", name),
+      });
+      ui.spinner();
+    }
+
+    if ui.button("⏹ Cancel").clicked() {
+      cancel = true;
+    }
+  });
+
+  let progress = if status.bytes_total > 0 {
+    status.bytes_done as f32 / status.bytes_total as f32
+  } else {
+    done as f32 / status.total_files.max(1) as f32
+  };
+
+  ui.add(ProgressBar::new(progress).text(format!(
+    "{} / {} files, {} / {} ({})",
+    done,
+    status.total_files,
+    format_size(status.bytes_done),
+    format_size(status.bytes_total),
+    counts
+  )));
+
+  cancel
+}
+
+/// Shows the files of the camera, returns the visible files that need a thumbnail
+fn file_grid(ui: &mut Ui, media: &mut MediaBrowser) -> Vec<CameraFilePath> {
+  let mut missing_thumbnails = vec![];
+
+  if media.files.is_empty() {
+    ui.centered_and_justified(|ui| {
+      ui.label(if media.list_failed {
+        "The files could not be listed, press Refresh to try again"
+      } else if media.loaded {
+        "The camera storage is empty"
+      } else {
+        "Loading files..."
+      })
+    });
+    return missing_thumbnails;
+  }
+
+  let spacing = ui.spacing().item_spacing;
+  let columns = ((ui.available_width() + spacing.x) / (CELL_SIZE.x + spacing.x)).max(1.) as usize;
+  let rows = media.files.len().div_ceil(columns);
+  let mut clicked = None;
+
+  ScrollArea::vertical().auto_shrink([false, false]).show_rows(
+    ui,
+    CELL_SIZE.y,
+    rows,
+    |ui, row_range| {
+      for row in row_range {
+        ui.horizontal(|ui| {
+          for index in row * columns..((row + 1) * columns).min(media.files.len()) {
+            let file = &media.files[index];
+
+            if !media.thumbnails.contains_key(&file.path) {
+              missing_thumbnails.push(file.path.clone());
+            }
+
+            if file_cell(ui, media, file).clicked() {
+              clicked = Some(index);
+            }
+          }
+        });
+      }
+    },
+  );
+
+  if let Some(index) = clicked {
+    let path = media.files[index].path.clone();
+    let modifiers = ui.input().modifiers;
+
+    if modifiers.shift {
+      // Selects everything between the focused file and the clicked one
+      let focused = media
+        .focused
+        .as_ref()
+        .and_then(|focused| media.files.iter().position(|file| &file.path == focused))
+        .unwrap_or(index);
+
+      for file in &media.files[focused.min(index)..=focused.max(index)] {
+        media.selected.insert(file.path.clone());
+      }
+    } else if modifiers.command {
+      if !media.selected.remove(&path) {
+        media.selected.insert(path.clone());
+      }
+    } else {
+      media.selected.clear();
+      media.selected.insert(path.clone());
+    }
+
+    if media.focused.as_ref() != Some(&path) {
+      media.preview_failed = None;
+    }
+    media.focused = Some(path);
+  }
+
+  missing_thumbnails
+}
+
+fn file_cell(ui: &mut Ui, media: &MediaBrowser, file: &MediaFile) -> eframe::egui::Response {
+  let (rect, response) = ui.allocate_exact_size(CELL_SIZE, Sense::click());
+  let painter = ui.painter_at(rect);
+  let visuals = ui.visuals();
+
+  if media.selected.contains(&file.path) {
+    painter.rect_filled(rect, 4., visuals.selection.bg_fill);
+  } else if response.hovered() {
+    painter.rect_filled(rect, 4., visuals.widgets.hovered.bg_fill);
+  }
+
+  let thumbnail_rect =
+    Rect::from_min_size(rect.min + Vec2::splat(4.), Vec2::new(rect.width() - 8., THUMBNAIL_HEIGHT));
+
+  match media.thumbnails.get(&file.path) {
+    Some(Thumbnail::Loaded(texture)) => {
+      painter.add(Shape::image(
+        texture.id(),
+        ViewTransform::fit(texture.size_vec2(), thumbnail_rect).image_rect(),
+        Rect::from_min_max(pos2(0., 0.), pos2(1., 1.)),
+        Color32::WHITE,
+      ));
+    }
+    thumbnail => {
+      painter.text(
+        thumbnail_rect.center(),
+        Align2::CENTER_CENTER,
+        if matches!(thumbnail, Some(Thumbnail::Unavailable)) { "📄" } else { "⏳" },
+        FontId::proportional(32.),
+        visuals.weak_text_color(),
+      );
+    }
+  }
+
+  let text_color = visuals.text_color();
+  let mut text_pos = pos2(rect.center().x, thumbnail_rect.max.y + 4.);
+  for line in [
+    file.path.name.clone(),
+    file.info.size.map(format_size).unwrap_or_default(),
+    file.info.mtime.map(format_timestamp).unwrap_or_default(),
+  ] {
+    painter.text(text_pos, Align2::CENTER_TOP, line, FontId::proportional(11.), text_color);
+    text_pos.y += 13.;
+  }
+
+  response.on_hover_text(format!("{}/{}", file.path.folder, file.path.name))
+}
+
+fn preview(ui: &mut Ui, media: &MediaBrowser) {
+  let file = match media
+    .focused
+    .as_ref()
+    .and_then(|focused| media.files.iter().find(|file| &file.path == focused))
+  {
+    Some(file) => file,
+    None => {
+      ui.centered_and_justified(|ui| ui.label("Select a file to preview it"));
+      return;
+    }
+  };
+
+  Grid::new("camera_media_file_info").num_columns(2).show(ui, |ui| {
+    ui.label("Name");
+    ui.label(&file.path.name);
+    ui.end_row();
+
+    ui.label("Folder");
+    ui.label(&file.path.folder);
+    ui.end_row();
+
+    if let Some(size) = file.info.size {
+      ui.label("Size");
+      ui.label(format_size(size));
+      ui.end_row();
+    }
+
+    if let Some(mtime) = file.info.mtime {
+      ui.label("Date");
+      ui.label(format_timestamp(mtime));
+      ui.end_row();
+    }
+
+    if let Some(mime_type) = &file.info.mime_type {
+      ui.label("Type");
+      ui.label(mime_type);
+      ui.end_row();
+    }
+  });
+
+  match &media.preview {
+    Some((path, texture)) if path == &file.path => {
+      ui.centered_and_justified(|ui| {
+        let fitted = ViewTransform::fit(texture.size_vec2(), ui.available_rect_before_wrap());
+        ui.image(texture, fitted.image_rect().size())
+      });
+    }
+    _ => {
+      ui.centered_and_justified(|ui| ui.spinner());
+    }
+  }
 }
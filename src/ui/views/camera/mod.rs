@@ -1,4 +1,13 @@
+pub mod bracketing;
+pub mod bulb;
 pub mod central_view;
+pub mod focus_stack;
+pub mod guides;
+pub mod histogram;
+pub mod history;
 pub mod info;
+pub mod intervalometer;
 pub mod media;
+pub mod preview;
+pub mod scopes;
 pub mod settings;
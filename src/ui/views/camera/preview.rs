@@ -0,0 +1,110 @@
+//! The live view preview with its overlays, zoomed with the mouse wheel and panned by dragging
+
+use super::{guides, histogram, scopes};
+use crate::{
+  settings::GuideSettings,
+  ui::state::live_view::{LivePreview, LiveViewOverlays, LiveViewZoom},
+};
+use eframe::{
+  egui::{Button, Checkbox, Rect, Sense, Ui},
+  emath::pos2,
+  epaint::{Color32, Shape},
+};
+
+/// Points of scrolling that double the zoom
+const SCROLL_PER_DOUBLING: f32 = 200.;
+
+/// Zoom controls for the live view toolbar, returns `true` if the zoom was changed
+pub fn zoom_toolbar(ui: &mut Ui, zoom: &mut LiveViewZoom, sensor_zoom_available: bool) -> bool {
+  let mut changed = false;
+  let pixel_zoom = zoom.view.pixel_zoom(ui.ctx().pixels_per_point());
+  let center = zoom.view.viewport.center();
+
+  ui.label(format!("🔍 {:.0} %", zoom.view.zoom / pixel_zoom * 100.))
+    .on_hover_text("Scroll to zoom, drag to pan and click to switch between fit and 100 %");
+
+  if ui.add_enabled(zoom.view.zoom > 1., Button::new("Fit")).clicked() {
+    zoom.view.zoom_at(center, 1.);
+    changed = true;
+  }
+
+  if ui.button("100 %").clicked() {
+    zoom.view.zoom_at(center, pixel_zoom);
+    changed = true;
+  }
+
+  changed |= ui
+    .add_enabled(sensor_zoom_available, Checkbox::new(&mut zoom.sensor_zoom, "Sensor zoom"))
+    .on_hover_text("Zooms with the camera, so the preview shows sensor pixels")
+    .changed();
+
+  changed
+}
+
+/// Shows the preview, returns `true` if the zoom was changed
+pub fn show(
+  ui: &mut Ui,
+  preview: &LivePreview,
+  overlays: &LiveViewOverlays,
+  guides: &GuideSettings,
+  zoom: &mut LiveViewZoom,
+) -> bool {
+  let texture = match &preview.texture {
+    Some(texture) => texture,
+    None => {
+      ui.label("No preview has been captured");
+      return false;
+    }
+  };
+
+  let (viewport, response) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+  zoom.view = zoom.view.with_layout(texture.size_vec2(), viewport);
+  let zoom_before = zoom.view.zoom;
+
+  if let Some(cursor) = response.hover_pos() {
+    let scroll = ui.input().scroll_delta.y;
+    if scroll != 0. {
+      zoom.view.zoom_at(cursor, zoom.view.zoom * (scroll / SCROLL_PER_DOUBLING).exp2());
+    }
+  }
+
+  if response.dragged() {
+    zoom.view.pan(response.drag_delta());
+  }
+
+  // 100 % at the cursor, or back to fitting the whole image
+  if let (true, Some(cursor)) = (response.clicked(), response.interact_pointer_pos()) {
+    if zoom.view.zoom > 1. {
+      zoom.view.zoom_at(cursor, 1.);
+    } else {
+      zoom.view.zoom_at(cursor, zoom.view.pixel_zoom(ui.ctx().pixels_per_point()));
+    }
+  }
+
+  let view = zoom.display_view();
+  let image_rect = view.image_rect();
+  let painter = ui.painter_at(viewport);
+  let full_uv = Rect::from_min_max(pos2(0., 0.), pos2(1., 1.));
+
+  painter.add(Shape::image(texture.id(), image_rect, full_uv, Color32::WHITE));
+
+  for (enabled, texture, tint) in [
+    (overlays.false_color, &preview.false_color, Color32::WHITE),
+    (overlays.zebra, &preview.zebra, Color32::WHITE),
+    (overlays.focus_peaking, &preview.peaking, overlays.peaking_color),
+  ] {
+    if let (true, Some(texture)) = (enabled, texture) {
+      painter.add(Shape::image(texture.id(), image_rect, full_uv, tint));
+    }
+  }
+
+  guides::paint(&painter, image_rect, guides);
+
+  if let (true, Some(preview_histogram)) = (overlays.histogram, &preview.histogram) {
+    histogram::paint(&painter, view.visible_rect(), preview_histogram);
+  }
+
+  scopes::paint_overlays(&painter, view.visible_rect(), preview, overlays);
+
+  zoom.view.zoom != zoom_before
+}
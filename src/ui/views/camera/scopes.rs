@@ -0,0 +1,143 @@
+//! Waveform monitor and vectorscope, in a panel next to the preview or over it
+
+use crate::ui::state::live_view::{LivePreview, LiveViewOverlays, ScopeDisplay};
+use eframe::{
+  egui::{Align2, ComboBox, FontId, Painter, Rect, Sense, Stroke, Ui, Vec2},
+  emath::pos2,
+  epaint::{Color32, Shape, TextureHandle},
+};
+use gcam_lib::utils::scopes::chroma_position;
+
+const GRATICULE: Color32 = Color32::from_rgba_premultiplied(120, 120, 120, 160);
+/// Levels of the waveform graticule in IRE
+const WAVEFORM_LINES: [u8; 5] = [0, 25, 50, 75, 100];
+/// Largest size of a scope drawn over the preview, relative to the preview width
+const OVERLAY_SIZE: f32 = 0.3;
+
+/// Scope selection for the live view toolbar, returns `true` if the camera thread has to compute
+/// different scopes
+pub fn toolbar(ui: &mut Ui, overlays: &mut LiveViewOverlays) -> bool {
+  let mut changed = false;
+
+  for (id, label, display) in [
+    ("waveform_display", "Waveform", &mut overlays.waveform),
+    ("vectorscope_display", "Vectorscope", &mut overlays.vectorscope),
+  ] {
+    let selected_text = format!("{}: {}", label, display.label());
+    ComboBox::from_id_source(id).selected_text(selected_text).show_ui(ui, |ui| {
+      for option in ScopeDisplay::ALL {
+        changed |= ui.selectable_value(display, option, option.label()).changed();
+      }
+    });
+  }
+
+  changed
+}
+
+/// Whether the scope panel has something to show
+pub fn has_panel(overlays: &LiveViewOverlays) -> bool {
+  overlays.waveform == ScopeDisplay::Panel || overlays.vectorscope == ScopeDisplay::Panel
+}
+
+/// Shows the scopes that are in the panel
+pub fn panel(ui: &mut Ui, preview: &LivePreview, overlays: &LiveViewOverlays) {
+  let width = ui.available_width();
+
+  if overlays.waveform == ScopeDisplay::Panel {
+    ui.label("Waveform");
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(width, width * 0.6), Sense::hover());
+    paint_waveform(ui.painter(), rect, preview.waveform.as_ref());
+  }
+
+  if overlays.vectorscope == ScopeDisplay::Panel {
+    ui.label("Vectorscope");
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(width), Sense::hover());
+    paint_vectorscope(ui.painter(), rect, preview.vectorscope.as_ref());
+  }
+}
+
+/// Paints the scopes that are shown over the preview into the top corners of `image_rect`
+pub fn paint_overlays(
+  painter: &Painter,
+  image_rect: Rect,
+  preview: &LivePreview,
+  overlays: &LiveViewOverlays,
+) {
+  let width = (image_rect.width() * OVERLAY_SIZE).min(256.);
+  let margin = Vec2::splat(8.);
+
+  if overlays.waveform == ScopeDisplay::Overlay {
+    let rect = Rect::from_min_size(image_rect.min + margin, Vec2::new(width, width * 0.6));
+    paint_waveform(painter, rect, preview.waveform.as_ref());
+  }
+
+  if overlays.vectorscope == ScopeDisplay::Overlay {
+    let min = pos2(image_rect.max.x - width - margin.x, image_rect.min.y + margin.y);
+    paint_vectorscope(
+      painter,
+      Rect::from_min_size(min, Vec2::splat(width)),
+      preview.vectorscope.as_ref(),
+    );
+  }
+}
+
+fn paint_waveform(painter: &Painter, rect: Rect, texture: Option<&TextureHandle>) {
+  painter.rect_filled(rect, 4., Color32::from_black_alpha(180));
+
+  for level in WAVEFORM_LINES {
+    let y = rect.max.y - rect.height() * level as f32 / 100.;
+    painter.line_segment([pos2(rect.min.x, y), pos2(rect.max.x, y)], Stroke::new(1., GRATICULE));
+    painter.text(
+      pos2(rect.min.x + 2., y),
+      if level == 100 { Align2::LEFT_TOP } else { Align2::LEFT_BOTTOM },
+      level.to_string(),
+      FontId::proportional(9.),
+      GRATICULE,
+    );
+  }
+
+  paint_trace(painter, rect, texture);
+}
+
+fn paint_vectorscope(painter: &Painter, rect: Rect, texture: Option<&TextureHandle>) {
+  let radius = rect.width().min(rect.height()) / 2.;
+  let stroke = Stroke::new(1., GRATICULE);
+
+  painter.circle_filled(rect.center(), radius, Color32::from_black_alpha(180));
+  painter.circle_stroke(rect.center(), radius, stroke);
+  painter.line_segment([rect.center_top(), rect.center_bottom()], stroke);
+  painter.line_segment([rect.left_center(), rect.right_center()], stroke);
+
+  // Targets of 75 % color bars
+  for (label, color) in [
+    ("R", Color32::from_rgb(191, 0, 0)),
+    ("Yl", Color32::from_rgb(191, 191, 0)),
+    ("G", Color32::from_rgb(0, 191, 0)),
+    ("Cy", Color32::from_rgb(0, 191, 191)),
+    ("B", Color32::from_rgb(0, 0, 191)),
+    ("Mg", Color32::from_rgb(191, 0, 191)),
+  ] {
+    let target = rect.lerp(chroma_position(color).to_vec2());
+    painter.rect_stroke(Rect::from_center_size(target, Vec2::splat(8.)), 0., stroke);
+    painter.text(
+      target + Vec2::new(6., -6.),
+      Align2::LEFT_BOTTOM,
+      label,
+      FontId::proportional(9.),
+      GRATICULE,
+    );
+  }
+
+  paint_trace(painter, rect, texture);
+}
+
+fn paint_trace(painter: &Painter, rect: Rect, texture: Option<&TextureHandle>) {
+  if let Some(texture) = texture {
+    painter.add(Shape::image(
+      texture.id(),
+      rect,
+      Rect::from_min_max(pos2(0., 0.), pos2(1., 1.)),
+      Color32::WHITE,
+    ));
+  }
+}
@@ -1,49 +1,92 @@
 use crate::{
-  cam_thread::settings::{display_radio_choice, CameraSettings, RadioChoice, StaticWidget},
+  cam_thread::messages::{SettingOutcome, SettingResult},
   error::CatchAppResult,
   settings::Settings,
   ui::{
-    state::{camera::ModifiedSettingsMap, AppState},
+    state::{
+      camera::{SettingsFilter, UICamera},
+      presets::PresetsState,
+      AppState,
+    },
     widgets,
   },
 };
 use eframe::{
   egui::{
-    ComboBox, Context, DragValue, Frame, Layout, ScrollArea, SidePanel, Slider, TopBottomPanel, Ui,
+    Button, CollapsingHeader, ComboBox, Context, DragValue, Frame, Layout, RichText, ScrollArea,
+    SidePanel, Slider, TextEdit, TopBottomPanel, Ui,
   },
   emath::Align,
   epaint::Color32,
 };
+use gcam_lib::camera::settings::{display_radio_choice, CameraSettings, RadioChoice, StaticWidget};
+
+enum SettingAction {
+  Modify {
+    setting: CameraSettings,
+    section_id: i32,
+  },
+  /// Resolves a conflict with a change made on the camera
+  KeepEdit(i32),
+  UseCameraValue(i32),
+}
+
+enum PresetAction {
+  Save(String),
+  Load(String),
+  Delete(String),
+}
 
 pub fn show(ctx: &Context, state: &mut AppState) {
-  let mut changed_setting = None;
+  let mut setting_action = None;
   let mut apply_settings = false;
   let mut discard_settings = false;
+  let mut reload_settings = false;
+  let mut preset_action = None;
+  let mut all_or_nothing = matches!(&state.camera, Some(camera) if camera.all_or_nothing);
+
+  if state.presets.names.is_none() {
+    let _ = state.list_presets().catch(state);
+  }
 
   if let Some(camera) = &state.camera {
+    let presets = &mut state.presets;
+    let filter = &mut state.settings_filter;
+
     SidePanel::right("camera_settings_panel").show(ctx, |ui| {
       ui.horizontal(|ui| {
         ui.heading("Camera settings");
 
-        if ui.with_layout(Layout::right_to_left(Align::Center), |ui| ui.button("↻")).inner.clicked()
-        {
-          state.reload_settings().unwrap();
-        }
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+          if ui.button("↻").clicked() {
+            reload_settings = true;
+          }
+
+          ui.add_enabled_ui(camera.settings.is_some(), |ui| {
+            ui.menu_button("Presets", |ui| {
+              preset_action = presets_menu(ui, presets);
+              if preset_action.is_some() {
+                ui.close_menu();
+              }
+            })
+          });
+        });
       });
       ui.separator();
 
-      let mut set_setting =
-        |setting: CameraSettings, section_id: i32| changed_setting = Some((setting, section_id));
+      let mut on_action = |action: SettingAction| setting_action = Some(action);
 
       if let Some(camera_settings) = &camera.settings {
         display_settings_root(
           ui,
           &state.settings,
           camera_settings,
-          &camera.modified_settings,
-          &mut set_setting,
+          camera,
+          filter,
+          &mut on_action,
           &mut apply_settings,
           &mut discard_settings,
+          &mut all_or_nothing,
         );
       } else {
         ui.centered_and_justified(|ui| ui.label("Loading settings..."));
@@ -51,98 +94,316 @@ pub fn show(ctx: &Context, state: &mut AppState) {
     });
   }
 
+  if reload_settings {
+    let _ = state.reload_settings().catch(state);
+  }
+
+  match preset_action {
+    Some(PresetAction::Save(name)) => {
+      let _ = state.save_preset(name).catch(state);
+    }
+    Some(PresetAction::Load(name)) => {
+      let _ = state.load_preset(&name).catch(state);
+    }
+    Some(PresetAction::Delete(name)) => {
+      let _ = state.delete_preset(&name).catch(state);
+    }
+    None => {}
+  }
+
+  if let Some(camera) = &mut state.camera {
+    camera.all_or_nothing = all_or_nothing;
+  }
+
   if apply_settings {
     let _ = state.apply_settings().catch(state);
   } else if let Some(camera) = &mut state.camera {
-    if let Some((setting, section_id)) = changed_setting {
-      camera.modify_setting(section_id, setting);
-    } else if discard_settings {
-      camera.discard_settings();
+    match setting_action {
+      Some(SettingAction::Modify { setting, section_id }) => {
+        camera.modify_setting(section_id, setting)
+      }
+      Some(SettingAction::KeepEdit(id)) => camera.keep_edit(id),
+      Some(SettingAction::UseCameraValue(id)) => camera.use_camera_value(id),
+      None if discard_settings => camera.discard_settings(),
+      None => {}
     }
   }
 }
 
+fn presets_menu(ui: &mut Ui, presets: &mut PresetsState) -> Option<PresetAction> {
+  let mut action = None;
+
+  ui.horizontal(|ui| {
+    ui.add(TextEdit::singleline(&mut presets.new_name).hint_text("Preset name"));
+
+    if ui.add_enabled(!presets.new_name.trim().is_empty(), Button::new("Save")).clicked() {
+      action = Some(PresetAction::Save(presets.new_name.trim().to_string()));
+    }
+  });
+
+  ui.separator();
+
+  match presets.names.as_deref() {
+    Some([]) | None => {
+      ui.label("No presets saved yet");
+    }
+    Some(names) => {
+      for name in names {
+        ui.horizontal(|ui| {
+          if ui.button(name).on_hover_text("Compare with the camera and apply").clicked() {
+            action = Some(PresetAction::Load(name.clone()));
+          }
+
+          if ui.small_button("🗑").on_hover_text("Delete preset").clicked() {
+            action = Some(PresetAction::Delete(name.clone()));
+          }
+        });
+      }
+    }
+  }
+
+  action
+}
+
 #[inline]
+#[allow(clippy::too_many_arguments)]
 fn display_settings_root(
   ui: &mut Ui,
   app_settings: &Settings,
   camera_settings: &CameraSettings,
-  modified_settings: &ModifiedSettingsMap,
-  set_setting: &mut impl FnMut(CameraSettings, i32),
+  camera: &UICamera,
+  filter: &mut SettingsFilter,
+  on_action: &mut impl FnMut(SettingAction),
   apply_settings: &mut bool,
   discard_settings: &mut bool,
+  all_or_nothing: &mut bool,
 ) {
   if let StaticWidget::Group { children, .. } = &camera_settings.widget {
     TopBottomPanel::bottom("camera_settings_apply").show_inside(ui, |ui| {
-      ui.add_enabled_ui(!modified_settings.is_empty(), |ui| {
-        ui.horizontal_centered(|ui| {
-          if ui.button("Apply").clicked() {
+      display_apply_results(ui, &camera.apply_results);
+
+      ui.horizontal_centered(|ui| {
+        ui.add_enabled_ui(!camera.modified_settings.is_empty(), |ui| {
+          if ui
+            .add_enabled(camera.connected && !camera.block_config, Button::new("Apply"))
+            .clicked()
+          {
             *apply_settings = true;
           }
           if ui.button("Cancel").clicked() {
             *discard_settings = true;
           }
-        })
-      })
+        });
+
+        if camera.block_config {
+          ui.spinner();
+        }
+
+        ui.checkbox(all_or_nothing, "All or nothing")
+          .on_hover_text("Restore the previous values when a setting can not be applied");
+      });
     });
 
+    display_filter(ui, filter);
+    ui.separator();
+
+    let filtering = filter.is_active();
+    let mut matches = 0;
+
     ScrollArea::vertical().show(ui, |ui| {
       for section in children.values() {
         if app_settings.dev_settings.exclude_settings.contains(&section.name) {
           continue;
         }
 
-        ui.collapsing(&section.name, |ui| {
-          display_settings_section(ui, app_settings, section, modified_settings, set_setting)
-        });
+        let settings = visible_settings(app_settings, section, camera, filter);
+        if filtering && settings.is_empty() {
+          continue;
+        }
+        matches += settings.len();
+
+        // Sections with matches are expanded, without changing the state they have when the
+        // filter is cleared
+        CollapsingHeader::new(&section.name)
+          .id_source((&section.name, filtering))
+          .default_open(filtering)
+          .show(ui, |ui| display_settings_section(ui, section.id, settings, camera, on_action));
+      }
+
+      if filtering && matches == 0 {
+        ui.label("No settings match the filter");
       }
     });
   }
 }
 
+fn outcome_text(outcome: &SettingOutcome) -> String {
+  match outcome {
+    SettingOutcome::Applied => "Applied".into(),
+    SettingOutcome::Failed(error) => format!("Not applied: {}", error),
+    SettingOutcome::RolledBack => "Restored because another setting failed".into(),
+    SettingOutcome::Skipped => "Not applied because another setting failed".into(),
+  }
+}
+
+/// Summary of the last apply, with the settings that were not applied on hover
+fn display_apply_results(ui: &mut Ui, results: &[SettingResult]) {
+  if results.is_empty() {
+    return;
+  }
+
+  let applied = results.iter().filter(|result| result.outcome == SettingOutcome::Applied).count();
+
+  ui.horizontal(|ui| {
+    if applied > 0 {
+      ui.label(RichText::new(format!("✔ {} applied", applied)).color(Color32::LIGHT_GREEN));
+    }
+
+    if applied < results.len() {
+      ui.label(
+        RichText::new(format!("✖ {} not applied", results.len() - applied))
+          .color(Color32::LIGHT_RED),
+      )
+      .on_hover_ui(|ui| {
+        for result in results.iter().filter(|result| result.outcome != SettingOutcome::Applied) {
+          ui.label(format!("{}: {}", result.setting.label, outcome_text(&result.outcome)));
+        }
+      });
+    }
+  });
+}
+
+fn display_filter(ui: &mut Ui, filter: &mut SettingsFilter) {
+  ui.horizontal(|ui| {
+    ui.add(TextEdit::singleline(&mut filter.query).hint_text("🔍 Search settings"));
+
+    if !filter.query.is_empty() && ui.small_button("✖").clicked() {
+      filter.query.clear();
+    }
+  });
+
+  ui.horizontal_wrapped(|ui| {
+    ui.toggle_value(&mut filter.modified_only, "Modified");
+    ui.toggle_value(&mut filter.writable_only, "Writable");
+    ui.toggle_value(&mut filter.changed_only, "Changed since connect");
+  });
+}
+
+/// Settings of a section that pass the filter, with their modified value and whether they are
+/// modified
+fn visible_settings(
+  app_settings: &Settings,
+  section: &CameraSettings,
+  camera: &UICamera,
+  filter: &SettingsFilter,
+) -> Vec<(CameraSettings, bool)> {
+  let children = match &section.widget {
+    StaticWidget::Group { children, .. } => children,
+    _ => return vec![],
+  };
+
+  children
+    .values()
+    .filter(|setting| !app_settings.dev_settings.exclude_settings.contains(&setting.name))
+    .map(|setting| match camera.modified_settings.get(&setting.id) {
+      Some((_, modified)) => (modified.to_owned(), true),
+      None => (setting.to_owned(), false),
+    })
+    .filter(|(setting, _)| filter.matches(setting, camera))
+    .collect()
+}
+
 #[inline]
 fn display_settings_section(
   ui: &mut Ui,
-  app_settings: &Settings,
-  section: &CameraSettings,
-  modified_settings: &ModifiedSettingsMap,
-  set_setting: &mut impl FnMut(CameraSettings, i32),
+  section_id: i32,
+  settings: Vec<(CameraSettings, bool)>,
+  camera: &UICamera,
+  on_action: &mut impl FnMut(SettingAction),
 ) {
-  if let StaticWidget::Group { children, .. } = &section.widget {
-    for setting in children.values() {
-      if app_settings.dev_settings.exclude_settings.contains(&setting.name) {
-        continue;
-      }
+  for (mut setting, modified) in settings {
+    let mut changed = false;
+    let mut action = None;
+    let marks = SettingMarks {
+      modified,
+      failure: if modified { camera.apply_failure(setting.id) } else { None },
+      conflict: camera.conflicts.get(&setting.id),
+      highlight: camera.change_highlight(&setting.name),
+    };
 
-      let mut changed = false;
-      let (mut setting, modified) = {
-        match modified_settings.get(&setting.id) {
-          Some((_, setting)) => (setting.to_owned(), true),
-          None => (setting.to_owned(), false),
-        }
-      };
+    if marks.highlight.is_some() {
+      ui.ctx().request_repaint();
+    }
 
-      ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
-        display_setting(ui, &mut setting, &mut changed, modified);
-      });
+    ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
+      display_setting(ui, &mut setting, &mut changed, &marks, &mut action);
+    });
 
-      if changed {
-        set_setting(setting, section.id);
-      }
+    if changed {
+      on_action(SettingAction::Modify { setting, section_id });
+    } else if let Some(action) = action {
+      on_action(action);
     }
   }
 }
 
+/// How a setting is marked in the panel
+struct SettingMarks<'a> {
+  modified: bool,
+  /// Why the last apply did not write the edit
+  failure: Option<&'a SettingOutcome>,
+  /// The setting as changed on the camera while it was edited
+  conflict: Option<&'a CameraSettings>,
+  /// Fading highlight of a change made on the camera
+  highlight: Option<f32>,
+}
+
 #[inline]
-fn display_setting(ui: &mut Ui, setting: &mut CameraSettings, changed: &mut bool, modified: bool) {
+fn display_setting(
+  ui: &mut Ui,
+  setting: &mut CameraSettings,
+  changed: &mut bool,
+  marks: &SettingMarks,
+  action: &mut Option<SettingAction>,
+) {
   let mut group = Frame::group(ui.style());
-  if modified {
+  if marks.modified {
     group.stroke.color = Color32::LIGHT_GRAY;
   }
+  if let Some(highlight) = marks.highlight {
+    group.stroke.color = Color32::GOLD.linear_multiply(highlight);
+    group.fill = Color32::GOLD.linear_multiply(highlight * 0.15);
+  }
+  if marks.failure.is_some() || marks.conflict.is_some() {
+    group.stroke.color = Color32::LIGHT_RED;
+  }
 
   group.show(ui, |ui| {
     ui.label(&setting.label);
 
+    if let Some(failure) = marks.failure {
+      ui.small(RichText::new(outcome_text(failure)).color(Color32::LIGHT_RED));
+    }
+
+    if let Some(conflict) = marks.conflict {
+      ui.small(
+        RichText::new(format!(
+          "⚠ Changed on the camera to {} while it was edited",
+          conflict.widget.value_text().unwrap_or_default()
+        ))
+        .color(Color32::LIGHT_RED),
+      );
+
+      ui.horizontal(|ui| {
+        if ui.small_button("Keep my edit").clicked() {
+          *action = Some(SettingAction::KeepEdit(setting.id));
+        }
+        if ui.small_button("Use camera value").clicked() {
+          *action = Some(SettingAction::UseCameraValue(setting.id));
+        }
+      });
+    }
+
     ui.separator();
 
     ui.add_enabled_ui(!setting.readonly, |ui| match &mut setting.widget {
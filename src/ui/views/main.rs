@@ -16,6 +16,12 @@ pub fn show(ctx: &Context, state: &mut AppState) {
       camera::settings::show(ctx, state);
     }
 
+    if state.panes.settings_history {
+      camera::history::show(ctx, state);
+    }
+
+    camera::history::handle_shortcuts(ctx, state);
+
     camera::central_view::show(ctx, state);
   } else {
     CentralPanel::default().show(ctx, |ui| {
@@ -0,0 +1,96 @@
+use crate::{error::CatchAppResult, ui::state::AppState};
+use eframe::{
+  egui::{Button, Context, Grid, RichText, ScrollArea, Window},
+  emath::Align2,
+  epaint::Color32,
+};
+use gcam_lib::camera::presets::PresetStatus;
+
+/// Compares the loaded preset with the camera and applies it
+pub fn show(ctx: &Context, state: &mut AppState) {
+  let loaded = match &state.presets.loaded {
+    Some(loaded) => loaded,
+    None => return,
+  };
+  let can_apply = matches!(&state.camera, Some(camera) if camera.connected && !camera.block_config);
+  let mut open = true;
+  let mut apply = false;
+  let mut cancel = false;
+
+  Window::new(format!("Preset {}", loaded.preset.name))
+    .collapsible(false)
+    .anchor(Align2::CENTER_CENTER, (0., 0.))
+    .open(&mut open)
+    .show(ctx, |ui| {
+      if loaded.model_mismatch() {
+        ui.label(
+          RichText::new(format!(
+            "⚠ This preset was saved on a {}, the camera is a {}",
+            loaded.preset.model, loaded.camera_model
+          ))
+          .color(Color32::YELLOW),
+        );
+      }
+
+      let skipped = loaded.count(|status| {
+        matches!(status, PresetStatus::Missing | PresetStatus::ReadOnly | PresetStatus::Invalid(_))
+      });
+      if skipped > 0 {
+        ui.label(
+          RichText::new(format!("⚠ {} settings can not be applied to this camera", skipped))
+            .color(Color32::YELLOW),
+        );
+      }
+
+      let changed = loaded.count(|status| *status == PresetStatus::Changed);
+      ui.label(format!(
+        "{} settings change, {} already match",
+        changed,
+        loaded.count(|status| *status == PresetStatus::Unchanged)
+      ));
+      ui.separator();
+
+      ScrollArea::vertical().max_height(400.).show(ui, |ui| {
+        Grid::new("preset_diff").num_columns(3).striped(true).show(ui, |ui| {
+          ui.strong("Setting");
+          ui.strong("Camera");
+          ui.strong("Preset");
+          ui.end_row();
+
+          for entry in &loaded.entries {
+            let (color, note) = match &entry.status {
+              PresetStatus::Unchanged => continue,
+              PresetStatus::Changed => (Color32::LIGHT_GREEN, None),
+              PresetStatus::Missing => (Color32::YELLOW, Some("The camera has no such setting")),
+              PresetStatus::ReadOnly => (Color32::YELLOW, Some("Read only on this camera")),
+              PresetStatus::Invalid(reason) => (Color32::YELLOW, Some(reason.as_str())),
+            };
+
+            ui.label(&entry.label).on_hover_text(match note {
+              Some(note) => format!("{}: {}", entry.name, note),
+              None => entry.name.clone(),
+            });
+            ui.label(entry.current.as_deref().unwrap_or("-"));
+            ui.label(RichText::new(&entry.value).color(color));
+            ui.end_row();
+          }
+        });
+      });
+
+      ui.separator();
+      ui.horizontal(|ui| {
+        if ui.add_enabled(can_apply && changed > 0, Button::new("Apply")).clicked() {
+          apply = true;
+        }
+        if ui.button("Cancel").clicked() {
+          cancel = true;
+        }
+      });
+    });
+
+  if apply {
+    let _ = state.apply_preset().catch(state);
+  } else if cancel || !open {
+    state.presets.loaded = None;
+  }
+}
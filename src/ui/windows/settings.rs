@@ -1,21 +1,94 @@
-use crate::ui::state::AppState;
+use crate::{
+  error::{CatchAppResult, ToUIError},
+  ui::state::AppState,
+};
 use eframe::{
-  egui::{Context, Layout, Window},
+  egui::{Context, DragValue, Grid, Layout, Window},
   emath::{Align, Align2},
 };
 
+const FILENAME_TEMPLATE_HELP: &str = "Available placeholders: {date}, {time}, {model}, {seq}, \
+                                      {seq:04} (padded), {name} and {ext}";
+
 pub fn show(ctx: &Context, state: &mut AppState) {
-  // TODO
+  let mut save = false;
+
   Window::new("Settings")
     .collapsible(false)
     .anchor(Align2::CENTER_CENTER, (0., 0.))
     .resizable(false)
     .open(&mut state.open_dialogs.settings)
     .show(ctx, |ui| {
-      ui.with_layout(Layout::left_to_right(Align::Max).with_cross_justify(true), |ui| {
-        ui.group(|ui| {
-          ui.label("TODO");
-        });
-      })
+      let capture = &mut state.settings.capture;
+
+      ui.heading("Capture");
+      ui.separator();
+
+      Grid::new("capture_settings_grid").num_columns(2).striped(true).show(ui, |ui| {
+        ui.label("Download directory");
+        let mut directory = capture.target_directory.display().to_string();
+        if ui.text_edit_singleline(&mut directory).changed() {
+          capture.target_directory = directory.into();
+        }
+        ui.end_row();
+
+        ui.label("File name").on_hover_text(FILENAME_TEMPLATE_HELP);
+        ui.text_edit_singleline(&mut capture.filename_template)
+          .on_hover_text(FILENAME_TEMPLATE_HELP);
+        ui.end_row();
+
+        ui.label("Next sequence number");
+        ui.add(DragValue::new(&mut capture.next_sequence));
+        ui.end_row();
+
+        ui.label("Keep pictures on camera");
+        ui.checkbox(&mut capture.keep_on_camera, "");
+        ui.end_row();
+
+        ui.label("Download pictures taken on the camera");
+        ui.checkbox(&mut capture.download_camera_shots, "");
+        ui.end_row();
+      });
+
+      ui.add_space(8.);
+      ui.heading("Import");
+      ui.separator();
+
+      let import = &mut state.settings.import;
+      Grid::new("import_settings_grid").num_columns(2).striped(true).show(ui, |ui| {
+        ui.label("Import directory");
+        let mut directory = import.directory.display().to_string();
+        if ui.text_edit_singleline(&mut directory).changed() {
+          import.directory = directory.into();
+        }
+        ui.end_row();
+      });
+
+      ui.add_space(8.);
+      ui.heading("Live view");
+      ui.separator();
+
+      let live_view = &mut state.settings.live_view;
+      Grid::new("live_view_settings_grid").num_columns(2).striped(true).show(ui, |ui| {
+        ui.label("Target frame rate");
+        ui.add(DragValue::new(&mut live_view.target_fps).clamp_range(1..=60).suffix(" fps"));
+        ui.end_row();
+      });
+
+      ui.separator();
+
+      ui.with_layout(Layout::top_down(Align::Center), |ui| {
+        if ui.button("Save").clicked() {
+          save = true;
+        }
+      });
     });
+
+  if save {
+    if let Err(err) = state.settings.save() {
+      state.show_error(err.to_ui_error());
+    }
+
+    let _ = state.update_live_view_fps().catch(state);
+  }
 }